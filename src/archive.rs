@@ -0,0 +1,164 @@
+use anyhow::{Context, Result, anyhow};
+use regex::Regex;
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use walkdir::WalkDir;
+
+use crate::{trash::TRASH_DIR, video::parse_extension};
+
+/// Folder in the source that archives are extracted to for the length of a run
+///
+/// Keeping it in the source puts the extracted files on the same filesystem, so they can be
+/// hard-linked into the library.
+pub const EXTRACT_DIR: &str = ".mediar-extract";
+
+/// Whether a file is a RAR archive or the first volume of a multi-part set
+///
+/// Sets are named `name.part01.rar`, `name.part02.rar`, ... or `name.rar`, `name.r00`, ...
+pub fn is_first_volume(path: &Path) -> bool {
+    let is_rar = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rar"));
+    is_rar && volume(path).is_some_and(|(_, part)| part.is_none_or(|part| part == 1))
+}
+
+/// The name shared by a set's volumes and the volume number, if the name has one
+fn volume(path: &Path) -> Option<(String, Option<u32>)> {
+    let stem = path.file_stem()?.to_str()?;
+    let re = Regex::new(r"(?i)^(.*)\.part(\d+)$").ok()?;
+    Some(match re.captures(stem) {
+        Some(captures) => (captures[1].to_string(), captures[2].parse().ok()),
+        None => (stem.to_string(), None),
+    })
+}
+
+/// Run `unrar` with `args`, which name `archive`, returning what it printed
+fn unrar<I, S>(args: I, archive: &Path) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let output = Command::new("unrar")
+        .args(args)
+        .output()
+        .context("Failed to run unrar; is it installed?")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "unrar failed on {}: {}",
+            archive.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether an archive's listing (`unrar lb`) has a video or subtitle in it
+fn has_media(listing: &str) -> bool {
+    listing
+        .lines()
+        .any(|name| parse_extension(Path::new(name.trim())).is_some())
+}
+
+/// Extracted archives, deleted again when dropped
+#[derive(Debug)]
+pub struct Extracted {
+    dir: PathBuf,
+    pub archives: Vec<PathBuf>,
+}
+
+impl Drop for Extracted {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Extract the RAR archives in `source` that contain media into its [`EXTRACT_DIR`]
+///
+/// Each archive goes into a folder named after it, next to where it was found, so extras
+/// folders and release names still apply to the extracted files.
+pub fn extract_all(source: &Path) -> Result<Extracted> {
+    let dir = source.join(EXTRACT_DIR);
+    let mut extracted = Extracted {
+        dir: dir.clone(),
+        archives: Vec::new(),
+    };
+    let archives: Vec<PathBuf> = WalkDir::new(source)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != EXTRACT_DIR && entry.file_name() != TRASH_DIR)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_first_volume(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect();
+
+    for archive in archives {
+        let name = archive.as_os_str();
+        if !has_media(&unrar([OsStr::new("lb"), name], &archive)?) {
+            continue;
+        }
+        let relative = archive.strip_prefix(source).unwrap_or(&archive);
+        let (set, _) = volume(&archive).context("Archive name is not valid UTF-8")?;
+        let into = dir.join(relative.with_file_name(set));
+        fs::create_dir_all(&into)?;
+        // unrar extracts into a folder given with a trailing separator
+        let mut destination = into.into_os_string();
+        destination.push(std::path::MAIN_SEPARATOR_STR);
+        unrar(
+            [
+                OsStr::new("x"),
+                OsStr::new("-o+"),
+                OsStr::new("-idq"),
+                name,
+                &destination,
+            ],
+            &archive,
+        )?;
+        extracted.archives.push(archive);
+    }
+    Ok(extracted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_first_volume() {
+        let first = |name: &str| is_first_volume(Path::new(name));
+        assert!(first("Show.S01E01.rar"));
+        assert!(first("Show.S01E01.part01.rar"));
+        assert!(first("Show.S01E01.PART1.RAR"));
+        assert!(!first("Show.S01E01.part02.rar"));
+        assert!(!first("Show.S01E01.r00"));
+        assert!(!first("Show.S01E01.mkv"));
+        assert_eq!(
+            volume(Path::new("Heat.1995.part01.rar")),
+            Some(("Heat.1995".to_string(), Some(1)))
+        );
+    }
+
+    #[test]
+    fn test_has_media() {
+        assert!(has_media("Show.S01E01.nfo\nShow.S01E01.mkv\n"));
+        assert!(!has_media("Show.S01E01.nfo\n"));
+    }
+
+    #[test]
+    fn test_extracted_cleanup() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join(EXTRACT_DIR);
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("a").join("b.mkv"), "video").unwrap();
+
+        drop(Extracted {
+            dir: dir.clone(),
+            archives: Vec::new(),
+        });
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{Context, Result};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Where `mediar auth login` put the token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stored {
+    Keychain,
+    File(PathBuf),
+}
+
+/// The OS keychain: the macOS Keychain or the Windows Credential Manager
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+mod keychain {
+    use anyhow::Result;
+    use keyring::{Entry, Error};
+
+    fn entry() -> keyring::Result<Entry> {
+        Entry::new("mediar", "tmdb")
+    }
+
+    pub fn get() -> Option<String> {
+        entry().ok()?.get_password().ok()
+    }
+
+    pub fn set(token: &str) -> Result<()> {
+        Ok(entry()?.set_password(token)?)
+    }
+
+    pub fn delete() -> Result<bool> {
+        match entry()?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(Error::NoEntry) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Other platforms have no keychain mediar can use, so tokens go to the token file
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod keychain {
+    use anyhow::{Result, anyhow};
+
+    pub fn get() -> Option<String> {
+        None
+    }
+
+    pub fn set(_token: &str) -> Result<()> {
+        Err(anyhow!("No supported keychain on this platform"))
+    }
+
+    pub fn delete() -> Result<bool> {
+        Ok(false)
+    }
+}
+
+/// The file a token is stored in when it is not in the keychain
+pub fn token_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("mediar").join("tmdb-token"))
+}
+
+fn read_token(path: &Path) -> Option<String> {
+    let token = fs::read_to_string(path).ok()?;
+    Some(token.trim().to_string()).filter(|token| !token.is_empty())
+}
+
+/// Write a token readable only by the current user
+fn write_token(path: &Path, token: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // The mode only applies to new files; tighten one left by an older login too
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    writeln!(file, "{}", token)?;
+    Ok(())
+}
+
+/// The TMDB token: from the keychain, then the token file, then `TMDB_API_TOKEN`
+pub fn token() -> Option<String> {
+    keychain::get()
+        .or_else(|| token_path().and_then(|path| read_token(&path)))
+        .or_else(|| std::env::var("TMDB_API_TOKEN").ok())
+}
+
+/// Store a token in the keychain, or in the token file if there is none or `to_file` is set
+pub fn store(token: &str, to_file: bool) -> Result<Stored> {
+    if !to_file && keychain::set(token).is_ok() {
+        return Ok(Stored::Keychain);
+    }
+    let path = token_path().context("Failed to find a config directory for the token")?;
+    write_token(&path, token)?;
+    Ok(Stored::File(path))
+}
+
+/// Remove a stored token from the keychain and the token file, returning whether there was one
+pub fn forget() -> Result<bool> {
+    let mut removed = keychain::delete()?;
+    if let Some(path) = token_path().filter(|path| path.exists()) {
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        removed = true;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_token_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("mediar").join("tmdb-token");
+        assert_eq!(read_token(&path), None);
+
+        write_token(&path, "first").unwrap();
+        write_token(&path, "eyJhbGciOi").unwrap();
+        assert_eq!(read_token(&path).as_deref(), Some("eyJhbGciOi"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::write(&path, "\n").unwrap();
+        assert_eq!(read_token(&path), None);
+    }
+}
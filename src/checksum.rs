@@ -0,0 +1,249 @@
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::trash::TRASH_DIR;
+
+/// Name of the manifest written in `sfv` format
+const SFV_MANIFEST: &str = "checksums.sfv";
+
+/// How checksums are recorded next to organized files
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumFormat {
+    /// `SHA256SUMS`, as written by `sha256sum` and checked with `sha256sum -c`
+    #[default]
+    Sha256,
+    /// `checksums.sfv` with CRC32 checksums, as read by QuickSFV and similar tools
+    Sfv,
+}
+
+/// Whether a checksum verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    /// The file's contents changed since its checksum was recorded
+    Mismatch,
+    /// The manifest lists a file that is gone
+    Missing,
+}
+
+/// Feed a file's contents to `update` in chunks, so large videos are never read into memory
+fn read_chunks(path: &Path, mut update: impl FnMut(&[u8])) -> Result<()> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        update(&buffer[..read]);
+    }
+}
+
+impl ChecksumFormat {
+    /// The manifest's file name in each directory
+    pub fn manifest(self) -> &'static str {
+        match self {
+            Self::Sha256 => "SHA256SUMS",
+            Self::Sfv => SFV_MANIFEST,
+        }
+    }
+
+    /// The format of a manifest named `name`, if it is one
+    pub fn from_manifest(name: &str) -> Option<Self> {
+        [Self::Sha256, Self::Sfv]
+            .into_iter()
+            .find(|format| format.manifest() == name)
+    }
+
+    /// A file's checksum as the manifest writes it
+    fn digest(self, path: &Path) -> Result<String> {
+        Ok(match self {
+            Self::Sha256 => {
+                let mut hasher = Sha256::new();
+                read_chunks(path, |chunk| hasher.update(chunk))?;
+                hasher
+                    .finalize()
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect()
+            }
+            Self::Sfv => {
+                let mut hasher = crc32fast::Hasher::new();
+                read_chunks(path, |chunk| hasher.update(chunk))?;
+                format!("{:08X}", hasher.finalize())
+            }
+        })
+    }
+
+    /// File names and checksums in a manifest
+    fn parse(self, manifest: &str) -> Result<BTreeMap<String, String>> {
+        manifest
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with(';'))
+            .map(|line| {
+                let entry = match self {
+                    // `*` marks a checksum taken in binary mode
+                    Self::Sha256 => line
+                        .split_once(' ')
+                        .map(|(digest, name)| (name.trim_start_matches([' ', '*']), digest)),
+                    Self::Sfv => line.rsplit_once(' '),
+                };
+                let (name, digest) =
+                    entry.with_context(|| format!("Unexpected manifest line: {}", line))?;
+                Ok((name.to_string(), digest.to_string()))
+            })
+            .collect()
+    }
+
+    fn render(self, entries: &BTreeMap<String, String>) -> String {
+        entries
+            .iter()
+            .map(|(name, digest)| match self {
+                Self::Sha256 => format!("{}  {}\n", digest, name),
+                Self::Sfv => format!("{} {}\n", name, digest),
+            })
+            .collect()
+    }
+}
+
+/// Record the checksums of `files` in the manifest of each directory they are in
+///
+/// Entries for other files already in a manifest are kept, so runs into the same folder add
+/// to it. Returns the manifests written.
+pub fn write(files: &[PathBuf], format: ChecksumFormat) -> Result<Vec<PathBuf>> {
+    let mut by_dir: BTreeMap<&Path, Vec<&Path>> = BTreeMap::new();
+    for file in files.iter().filter(|file| file.is_file()) {
+        if let Some(dir) = file.parent() {
+            by_dir.entry(dir).or_default().push(file);
+        }
+    }
+
+    let mut written = Vec::new();
+    for (dir, files) in by_dir {
+        let manifest = dir.join(format.manifest());
+        let mut entries = match fs::read_to_string(&manifest) {
+            Ok(contents) => format.parse(&contents)?,
+            Err(_) => BTreeMap::new(),
+        };
+        for file in files {
+            let name = file
+                .file_name()
+                .and_then(|name| name.to_str())
+                .with_context(|| format!("{} is not valid UTF-8", file.display()))?;
+            entries.insert(name.to_string(), format.digest(file)?);
+        }
+        fs::write(&manifest, format.render(&entries))
+            .with_context(|| format!("Failed to write {}", manifest.display()))?;
+        written.push(manifest);
+    }
+    Ok(written)
+}
+
+/// Check every file listed in the manifests under `library` against its recorded checksum
+pub fn verify(library: &Path) -> Result<Vec<(PathBuf, Outcome)>> {
+    if !library.is_dir() {
+        return Err(anyhow!("{} is not a directory", library.display()));
+    }
+    let mut results = Vec::new();
+    for entry in WalkDir::new(library)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != TRASH_DIR)
+    {
+        let entry = entry?;
+        let Some(format) = entry
+            .file_name()
+            .to_str()
+            .and_then(ChecksumFormat::from_manifest)
+        else {
+            continue;
+        };
+        let dir = entry.path().parent().unwrap_or(library);
+        let contents = fs::read_to_string(entry.path())
+            .with_context(|| format!("Failed to read {}", entry.path().display()))?;
+        for (name, digest) in format.parse(&contents)? {
+            let file = dir.join(&name);
+            let outcome = if !file.is_file() {
+                Outcome::Missing
+            } else if format.digest(&file)?.eq_ignore_ascii_case(&digest) {
+                Outcome::Ok
+            } else {
+                Outcome::Mismatch
+            };
+            results.push((file, outcome));
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_digest() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("a.mkv");
+        fs::write(&file, "hello").unwrap();
+        assert_eq!(
+            ChecksumFormat::Sha256.digest(&file).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert_eq!(ChecksumFormat::Sfv.digest(&file).unwrap(), "3610A686");
+    }
+
+    #[test]
+    fn test_parse() {
+        let sums = ChecksumFormat::Sha256
+            .parse("abc  Heat (1995).mkv\ndef *Heat (1995).en.srt\n")
+            .unwrap();
+        assert_eq!(sums["Heat (1995).mkv"], "abc");
+        assert_eq!(sums["Heat (1995).en.srt"], "def");
+
+        let sfv = ChecksumFormat::Sfv
+            .parse("; made by mediar\nHeat (1995).mkv 3610A686\n")
+            .unwrap();
+        assert_eq!(sfv["Heat (1995).mkv"], "3610A686");
+        assert!(ChecksumFormat::Sha256.parse("garbage").is_err());
+    }
+
+    #[test]
+    fn test_write_and_verify() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("Heat (1995)");
+        fs::create_dir_all(&dir).unwrap();
+        let (video, subtitle) = (dir.join("Heat (1995).mkv"), dir.join("Heat (1995).srt"));
+        fs::write(&video, "video").unwrap();
+        fs::write(&subtitle, "subtitle").unwrap();
+
+        let format = ChecksumFormat::Sha256;
+        write(std::slice::from_ref(&video), format).unwrap();
+        let written = write(std::slice::from_ref(&subtitle), format).unwrap();
+        assert_eq!(written, vec![dir.join("SHA256SUMS")]);
+        assert!(
+            verify(temp_dir.path())
+                .unwrap()
+                .iter()
+                .all(|(_, outcome)| *outcome == Outcome::Ok)
+        );
+
+        fs::write(&video, "rotten").unwrap();
+        fs::remove_file(&subtitle).unwrap();
+        assert_eq!(
+            verify(temp_dir.path()).unwrap(),
+            vec![(video, Outcome::Mismatch), (subtitle, Outcome::Missing)]
+        );
+    }
+}
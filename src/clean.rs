@@ -0,0 +1,397 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDate};
+use regex::Regex;
+use std::{
+    fmt,
+    fs::{self, Metadata},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    checksum::ChecksumFormat,
+    config::Config,
+    journal::{JOURNAL_FILE, REPLACED_SUFFIX},
+    scan::parse_duration,
+    transfer::PARTIAL_SUFFIX,
+    trash::{self, TRASH_DIR},
+    video::{is_sample, parse_extension},
+};
+
+/// Default time files left behind by runs are kept
+const DEFAULT_RETENTION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Extensions that never belong in a media library
+const JUNK_EXTENSIONS: &[&str] = &["nfo", "exe", "url", "lnk", "sfv"];
+
+/// Image extensions considered screenshots when found in a screenshot folder or name
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp"];
+
+/// Why a file was identified as release junk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JunkReason {
+    /// A text file advertising the release group or site, e.g. `RARBG.txt`
+    ReleaseNotice,
+    /// An `.nfo`, `.exe`, `.url` or similar file
+    Extension,
+    /// A preview image, e.g. `Screens/screen1.jpg`
+    Screenshot,
+    /// A release sample clip
+    Sample,
+    /// A source staged by `move --trash` longer ago than `trash_retention_days`
+    ExpiredTrash,
+    /// A `.mediar-replaced` file kept past `retention.backup`
+    StaleBackup,
+    /// A `.mediar-partial` copy untouched for longer than `retention.partial`
+    StalePartial,
+    /// A run's journal untouched for longer than `retention.journal`
+    StaleJournal,
+}
+
+impl fmt::Display for JunkReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JunkReason::ReleaseNotice => write!(f, "release notice"),
+            JunkReason::Extension => write!(f, "release file"),
+            JunkReason::Screenshot => write!(f, "screenshot"),
+            JunkReason::Sample => write!(f, "sample clip"),
+            JunkReason::ExpiredTrash => write!(f, "expired trash"),
+            JunkReason::StaleBackup => write!(f, "old replaced file"),
+            JunkReason::StalePartial => write!(f, "unfinished copy"),
+            JunkReason::StaleJournal => write!(f, "old journal"),
+        }
+    }
+}
+
+/// How long `clean` keeps the files runs leave behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// Days sources staged by `move --trash` are kept
+    pub trash_days: u32,
+    pub backup: Duration,
+    pub partial: Duration,
+    pub journal: Duration,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            trash_days: trash::DEFAULT_RETENTION_DAYS,
+            backup: DEFAULT_RETENTION,
+            partial: DEFAULT_RETENTION,
+            journal: DEFAULT_RETENTION,
+        }
+    }
+}
+
+impl Retention {
+    /// The windows set in `config.toml`, with the defaults for any left out
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let window = |setting: &Option<String>, name| {
+            setting
+                .as_deref()
+                .map(parse_duration)
+                .transpose()
+                .with_context(|| format!("Invalid retention.{}", name))
+                .map(|window| window.unwrap_or(DEFAULT_RETENTION))
+        };
+        Ok(Self {
+            trash_days: config
+                .trash_retention_days
+                .unwrap_or(trash::DEFAULT_RETENTION_DAYS),
+            backup: window(&config.retention.backup, "backup")?,
+            partial: window(&config.retention.partial, "partial")?,
+            journal: window(&config.retention.journal, "journal")?,
+        })
+    }
+}
+
+/// When a file last changed, including being renamed, which keeps its modification time
+fn changed(metadata: &Metadata) -> Result<SystemTime> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let since_epoch = Duration::new(metadata.ctime().max(0) as u64, 0);
+        Ok(SystemTime::UNIX_EPOCH + since_epoch)
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(metadata.modified()?)
+    }
+}
+
+/// Classify a file a run left behind that is past its retention window, or `None` otherwise
+fn leftover_reason(
+    path: &Path,
+    relative: &Path,
+    retention: &Retention,
+    now: SystemTime,
+) -> Result<Option<JunkReason>> {
+    // Trash is dated by the folder it is staged in: `.mediar-trash/<date>/...`
+    let mut components = relative.iter();
+    if components.any(|component| component == TRASH_DIR) {
+        let staged = components
+            .next()
+            .and_then(|date| date.to_str())
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+        let today = DateTime::<Local>::from(now).date_naive();
+        return Ok(staged
+            .filter(|&staged| (today - staged).num_days() > i64::from(retention.trash_days))
+            .map(|_| JunkReason::ExpiredTrash));
+    }
+
+    let name = relative.file_name().and_then(|name| name.to_str());
+    let (reason, window) = match name {
+        Some(name) if name.ends_with(REPLACED_SUFFIX) => {
+            (JunkReason::StaleBackup, retention.backup)
+        }
+        Some(name) if name.ends_with(PARTIAL_SUFFIX) => {
+            (JunkReason::StalePartial, retention.partial)
+        }
+        Some(JOURNAL_FILE) => (JunkReason::StaleJournal, retention.journal),
+        _ => return Ok(None),
+    };
+    let age = now
+        .duration_since(changed(&fs::metadata(path)?)?)
+        .unwrap_or_default();
+    Ok((age > window).then_some(reason))
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+}
+
+/// Classify a path (relative to the cleaned directory), or `None` if it should be kept
+pub fn junk_reason(relative: &Path) -> Option<JunkReason> {
+    // Release `.sfv` files are junk, but not the manifests `--write-checksums` keeps
+    if ChecksumFormat::from_manifest(relative.file_name()?.to_str()?).is_some() {
+        return None;
+    }
+
+    let ext = extension(relative)?;
+    let stem = relative.file_stem()?.to_str()?;
+
+    if JUNK_EXTENSIONS.contains(&ext.as_str()) {
+        return Some(JunkReason::Extension);
+    }
+
+    let notice = Regex::new(r"(?i)rarbg|yts|yify|etrg|torrent|www\.").ok()?;
+    if ext == "txt" && notice.is_match(stem) {
+        return Some(JunkReason::ReleaseNotice);
+    }
+
+    if IMAGE_EXTENSIONS.contains(&ext.as_str()) {
+        let in_screens_folder = relative.parent().is_some_and(|parent| {
+            parent.iter().any(|component| {
+                component.eq_ignore_ascii_case("screens")
+                    || component.eq_ignore_ascii_case("screenshots")
+            })
+        });
+        let screenshot = Regex::new(r"(?i)^screen(shot)?s?[ ._\-]*\d*$").ok()?;
+        if in_screens_folder || screenshot.is_match(stem) || notice.is_match(stem) {
+            return Some(JunkReason::Screenshot);
+        }
+    }
+
+    if parse_extension(relative).is_some() && is_sample(relative) {
+        return Some(JunkReason::Sample);
+    }
+
+    None
+}
+
+/// Every junk file under a directory, sorted, including files left behind by runs that are
+/// older than `now` allows
+pub fn find_junk(
+    dir: &Path,
+    retention: &Retention,
+    now: SystemTime,
+) -> Result<Vec<(PathBuf, JunkReason)>> {
+    let mut junk = Vec::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir)?;
+        let reason = match leftover_reason(entry.path(), relative, retention, now)? {
+            Some(reason) => Some(reason),
+            None => junk_reason(relative),
+        };
+        if let Some(reason) = reason {
+            junk.push((entry.path().to_path_buf(), reason));
+        }
+    }
+    Ok(junk)
+}
+
+/// Delete junk files, then any folders below `dir` they leave empty
+pub fn remove_junk(dir: &Path, junk: &[(PathBuf, JunkReason)]) -> Result<()> {
+    for (path, _) in junk {
+        fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        // Only empty directories are removed, so `Sample/` goes but a show folder stays
+        for parent in path.ancestors().skip(1) {
+            if parent == dir || !parent.starts_with(dir) || fs::remove_dir(parent).is_err() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_junk_reason() {
+        let cases = [
+            ("RARBG.txt", Some(JunkReason::ReleaseNotice)),
+            ("RARBG_DO_NOT_MIRROR.exe", Some(JunkReason::Extension)),
+            ("Movie.2020.1080p.nfo", Some(JunkReason::Extension)),
+            ("Movie.2020.1080p.sfv", Some(JunkReason::Extension)),
+            ("Movie (2020)/checksums.sfv", None),
+            ("Movie (2020)/SHA256SUMS", None),
+            ("Screens/screen0001.jpg", Some(JunkReason::Screenshot)),
+            ("screenshot-2.png", Some(JunkReason::Screenshot)),
+            ("WWW.YTS.MX.jpg", Some(JunkReason::Screenshot)),
+            ("Sample/movie-sample.mkv", Some(JunkReason::Sample)),
+            ("movie.sample.mkv", Some(JunkReason::Sample)),
+            ("Movie.2020.1080p.mkv", None),
+            ("Movie.2020.1080p.en.srt", None),
+            ("poster.jpg", None),
+            ("notes.txt", None),
+            ("Sample/readme", None),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(junk_reason(Path::new(path)), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_find_and_remove_junk() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        for file in [
+            "Movie.2020/Movie.2020.mkv",
+            "Movie.2020/RARBG.txt",
+            "Movie.2020/Sample/sample.mkv",
+            "Movie.2020/Screens/screen1.jpg",
+            "Movie.2020/Screens/poster.jpg",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let junk = find_junk(dir, &Retention::default(), SystemTime::now()).unwrap();
+        assert_eq!(
+            junk,
+            vec![
+                (dir.join("Movie.2020/RARBG.txt"), JunkReason::ReleaseNotice),
+                (dir.join("Movie.2020/Sample/sample.mkv"), JunkReason::Sample),
+                (
+                    dir.join("Movie.2020/Screens/poster.jpg"),
+                    JunkReason::Screenshot
+                ),
+                (
+                    dir.join("Movie.2020/Screens/screen1.jpg"),
+                    JunkReason::Screenshot
+                ),
+            ]
+        );
+
+        remove_junk(dir, &junk).unwrap();
+        assert!(dir.join("Movie.2020/Movie.2020.mkv").exists());
+        assert!(!dir.join("Movie.2020/Sample").exists());
+        assert!(!dir.join("Movie.2020/Screens").exists());
+    }
+
+    #[test]
+    fn test_retention_from_config() {
+        assert_eq!(
+            Retention::from_config(&Config::default()).unwrap(),
+            Retention::default()
+        );
+
+        let config = Config::parse(
+            r#"
+            trash_retention_days = 7
+            [retention]
+            backup = "14d"
+            partial = "12h"
+            "#,
+        )
+        .unwrap();
+        let retention = Retention::from_config(&config).unwrap();
+        assert_eq!(retention.trash_days, 7);
+        assert_eq!(retention.backup, Duration::from_secs(14 * 24 * 60 * 60));
+        assert_eq!(retention.partial, Duration::from_secs(12 * 60 * 60));
+        assert_eq!(retention.journal, DEFAULT_RETENTION);
+
+        let config = Config::parse("[retention]\njournal = \"soon\"").unwrap();
+        assert!(Retention::from_config(&config).is_err());
+        assert!(Config::parse("[retention]\ntrash = \"30d\"").is_err());
+    }
+
+    #[test]
+    fn test_find_leftovers() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let today = Local::now().date_naive();
+        let old_trash = format!(".mediar-trash/{}/e01.mkv", today - chrono::Days::new(10));
+        let new_trash = format!(".mediar-trash/{}/e02.mkv", today);
+        for file in [
+            "Heat (1995)/Heat (1995).mkv",
+            "Heat (1995)/Heat (1995).mkv.mediar-replaced",
+            "Heat (1995)/Heat (1995).mkv.mediar-partial",
+            ".mediar-journal.json",
+            &old_trash,
+            &new_trash,
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let retention = Retention {
+            trash_days: 5,
+            backup: Duration::from_secs(60 * 60),
+            partial: Duration::from_secs(3 * 60 * 60),
+            journal: Duration::from_secs(24 * 60 * 60),
+        };
+
+        let junk = find_junk(dir, &retention, SystemTime::now()).unwrap();
+        assert_eq!(junk, vec![(dir.join(&old_trash), JunkReason::ExpiredTrash)]);
+
+        let later = SystemTime::now() + Duration::from_secs(2 * 60 * 60);
+        let junk = find_junk(dir, &retention, later).unwrap();
+        assert_eq!(
+            junk,
+            vec![
+                (dir.join(&old_trash), JunkReason::ExpiredTrash),
+                (
+                    dir.join("Heat (1995)/Heat (1995).mkv.mediar-replaced"),
+                    JunkReason::StaleBackup
+                ),
+            ]
+        );
+
+        let later = SystemTime::now() + Duration::from_secs(2 * 24 * 60 * 60);
+        let junk = find_junk(dir, &retention, later).unwrap();
+        assert_eq!(junk.len(), 4);
+        assert!(junk.contains(&(dir.join(".mediar-journal.json"), JunkReason::StaleJournal)));
+        assert!(junk.contains(&(
+            dir.join("Heat (1995)/Heat (1995).mkv.mediar-partial"),
+            JunkReason::StalePartial
+        )));
+
+        remove_junk(dir, &junk).unwrap();
+        assert!(dir.join("Heat (1995)/Heat (1995).mkv").exists());
+        assert!(!dir.join(&old_trash).parent().unwrap().exists());
+        assert!(dir.join(&new_trash).exists());
+    }
+}
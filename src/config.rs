@@ -0,0 +1,294 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{
+    naming::IdTag,
+    notify::NotifyConfig,
+    tmdb::EpisodeOrder,
+    video::{ContentType, Resolution},
+};
+
+/// User configuration loaded from `config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Language for TMDB metadata (e.g., en-US, de, ja-JP)
+    pub metadata_language: Option<String>,
+    /// Provider ID tag appended to show and movie folder names
+    pub id_tag: Option<IdTag>,
+    /// Rhai script defining `fn rename(path, media)` to rewrite destination paths
+    pub naming_script: Option<PathBuf>,
+    /// Days to keep sources staged by `move --trash` (default: 30)
+    pub trash_retention_days: Option<u32>,
+    /// Minimum confidence for `--auto` to pick a search result without prompting
+    pub auto_confidence: Option<f64>,
+    /// Library root for TV shows when no target is given on the command line
+    pub tv_target: Option<PathBuf>,
+    /// Library root for movies when no target is given on the command line
+    pub movie_target: Option<PathBuf>,
+    /// SQLite database recording organized files, so later runs skip them
+    pub state_db: Option<PathBuf>,
+    /// Where `mediar personal` puts each file, e.g. `{year}/{year}-{month}-{folder}/{name}`
+    pub date_template: Option<String>,
+    /// Shell commands run after each successful organize, before any `--post-hook`
+    pub post_hooks: Vec<String>,
+    /// Shell commands run for each completed operation, before any `--file-hook`
+    pub file_hooks: Vec<String>,
+    /// `[[notify]]` services told about each run's outcome
+    pub notify: Vec<NotifyConfig>,
+    /// How to reach TMDB
+    pub tmdb: TmdbConfig,
+    /// How long `mediar clean` keeps files left behind by runs
+    pub retention: RetentionConfig,
+    /// Per-show settings keyed by TMDB show name
+    #[serde(rename = "show")]
+    pub shows: HashMap<String, ShowConfig>,
+    /// Ordered `[[rule]]` entries; the first rule whose conditions all match applies
+    #[serde(rename = "rule")]
+    pub rules: Vec<RuleConfig>,
+}
+
+/// Connection settings for TMDB, under `[tmdb]`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TmdbConfig {
+    /// API root to use instead of TMDB's, e.g. a caching mirror
+    pub base_url: Option<String>,
+    /// HTTP(S) proxy for all TMDB requests, e.g. `http://proxy.example.com:3128`
+    pub proxy: Option<String>,
+    /// How long a request may take, e.g. `30s`
+    pub timeout: Option<String>,
+}
+
+/// How long `mediar clean` keeps files left behind by runs, under `[retention]`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    /// Existing files set aside as `.mediar-replaced` by upgrades, e.g. `30d`
+    pub backup: Option<String>,
+    /// Unfinished `.mediar-partial` copies
+    pub partial: Option<String>,
+    /// Journals of runs that did not finish
+    pub journal: Option<String>,
+}
+
+/// Settings applied whenever a specific show is organized
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShowConfig {
+    /// Target directory used when none is given on the command line
+    pub target: Option<PathBuf>,
+    /// Renumbering for seasons that releases number differently than TMDB
+    pub season_map: Vec<SeasonMap>,
+    /// Episode numbering the show's releases follow, unless `--order` is given
+    pub order: Option<EpisodeOrder>,
+}
+
+/// Maps the episodes of one release season onto TMDB's numbering
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeasonMap {
+    /// Season number as parsed from the filenames
+    pub season: i32,
+    /// TMDB season the episodes belong to (default: the same season)
+    pub to_season: Option<i32>,
+    /// Added to the parsed episode number, e.g. 13 when a release restarts at 1 mid-season
+    #[serde(default)]
+    pub episode_offset: i32,
+}
+
+/// A renaming rule: conditions on the parsed file and the actions to take when they match
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleConfig {
+    /// Match only shows or only movies
+    pub content: Option<ContentType>,
+    /// Regex matched against the TMDB show or movie title
+    pub title: Option<String>,
+    /// Glob matched against the source path relative to the source directory
+    pub path: Option<String>,
+    /// File extensions to match, e.g. `["mkv", "mp4"]`
+    pub extension: Vec<String>,
+    /// Resolution to match (`480p`, `720p`, `1080p` or `2160p`), from the file name or ffprobe
+    pub resolution: Option<Resolution>,
+    /// Organize matching files under this target root instead
+    pub target: Option<PathBuf>,
+    /// Movie edition to use instead of the one parsed from the filename
+    pub edition: Option<String>,
+    /// Leave matching files alone
+    pub skip: bool,
+}
+
+impl Config {
+    /// Location of the config file, overridable with `MEDIAR_CONFIG`
+    pub fn path() -> Option<PathBuf> {
+        std::env::var_os("MEDIAR_CONFIG")
+            .map(PathBuf::from)
+            .or_else(|| dirs::config_dir().map(|dir| dir.join("mediar").join("config.toml")))
+    }
+
+    /// Load the config file, falling back to defaults if it does not exist
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Failed to parse config {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Look up the settings for a show by name, ignoring case
+    pub fn show(&self, name: &str) -> Option<&ShowConfig> {
+        self.shows
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, show)| show)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_empty() {
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.metadata_language, None);
+    }
+
+    #[test]
+    fn test_parse_metadata_language() {
+        let config = Config::parse(r#"metadata_language = "de-DE""#).unwrap();
+        assert_eq!(config.metadata_language.as_deref(), Some("de-DE"));
+    }
+
+    #[test]
+    fn test_parse_show_sections() {
+        let config = Config::parse(
+            r#"
+            [show."One Piece"]
+            target = "/mnt/anime"
+            "#,
+        )
+        .unwrap();
+
+        let show = config.show("one piece").unwrap();
+        assert_eq!(show.target.as_deref(), Some(Path::new("/mnt/anime")));
+        assert!(config.show("Naruto").is_none());
+    }
+
+    #[test]
+    fn test_parse_content_targets() {
+        let config = Config::parse(
+            r#"
+            tv_target = "/media/tv"
+            movie_target = "/media/movies"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.tv_target.as_deref(), Some(Path::new("/media/tv")));
+        assert_eq!(
+            config.movie_target.as_deref(),
+            Some(Path::new("/media/movies"))
+        );
+    }
+
+    #[test]
+    fn test_parse_season_map() {
+        let config = Config::parse(
+            r#"
+            [show."Futurama"]
+            season_map = [{ season = 6, to_season = 5, episode_offset = 13 }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.show("Futurama").unwrap().season_map,
+            [SeasonMap {
+                season: 6,
+                to_season: Some(5),
+                episode_offset: 13
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_show_order() {
+        let config = Config::parse(
+            r#"
+            [show."Firefly"]
+            order = "dvd"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.show("Firefly").unwrap().order,
+            Some(EpisodeOrder::Dvd)
+        );
+        assert!(Config::parse("[show.\"Firefly\"]\norder = \"broadcast\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let config = Config::parse(
+            r#"
+            [[rule]]
+            content = "show"
+            path = "*2160p*"
+            target = "/mnt/anime4k"
+
+            [[rule]]
+            extension = ["avi"]
+            skip = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].content, Some(ContentType::Show));
+        assert_eq!(
+            config.rules[0].target.as_deref(),
+            Some(Path::new("/mnt/anime4k"))
+        );
+        assert!(config.rules[1].skip);
+    }
+
+    #[test]
+    fn test_parse_id_tag() {
+        let config = Config::parse(r#"id_tag = "jellyfin""#).unwrap();
+        assert_eq!(config.id_tag, Some(IdTag::Jellyfin));
+        assert!(Config::parse(r#"id_tag = "kodi""#).is_err());
+    }
+
+    #[test]
+    fn test_parse_tmdb() {
+        let config = Config::parse(
+            r#"
+            [tmdb]
+            base_url = "http://tmdb-cache.lan/3"
+            proxy = "http://proxy.lan:3128"
+            timeout = "30s"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.tmdb.base_url.as_deref(),
+            Some("http://tmdb-cache.lan/3")
+        );
+        assert_eq!(config.tmdb.proxy.as_deref(), Some("http://proxy.lan:3128"));
+        assert_eq!(config.tmdb.timeout.as_deref(), Some("30s"));
+        assert!(Config::parse("[tmdb]\nretries = 3").is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_key_fails() {
+        assert!(Config::parse(r#"unknown = 1"#).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    process::ExitCode,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::tmdb::TmdbError;
+
+/// The most significant outcome recorded so far, by [`Status::rank`]
+static OUTCOME: AtomicU8 = AtomicU8::new(0);
+
+/// How a run ended, as reported to scripts through the exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// Everything was done (0)
+    Done,
+    /// There were no files to process (2)
+    NothingToDo,
+    /// Some operations failed under `--keep-going` (3)
+    PartialFailure,
+    /// The user declined the confirmation prompt (4)
+    Cancelled,
+}
+
+/// Exit code of a fatal error
+pub const FATAL: u8 = 1;
+
+/// Exit code of a fatal error, or a more specific one for TMDB errors a script can act on:
+/// 5 for an unknown ID, 6 for rejected credentials and 7 for rate limiting
+fn fatal_code(error: &anyhow::Error) -> u8 {
+    match error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<TmdbError>())
+    {
+        Some(TmdbError::NotFound { .. }) => 5,
+        Some(TmdbError::Unauthorized { .. }) => 6,
+        Some(TmdbError::RateLimited { .. }) => 7,
+        _ => FATAL,
+    }
+}
+
+impl Status {
+    /// The status as reported by `mediar serve`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Done => "done",
+            Self::NothingToDo => "nothing_to_do",
+            Self::PartialFailure => "partial_failure",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            Self::Done => 0,
+            Self::NothingToDo => 2,
+            Self::PartialFailure => 3,
+            Self::Cancelled => 4,
+        }
+    }
+
+    /// When a run organizes several things, the outcome that wins: any work done beats
+    /// nothing to do, and cancellations and failures beat both
+    fn rank(self) -> u8 {
+        match self {
+            Self::NothingToDo => 1,
+            Self::Done => 2,
+            Self::Cancelled => 3,
+            Self::PartialFailure => 4,
+        }
+    }
+
+    fn from_rank(rank: u8) -> Self {
+        match rank {
+            1 => Self::NothingToDo,
+            3 => Self::Cancelled,
+            4 => Self::PartialFailure,
+            _ => Self::Done,
+        }
+    }
+}
+
+/// Record an outcome of this run
+pub fn record(status: Status) {
+    OUTCOME.fetch_max(status.rank(), Ordering::Relaxed);
+}
+
+/// Forget the outcomes recorded so far, before `mediar serve` handles another request
+pub fn reset() {
+    OUTCOME.store(0, Ordering::Relaxed);
+}
+
+/// The outcome of this run so far; `Done` if nothing was recorded
+pub fn current() -> Status {
+    Status::from_rank(OUTCOME.load(Ordering::Relaxed))
+}
+
+/// The exit code for a run that ended with `error`, or without one
+pub fn code(error: Option<&anyhow::Error>) -> ExitCode {
+    match (current(), error) {
+        (Status::PartialFailure, _) => ExitCode::from(Status::PartialFailure.code()),
+        (_, Some(error)) => ExitCode::from(fatal_code(error)),
+        (status, None) => ExitCode::from(status.code()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_order() {
+        let combine = |statuses: &[Status]| {
+            Status::from_rank(
+                statuses
+                    .iter()
+                    .map(|status| status.rank())
+                    .max()
+                    .unwrap_or(0),
+            )
+        };
+        assert_eq!(combine(&[]), Status::Done);
+        assert_eq!(combine(&[Status::NothingToDo]), Status::NothingToDo);
+        assert_eq!(combine(&[Status::NothingToDo, Status::Done]), Status::Done);
+        assert_eq!(
+            combine(&[Status::Done, Status::Cancelled]),
+            Status::Cancelled
+        );
+        assert_eq!(
+            combine(&[Status::Cancelled, Status::PartialFailure, Status::Done]),
+            Status::PartialFailure
+        );
+    }
+
+    #[test]
+    fn test_fatal_code() {
+        assert_eq!(fatal_code(&anyhow::anyhow!("disk full")), FATAL);
+        let unauthorized = anyhow::Error::new(TmdbError::Unauthorized {
+            message: "Invalid API key".to_string(),
+        });
+        assert_eq!(fatal_code(&unauthorized), 6);
+        assert_eq!(
+            fatal_code(&unauthorized.context("Failed to fetch show 1396")),
+            6
+        );
+    }
+}
@@ -0,0 +1,379 @@
+use anyhow::{Result, anyhow};
+use clap::ValueEnum;
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::plan::{Plan, SkipReason};
+
+/// Longest file or folder name most filesystems accept
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Longest path the classic Windows APIs accept without a `\\?\` prefix (MAX_PATH minus the NUL)
+const WINDOWS_MAX_PATH: usize = 259;
+
+/// Largest file FAT32 can store (4 GiB - 1)
+const FAT_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024 - 1;
+
+/// The kind of filesystem a library is written to, which limits the names it accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TargetFs {
+    /// ext4, XFS, Btrfs, ZFS and other Unix filesystems: 255-byte names
+    Posix,
+    /// Windows NTFS: no reserved device names or trailing dots and spaces, case-insensitive
+    Ntfs,
+    /// exFAT: NTFS rules
+    Exfat,
+    /// FAT32: NTFS rules, and files must be smaller than 4 GiB
+    Fat,
+    /// SMB/CIFS shares: NTFS rules, since the server or its clients are usually Windows
+    Smb,
+}
+
+impl TargetFs {
+    /// Map a Linux mount type (from `/proc/self/mounts`) to a target filesystem
+    pub fn from_mount_type(fstype: &str) -> Option<Self> {
+        match fstype {
+            "ext2" | "ext3" | "ext4" | "xfs" | "btrfs" | "zfs" | "f2fs" | "tmpfs" | "nfs"
+            | "nfs4" => Some(TargetFs::Posix),
+            "ntfs" | "ntfs3" | "fuseblk" => Some(TargetFs::Ntfs),
+            "exfat" => Some(TargetFs::Exfat),
+            "vfat" | "msdos" => Some(TargetFs::Fat),
+            "cifs" | "smb3" | "smbfs" => Some(TargetFs::Smb),
+            _ => None,
+        }
+    }
+
+    /// Detect the filesystem a path (or its nearest existing ancestor) lives on
+    ///
+    /// On Windows, every target gets the NTFS rules since the OS enforces them everywhere.
+    pub fn detect(path: &Path) -> Option<Self> {
+        if cfg!(windows) {
+            return Some(TargetFs::Ntfs);
+        }
+        let mounts = fs::read_to_string("/proc/self/mounts").ok()?;
+        let path = path
+            .ancestors()
+            .find_map(|ancestor| ancestor.canonicalize().ok())?;
+        let fstype = mount_type(&mounts, &path)?;
+        Self::from_mount_type(fstype)
+    }
+
+    fn windows_rules(&self) -> bool {
+        *self != TargetFs::Posix
+    }
+
+    /// Adjust a single file or folder name to what this filesystem accepts, with the reasons
+    pub fn adapt_name(&self, name: &str) -> (String, Vec<&'static str>) {
+        let mut name = name.to_string();
+        let mut reasons = Vec::new();
+
+        if self.windows_rules() {
+            let trimmed = name.trim_end_matches(['.', ' ']);
+            if trimmed.len() != name.len() && !trimmed.is_empty() {
+                name = trimmed.to_string();
+                reasons.push("removed trailing dots or spaces");
+            }
+
+            let reserved = Regex::new(r"(?i)^(con|prn|aux|nul|com[0-9]|lpt[0-9])(\..*)?$").unwrap();
+            if let Some(captures) = reserved.captures(&name) {
+                name = format!(
+                    "{}_{}",
+                    &captures[1],
+                    captures.get(2).map_or("", |ext| ext.as_str())
+                );
+                reasons.push("renamed reserved device name");
+            }
+        }
+
+        if self.name_length(&name) > MAX_NAME_LENGTH {
+            name = self.shorten(&name);
+            reasons.push("shortened to 255 characters");
+        }
+
+        (name, reasons)
+    }
+
+    /// Length of a name as the filesystem counts it (bytes on Unix, UTF-16 units on Windows)
+    fn name_length(&self, name: &str) -> usize {
+        if self.windows_rules() {
+            name.encode_utf16().count()
+        } else {
+            name.len()
+        }
+    }
+
+    /// Shorten a name to the length limit, keeping its extension
+    fn shorten(&self, name: &str) -> String {
+        let (stem, ext) = match name.rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+            _ => (name, String::new()),
+        };
+
+        let mut shortened = String::new();
+        for c in stem.chars() {
+            let candidate = format!("{}{}{}", shortened, c, ext);
+            if self.name_length(&candidate) > MAX_NAME_LENGTH {
+                break;
+            }
+            shortened.push(c);
+        }
+        format!("{}{}", shortened.trim_end(), ext)
+    }
+
+    /// Adjust every name below `target` in a destination path
+    pub fn adapt_path(&self, target: &Path, new: &Path) -> (PathBuf, Vec<&'static str>) {
+        let (base, relative) = match new.strip_prefix(target) {
+            Ok(relative) => (target.to_path_buf(), relative),
+            // Rules may send files elsewhere; only the file name is ours then
+            Err(_) => (
+                new.parent().unwrap_or(Path::new("")).to_path_buf(),
+                Path::new(new.file_name().unwrap_or_default()),
+            ),
+        };
+
+        let mut adapted = base;
+        let mut reasons = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => {
+                    let (name, name_reasons) = self.adapt_name(&name.to_string_lossy());
+                    adapted.push(name);
+                    for reason in name_reasons {
+                        if !reasons.contains(&reason) {
+                            reasons.push(reason);
+                        }
+                    }
+                }
+                other => adapted.push(other),
+            }
+        }
+        (adapted, reasons)
+    }
+}
+
+/// Rewrite an absolute Windows path in the `\\?\` form, which lifts the MAX_PATH limit
+fn verbatim_path(path: &str) -> String {
+    if path.starts_with(r"\\?\") {
+        return path.to_string();
+    }
+    // The verbatim form is passed to the filesystem as-is, so it must use backslashes
+    let path = path.replace('/', r"\");
+    match path.strip_prefix(r"\\") {
+        Some(unc) => format!(r"\\?\UNC\{}", unc),
+        None => format!(r"\\?\{}", path),
+    }
+}
+
+/// On Windows, the `\\?\` form of paths too long for MAX_PATH; other paths are unchanged
+pub fn long_path(path: &Path) -> PathBuf {
+    if !cfg!(windows) || path.as_os_str().len() <= WINDOWS_MAX_PATH {
+        return path.to_path_buf();
+    }
+    match std::path::absolute(path) {
+        Ok(absolute) => PathBuf::from(verbatim_path(&absolute.to_string_lossy())),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// The mount type of the longest mount point containing `path`
+fn mount_type<'a>(mounts: &'a str, path: &Path) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            // Spaces in mount points are escaped as \040
+            let mount_point = fields.next()?.replace("\\040", " ");
+            let fstype = fields.next()?;
+            path.starts_with(&mount_point)
+                .then_some((mount_point.len(), fstype))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fstype)| fstype)
+}
+
+/// A destination renamed to suit the target filesystem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alteration {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub reasons: Vec<&'static str>,
+}
+
+/// Adapt a plan's destinations to the target filesystem, returning what was renamed
+///
+/// Fails if destinations would collide once case is ignored; files FAT cannot store are skipped.
+pub fn preflight(plan: &mut Plan, target: &Path, target_fs: TargetFs) -> Result<Vec<Alteration>> {
+    let mut alterations = Vec::new();
+    let mut operations = Vec::new();
+    for (old, new) in plan.operations.drain(..) {
+        if target_fs == TargetFs::Fat && fs::metadata(&old)?.len() > FAT_MAX_FILE_SIZE {
+            plan.skipped.push((
+                old,
+                SkipReason::Ignored("larger than the 4 GiB FAT file size limit"),
+            ));
+            continue;
+        }
+
+        let (adapted, reasons) = target_fs.adapt_path(target, &new);
+        if adapted == new {
+            operations.push((old, new));
+            continue;
+        }
+        if adapted.exists() {
+            plan.skipped.push((old, SkipReason::Exists(adapted)));
+            continue;
+        }
+        alterations.push(Alteration {
+            from: new,
+            to: adapted.clone(),
+            reasons,
+        });
+        operations.push((old, adapted));
+    }
+    plan.operations = operations;
+
+    if target_fs.windows_rules() {
+        let mut seen: HashMap<String, &Path> = HashMap::new();
+        for (_, new) in &plan.operations {
+            let key = new.to_string_lossy().to_lowercase();
+            if let Some(other) = seen.insert(key, new)
+                && other != new
+            {
+                return Err(anyhow!(
+                    "{} and {} would be the same file on a case-insensitive filesystem",
+                    other.display(),
+                    new.display()
+                ));
+            }
+        }
+    }
+
+    Ok(alterations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_from_mount_type() {
+        assert_eq!(TargetFs::from_mount_type("ext4"), Some(TargetFs::Posix));
+        assert_eq!(TargetFs::from_mount_type("ntfs3"), Some(TargetFs::Ntfs));
+        assert_eq!(TargetFs::from_mount_type("vfat"), Some(TargetFs::Fat));
+        assert_eq!(TargetFs::from_mount_type("cifs"), Some(TargetFs::Smb));
+        assert_eq!(TargetFs::from_mount_type("proc"), None);
+    }
+
+    #[test]
+    fn test_mount_type_longest_prefix() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+                      /dev/sdb1 /mnt/usb\\040drive vfat rw 0 0\n\
+                      //nas/media /mnt/nas cifs rw 0 0\n";
+        assert_eq!(mount_type(mounts, Path::new("/home/me")), Some("ext4"));
+        assert_eq!(
+            mount_type(mounts, Path::new("/mnt/usb drive/Movies")),
+            Some("vfat")
+        );
+        assert_eq!(mount_type(mounts, Path::new("/mnt/nas/TV")), Some("cifs"));
+    }
+
+    #[test]
+    fn test_adapt_name_posix_unchanged() {
+        let (name, reasons) = TargetFs::Posix.adapt_name("Con. ");
+        assert_eq!(name, "Con. ");
+        assert!(reasons.is_empty());
+    }
+
+    #[test]
+    fn test_adapt_name_trailing_dots() {
+        let (name, reasons) = TargetFs::Ntfs.adapt_name("What If...");
+        assert_eq!(name, "What If");
+        assert_eq!(reasons, ["removed trailing dots or spaces"]);
+    }
+
+    #[test]
+    fn test_adapt_name_reserved() {
+        assert_eq!(TargetFs::Smb.adapt_name("CON.mkv").0, "CON_.mkv");
+        assert_eq!(TargetFs::Smb.adapt_name("Con (2019)").0, "Con (2019)");
+    }
+
+    #[test]
+    fn test_adapt_name_length_keeps_extension() {
+        let long = format!("{}.mkv", "é".repeat(200));
+        let (posix, _) = TargetFs::Posix.adapt_name(&long);
+        assert!(posix.len() <= MAX_NAME_LENGTH);
+        assert!(posix.ends_with(".mkv"));
+
+        // 204 UTF-16 units fit on NTFS
+        assert_eq!(TargetFs::Ntfs.adapt_name(&long).0, long);
+    }
+
+    #[test]
+    fn test_adapt_path_only_below_target() {
+        let (path, reasons) = TargetFs::Exfat.adapt_path(
+            Path::new("/mnt/usb./lib"),
+            Path::new("/mnt/usb./lib/Show (2020)/Season 01/Show - S01E01 - Pilot....mkv"),
+        );
+        assert_eq!(
+            path,
+            Path::new("/mnt/usb./lib/Show (2020)/Season 01/Show - S01E01 - Pilot....mkv")
+        );
+        assert!(reasons.is_empty());
+
+        let (path, _) = TargetFs::Exfat.adapt_path(
+            Path::new("/lib"),
+            Path::new("/lib/Who Are You. (2020)./Movie.mkv"),
+        );
+        assert_eq!(path, Path::new("/lib/Who Are You. (2020)/Movie.mkv"));
+    }
+
+    #[test]
+    fn test_verbatim_path() {
+        assert_eq!(
+            verbatim_path(r"C:\Media\Show (2008)/Season 01/Show - S01E01.mkv"),
+            r"\\?\C:\Media\Show (2008)\Season 01\Show - S01E01.mkv"
+        );
+        assert_eq!(
+            verbatim_path(r"\\nas\media\Show (2008)"),
+            r"\\?\UNC\nas\media\Show (2008)"
+        );
+        assert_eq!(verbatim_path(r"\\?\C:\Media"), r"\\?\C:\Media");
+    }
+
+    #[test]
+    fn test_long_path_short_unchanged() {
+        let path = Path::new("/lib/Show (2008)/Season 01/Show - S01E01.mkv");
+        assert_eq!(long_path(path), path);
+    }
+
+    #[test]
+    fn test_preflight_alters_and_detects_collisions() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("lib");
+
+        let mut plan = Plan::default();
+        plan.operations.push((
+            temp_dir.path().join("a.mkv"),
+            target.join("Movie (2020).").join("a.mkv"),
+        ));
+        let alterations = preflight(&mut plan, &target, TargetFs::Ntfs).unwrap();
+        assert_eq!(alterations.len(), 1);
+        assert_eq!(
+            plan.operations[0].1,
+            target.join("Movie (2020)").join("a.mkv")
+        );
+
+        plan.operations.push((
+            temp_dir.path().join("b.mkv"),
+            target.join("movie (2020)/A.mkv"),
+        ));
+        assert!(preflight(&mut plan, &target, TargetFs::Ntfs).is_err());
+        assert!(preflight(&mut plan, &target, TargetFs::Posix).is_ok());
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result, anyhow};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use image::{ImageFormat, imageops::FilterType};
+use std::io::{Cursor, IsTerminal};
+
+/// Width of a poster preview, in terminal columns
+pub const POSTER_COLUMNS: u32 = 20;
+
+/// Pixels assumed per column when an image must be scaled before it is sent (sixel)
+const PIXELS_PER_COLUMN: u32 = 10;
+
+/// Largest payload in one kitty graphics escape, per the protocol
+const KITTY_CHUNK: usize = 4096;
+
+/// A way of drawing images inline in a terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// The kitty graphics protocol, also spoken by Ghostty and Konsole
+    Kitty,
+    /// iTerm2 inline images, also spoken by WezTerm
+    Iterm,
+    /// DEC sixel graphics, e.g. foot, mlterm and xterm started with `-ti vt340`
+    Sixel,
+}
+
+impl Protocol {
+    /// The protocol the terminal understands, judged from the environment it sets
+    ///
+    /// Terminals are not queried, so sixel-only terminals are recognized by name or by a
+    /// `TERM` that mentions sixel.
+    pub fn detect(var: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        if var("KITTY_WINDOW_ID").is_some()
+            || term == "xterm-kitty"
+            || term == "xterm-ghostty"
+            || program == "ghostty"
+        {
+            Some(Self::Kitty)
+        } else if program == "iTerm.app" || program == "WezTerm" {
+            Some(Self::Iterm)
+        } else if term.starts_with("foot") || term.starts_with("mlterm") || term.contains("sixel") {
+            Some(Self::Sixel)
+        } else {
+            None
+        }
+    }
+
+    /// The protocol of the terminal on stdout, or `None` when it cannot show images
+    ///
+    /// `MEDIAR_NO_POSTERS` turns images off for terminals that are detected wrongly.
+    pub fn current() -> Option<Self> {
+        if !std::io::stdout().is_terminal() || std::env::var_os("MEDIAR_NO_POSTERS").is_some() {
+            return None;
+        }
+        Self::detect(|name| std::env::var(name).ok())
+    }
+
+    /// The escape sequence that draws an image (JPEG or PNG) `columns` wide
+    pub fn render(&self, image: &[u8], columns: u32) -> Result<String> {
+        match self {
+            Self::Kitty => Ok(kitty(&to_png(image)?, columns)),
+            Self::Iterm => Ok(format!(
+                "\x1b]1337;File=inline=1;size={};width={};preserveAspectRatio=1:{}\x07",
+                image.len(),
+                columns,
+                STANDARD.encode(image)
+            )),
+            Self::Sixel => sixel(image, columns * PIXELS_PER_COLUMN),
+        }
+    }
+}
+
+fn decode(image: &[u8]) -> Result<image::DynamicImage> {
+    image::load_from_memory(image).context("Failed to decode image")
+}
+
+/// Kitty only takes PNG or raw pixels, not the JPEGs TMDB serves
+fn to_png(image: &[u8]) -> Result<Vec<u8>> {
+    if image::guess_format(image).ok() == Some(ImageFormat::Png) {
+        return Ok(image.to_vec());
+    }
+    let mut png = Vec::new();
+    decode(image)?.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Transmit and place a PNG, split into chunks the terminal accepts
+fn kitty(png: &[u8], columns: u32) -> String {
+    let encoded = STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK).collect();
+    let mut output = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let keys = if i == 0 {
+            format!("a=T,f=100,c={},m={}", columns, more)
+        } else {
+            format!("m={}", more)
+        };
+        output.push_str(&format!(
+            "\x1b_G{};{}\x1b\\",
+            keys,
+            String::from_utf8_lossy(chunk)
+        ));
+    }
+    output
+}
+
+/// Scale an image to `width` pixels and encode it as sixels
+fn sixel(image: &[u8], width: u32) -> Result<String> {
+    let decoded = decode(image)?;
+    let height = (decoded.height() * width / decoded.width().max(1)).max(1);
+    let rgb = decoded
+        .resize_exact(width, height, FilterType::Triangle)
+        .to_rgb8();
+    icy_sixel::sixel_string(
+        rgb.as_raw(),
+        width as i32,
+        height as i32,
+        icy_sixel::PixelFormat::RGB888,
+        icy_sixel::DiffusionMethod::Auto,
+        icy_sixel::MethodForLargest::Auto,
+        icy_sixel::MethodForRep::Auto,
+        icy_sixel::Quality::AUTO,
+    )
+    .map_err(|err| anyhow!("Failed to encode sixel image: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = Vec::new();
+        RgbImage::from_pixel(width, height, Rgb([200, 30, 30]))
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            Protocol::detect(env(&[("TERM", "xterm-kitty")])),
+            Some(Protocol::Kitty)
+        );
+        assert_eq!(
+            Protocol::detect(env(&[("TERM_PROGRAM", "iTerm.app")])),
+            Some(Protocol::Iterm)
+        );
+        assert_eq!(
+            Protocol::detect(env(&[("TERM", "foot")])),
+            Some(Protocol::Sixel)
+        );
+        assert_eq!(Protocol::detect(env(&[("TERM", "xterm-256color")])), None);
+    }
+
+    #[test]
+    fn test_render_kitty_chunks() {
+        let image = png(300, 450);
+        let output = Protocol::Kitty.render(&image, POSTER_COLUMNS).unwrap();
+        assert!(output.starts_with("\x1b_Ga=T,f=100,c=20,m="));
+        assert!(output.ends_with("\x1b\\"));
+        let chunks = output.matches("\x1b_G").count();
+        assert_eq!(chunks, STANDARD.encode(&image).len().div_ceil(KITTY_CHUNK));
+        assert!(output.contains("m=0;"));
+    }
+
+    #[test]
+    fn test_render_iterm_and_sixel() {
+        let image = png(30, 45);
+        let iterm = Protocol::Iterm.render(&image, 5).unwrap();
+        assert!(iterm.starts_with("\x1b]1337;File=inline=1;"));
+        assert!(iterm.contains(&STANDARD.encode(&image)));
+
+        let sixel = Protocol::Sixel.render(&image, 2).unwrap();
+        assert!(sixel.starts_with("\x1bP"));
+        assert!(Protocol::Sixel.render(b"not an image", 2).is_err());
+    }
+}
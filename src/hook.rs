@@ -0,0 +1,185 @@
+use anyhow::{Context, Result, anyhow};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::plan::{Media, Mode};
+
+/// What a run organized, passed to post-run hooks as `MEDIAR_*` environment variables
+#[derive(Debug)]
+pub struct RunSummary<'a> {
+    pub mode: Mode,
+    pub source: &'a Path,
+    pub media: Option<&'a Media>,
+    pub targets: Vec<PathBuf>,
+    pub skipped: usize,
+}
+
+impl RunSummary<'_> {
+    /// The environment a hook runs with
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let lines = |paths: &mut dyn Iterator<Item = &Path>| {
+            paths
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let dirs: BTreeSet<&Path> = self
+            .targets
+            .iter()
+            .filter_map(|target| target.parent())
+            .collect();
+
+        let mut env = vec![
+            (
+                "MEDIAR_MODE",
+                format!("{:?}", self.mode).to_ascii_lowercase(),
+            ),
+            ("MEDIAR_SOURCE", self.source.to_string_lossy().into_owned()),
+            ("MEDIAR_COUNT", self.targets.len().to_string()),
+            ("MEDIAR_SKIPPED", self.skipped.to_string()),
+            (
+                "MEDIAR_TARGETS",
+                lines(&mut self.targets.iter().map(PathBuf::as_path)),
+            ),
+            ("MEDIAR_TARGET_DIRS", lines(&mut dirs.into_iter())),
+        ];
+        if let Some(media) = self.media {
+            env.push(("MEDIAR_TITLE", media.title.clone()));
+            env.push(("MEDIAR_TMDB_ID", media.tmdb_id.to_string()));
+            env.push((
+                "MEDIAR_CONTENT_TYPE",
+                format!("{:?}", media.content).to_ascii_lowercase(),
+            ));
+        }
+        env
+    }
+}
+
+/// A hook command run through the shell
+///
+/// With `sh -c`, arguments after the command are `$1`, `$2`, ...; the `mediar` in between
+/// becomes `$0`. `cmd /C` simply appends them.
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command).arg("mediar");
+        shell
+    }
+}
+
+fn check(command: &str, mut shell: Command) -> Result<()> {
+    let status = shell
+        .status()
+        .with_context(|| format!("Failed to run hook `{}`", command))?;
+    if !status.success() {
+        return Err(anyhow!("Hook `{}` failed with {}", command, status));
+    }
+    Ok(())
+}
+
+/// Run a post-run hook with the run's environment
+pub fn run(command: &str, summary: &RunSummary) -> Result<()> {
+    let mut shell = shell(command);
+    shell.envs(summary.env());
+    check(command, shell)
+}
+
+/// Run a file hook for one completed operation, with its source and target as `$1` and `$2`
+pub fn run_for_file(command: &str, source: &Path, target: &Path) -> Result<()> {
+    let mut shell = shell(command);
+    shell.arg(source).arg(target);
+    check(command, shell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::video::ContentType;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn summary(media: Option<&Media>) -> RunSummary<'_> {
+        RunSummary {
+            mode: Mode::Link,
+            source: Path::new("/downloads/Heat.1995"),
+            media,
+            targets: vec![
+                PathBuf::from("/movies/Heat (1995)/Heat (1995).mkv"),
+                PathBuf::from("/movies/Heat (1995)/Heat (1995).en.srt"),
+            ],
+            skipped: 1,
+        }
+    }
+
+    #[test]
+    fn test_env() {
+        let media = Media {
+            content: ContentType::Movie,
+            tmdb_id: 949,
+            title: "Heat".to_string(),
+        };
+        let env = summary(Some(&media)).env();
+        let get = |name: &str| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(get("MEDIAR_MODE"), Some("link"));
+        assert_eq!(get("MEDIAR_COUNT"), Some("2"));
+        assert_eq!(get("MEDIAR_SKIPPED"), Some("1"));
+        assert_eq!(get("MEDIAR_TARGET_DIRS"), Some("/movies/Heat (1995)"));
+        assert_eq!(
+            get("MEDIAR_TARGETS"),
+            Some("/movies/Heat (1995)/Heat (1995).mkv\n/movies/Heat (1995)/Heat (1995).en.srt")
+        );
+        assert_eq!(get("MEDIAR_TITLE"), Some("Heat"));
+        assert_eq!(get("MEDIAR_TMDB_ID"), Some("949"));
+        assert_eq!(get("MEDIAR_CONTENT_TYPE"), Some("movie"));
+
+        assert_eq!(
+            summary(None)
+                .env()
+                .iter()
+                .find(|(key, _)| *key == "MEDIAR_TITLE"),
+            None
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let out = temp_dir.path().join("out.txt");
+        let command = format!("echo \"$MEDIAR_COUNT $MEDIAR_MODE\" > '{}'", out.display());
+
+        run(&command, &summary(None)).unwrap();
+        assert_eq!(fs::read_to_string(&out).unwrap(), "2 link\n");
+        assert!(run("exit 3", &summary(None)).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_for_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let out = temp_dir.path().join("out.txt");
+        let command = format!("echo \"$1 -> $2\" > '{}'", out.display());
+
+        run_for_file(
+            &command,
+            Path::new("/downloads/a b.mkv"),
+            Path::new("/library/A.mkv"),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&out).unwrap(),
+            "/downloads/a b.mkv -> /library/A.mkv\n"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static EXECUTING: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Handle Ctrl-C: while operations run, stop after the current file; otherwise exit at once
+pub fn install() -> Result<()> {
+    ctrlc::set_handler(|| {
+        if EXECUTING.load(Ordering::SeqCst) && !INTERRUPTED.swap(true, Ordering::SeqCst) {
+            eprintln!("\nStopping after the current file; press Ctrl-C again to quit immediately");
+        } else {
+            std::process::exit(130);
+        }
+    })
+    .map_err(|err| anyhow!("Failed to install the Ctrl-C handler: {}", err))
+}
+
+/// Marks operations as running until dropped
+pub struct Executing;
+
+impl Executing {
+    pub fn start() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        EXECUTING.store(true, Ordering::SeqCst);
+        Executing
+    }
+}
+
+impl Drop for Executing {
+    fn drop(&mut self) {
+        EXECUTING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Whether Ctrl-C was pressed while operations were running
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Fail with an "Interrupted" error if Ctrl-C was pressed
+pub fn check() -> Result<()> {
+    if requested() {
+        return Err(anyhow!("Interrupted"));
+    }
+    Ok(())
+}
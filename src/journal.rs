@@ -0,0 +1,335 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::plan::{ExecutionSettings, Mode, PlannedOperation};
+
+/// Written in the source directory while a run executes, and kept if it does not finish
+pub const JOURNAL_FILE: &str = ".mediar-journal.json";
+
+/// Appended to the name of an existing target while a better file replaces it
+pub const REPLACED_SUFFIX: &str = ".mediar-replaced";
+
+/// Where an existing target is kept while a better file replaces it, e.g. `Heat (1995).mkv.mediar-replaced`
+pub fn aside_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(REPLACED_SUFFIX);
+    path.with_file_name(name)
+}
+
+/// A completed operation, with what is needed to undo it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Entry {
+    /// A source renamed to its destination
+    Moved { source: PathBuf, target: PathBuf },
+    /// A destination created by copying or linking
+    Created { source: PathBuf, target: PathBuf },
+    /// A destination created and its source staged in the trash
+    Staged {
+        source: PathBuf,
+        target: PathBuf,
+        trashed: PathBuf,
+    },
+}
+
+impl Entry {
+    /// The source the operation was made from
+    pub fn source(&self) -> &Path {
+        match self {
+            Entry::Moved { source, .. }
+            | Entry::Created { source, .. }
+            | Entry::Staged { source, .. } => source,
+        }
+    }
+
+    /// The destination the operation created
+    pub fn target(&self) -> &Path {
+        match self {
+            Entry::Moved { target, .. }
+            | Entry::Created { target, .. }
+            | Entry::Staged { target, .. } => target,
+        }
+    }
+
+    pub fn undo(&self) -> Result<()> {
+        match self {
+            Entry::Moved { source, target } => fs::rename(target, source)
+                .with_context(|| format!("Failed to move {} back", target.display())),
+            Entry::Created { target, .. } => fs::remove_file(target)
+                .with_context(|| format!("Failed to remove {}", target.display())),
+            Entry::Staged {
+                source,
+                target,
+                trashed,
+            } => {
+                fs::rename(trashed, source)
+                    .with_context(|| format!("Failed to restore {}", source.display()))?;
+                fs::remove_file(target)
+                    .with_context(|| format!("Failed to remove {}", target.display()))
+            }
+        }
+    }
+}
+
+/// The operations completed during a run, in order, and the directories they created
+#[derive(Debug, Default)]
+pub struct Journal {
+    entries: Vec<Entry>,
+    /// Existing targets moved to their [`aside_path`] before being replaced
+    replaced: Vec<Entry>,
+    created_dirs: Vec<PathBuf>,
+}
+
+impl Journal {
+    /// Remember the ancestors of `dir` that do not exist yet, before they are created
+    pub fn record_dirs(&mut self, dir: &Path) {
+        let mut missing: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|ancestor| !ancestor.as_os_str().is_empty() && !ancestor.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        missing.reverse();
+        self.created_dirs.extend(missing);
+    }
+
+    pub fn record(&mut self, entry: Entry) {
+        self.entries.push(entry);
+    }
+
+    /// Remember a target moved aside for its replacement, so a rollback can bring it back
+    pub fn record_replaced(&mut self, aside: Entry) {
+        self.replaced.push(aside);
+    }
+
+    /// Delete the targets moved aside, once the run keeps their replacements
+    pub fn discard_replaced(&mut self) {
+        for aside in self.replaced.drain(..) {
+            if let Err(err) = fs::remove_file(aside.target()) {
+                tracing::warn!(path = %aside.target().display(), %err, "Failed to remove replaced file");
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Undo every recorded operation, newest first, returning the ones that could not be undone
+    pub fn rollback(self) -> Vec<(Entry, anyhow::Error)> {
+        let mut failures = Vec::new();
+        // Replacements are removed before the targets they replaced are moved back
+        for entry in self
+            .entries
+            .into_iter()
+            .rev()
+            .chain(self.replaced.into_iter().rev())
+        {
+            if let Err(err) = entry.undo() {
+                failures.push((entry, err));
+            }
+        }
+        // Only empty directories are removed, so anything left behind keeps its folder
+        for dir in self.created_dirs.iter().rev() {
+            let _ = fs::remove_dir(dir);
+        }
+        failures
+    }
+}
+
+/// What a run completed and what is left, for `mediar resume`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalFile {
+    pub mode: Mode,
+    /// The source directory of the run
+    pub source: PathBuf,
+    #[serde(flatten)]
+    pub settings: ExecutionSettings,
+    pub completed: Vec<PlannedOperation>,
+    pub remaining: Vec<PlannedOperation>,
+    /// Existing targets the remaining operations replace with a better file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced: Vec<PathBuf>,
+}
+
+impl JournalFile {
+    /// Where the journal for a source is written: in it, or next to it for a single file
+    pub fn path(source: &Path) -> PathBuf {
+        match source.parent() {
+            Some(parent) if !source.is_dir() => parent.join(JOURNAL_FILE),
+            _ => source.join(JOURNAL_FILE),
+        }
+    }
+
+    /// Move a finished operation from `remaining` to `completed` and save the journal
+    pub fn complete(&mut self, target: &Path) -> Result<PathBuf> {
+        if let Some(index) = self.remaining.iter().position(|op| op.target == target) {
+            let operation = self.remaining.remove(index);
+            self.replaced
+                .retain(|replaced| *replaced != operation.target);
+            self.completed.push(operation);
+        }
+        self.save()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read journal from {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid journal file {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<PathBuf> {
+        let path = Self::path(&self.source);
+        fs::write(&path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write journal to {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rollback() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("moved.mkv"), "moved").unwrap();
+        fs::write(source.join("copied.mkv"), "copied").unwrap();
+
+        let mut journal = Journal::default();
+        let season = target.join("Show (2008)").join("Season 01");
+        journal.record_dirs(&season);
+        fs::create_dir_all(&season).unwrap();
+        journal.record_dirs(&season);
+
+        fs::rename(source.join("moved.mkv"), season.join("A.mkv")).unwrap();
+        journal.record(Entry::Moved {
+            source: source.join("moved.mkv"),
+            target: season.join("A.mkv"),
+        });
+        fs::copy(source.join("copied.mkv"), season.join("B.mkv")).unwrap();
+        journal.record(Entry::Created {
+            source: source.join("copied.mkv"),
+            target: season.join("B.mkv"),
+        });
+        assert_eq!(journal.len(), 2);
+
+        assert!(journal.rollback().is_empty());
+        assert_eq!(
+            fs::read_to_string(source.join("moved.mkv")).unwrap(),
+            "moved"
+        );
+        assert!(source.join("copied.mkv").exists());
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn test_rollback_restores_replaced() {
+        let temp_dir = TempDir::new().unwrap();
+        let (old, target) = (
+            temp_dir.path().join("new.mkv"),
+            temp_dir.path().join("A.mkv"),
+        );
+        fs::write(&old, "better").unwrap();
+        fs::write(&target, "worse").unwrap();
+
+        let mut journal = Journal::default();
+        let aside = Entry::Moved {
+            source: target.clone(),
+            target: aside_path(&target),
+        };
+        fs::rename(&target, aside.target()).unwrap();
+        fs::rename(&old, &target).unwrap();
+        journal.record_replaced(aside);
+        journal.record(Entry::Moved {
+            source: old.clone(),
+            target: target.clone(),
+        });
+
+        assert!(journal.rollback().is_empty());
+        assert_eq!(fs::read_to_string(&old).unwrap(), "better");
+        assert_eq!(fs::read_to_string(&target).unwrap(), "worse");
+        assert!(!aside_path(&target).exists());
+    }
+
+    #[test]
+    fn test_journal_file_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal = JournalFile {
+            mode: Mode::Copy,
+            source: temp_dir.path().to_path_buf(),
+            settings: ExecutionSettings {
+                keep_going: true,
+                rate_limit: Some(1024),
+                file_hooks: vec!["true".to_string()],
+                ..Default::default()
+            },
+            completed: Vec::new(),
+            remaining: vec![PlannedOperation {
+                source: temp_dir.path().join("a.mkv"),
+                target: temp_dir.path().join("A.mkv"),
+            }],
+            replaced: vec![temp_dir.path().join("A.mkv")],
+        };
+        let path = journal.save().unwrap();
+        assert_eq!(path, temp_dir.path().join(JOURNAL_FILE));
+        assert_eq!(JournalFile::load(&path).unwrap(), journal);
+    }
+
+    #[test]
+    fn test_journal_file_complete() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("Heat.mkv");
+        fs::write(&source, "").unwrap();
+        let mut journal = JournalFile {
+            mode: Mode::Move,
+            source: source.clone(),
+            settings: ExecutionSettings::default(),
+            completed: Vec::new(),
+            remaining: vec![PlannedOperation {
+                source,
+                target: temp_dir.path().join("Heat (1995).mkv"),
+            }],
+            replaced: vec![temp_dir.path().join("Heat (1995).mkv")],
+        };
+        let path = journal
+            .complete(&temp_dir.path().join("Heat (1995).mkv"))
+            .unwrap();
+        assert_eq!(path, temp_dir.path().join(JOURNAL_FILE));
+        let saved = JournalFile::load(&path).unwrap();
+        assert_eq!(saved.completed.len(), 1);
+        assert!(saved.remaining.is_empty());
+        assert!(saved.replaced.is_empty());
+    }
+
+    #[test]
+    fn test_journal_file_without_settings() {
+        let journal: JournalFile = serde_json::from_str(
+            r#"{"mode": "move", "source": "/src", "trash_retention_days": 7, "completed": [], "remaining": []}"#,
+        )
+        .unwrap();
+        assert_eq!(journal.settings.trash_retention_days, Some(7));
+        assert!(!journal.settings.keep_going);
+    }
+
+    #[test]
+    fn test_rollback_reports_failures() {
+        let mut journal = Journal::default();
+        journal.record(Entry::Created {
+            source: PathBuf::from("/nonexistent/source.mkv"),
+            target: PathBuf::from("/nonexistent/file.mkv"),
+        });
+        assert_eq!(journal.rollback().len(), 1);
+    }
+}
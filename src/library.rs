@@ -0,0 +1,290 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use crate::{
+    naming::is_letter_folder,
+    tmdb::{Show, TvSeasonEpisode},
+    video::{parse_episode, parse_extension, parse_extra},
+};
+
+/// Season and episode numbers of the episodes in an organized show directory
+pub fn existing_episodes(show_dir: &Path) -> Result<HashSet<(i32, i32)>> {
+    let mut episodes = HashSet::new();
+    for entry in WalkDir::new(show_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || parse_extension(entry.path()).is_none() {
+            continue;
+        }
+
+        let relative = entry.path().strip_prefix(show_dir)?;
+        if parse_extra(relative).is_some() {
+            continue;
+        }
+
+        // Only the file name, since folder names like "Season 01" would be mistaken for codes
+        if let Ok(numbers) = parse_episode(Path::new(entry.file_name())) {
+            episodes.insert(numbers);
+        }
+    }
+    Ok(episodes)
+}
+
+/// Whether an organized title directory holds a show (season folders) rather than a movie
+pub fn is_show_dir(dir: &Path) -> bool {
+    let Ok(re) = Regex::new(r"(?i)^(season[ ._\-]*\d+|specials)$") else {
+        return false;
+    };
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .any(|entry| {
+            entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+                && entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| re.is_match(name))
+        })
+}
+
+/// The show and movie folders of a library, sorted, without hidden ones like `.mediar-trash`
+///
+/// Titles inside first-letter folders (`W/Wire, The (2002)`) are listed too.
+pub fn title_dirs(library: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(library)
+        .with_context(|| format!("Failed to read library {}", library.display()))?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+    {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        if is_letter_folder(&name) {
+            dirs.extend(title_dirs(&entry.path())?);
+        } else {
+            dirs.push(entry.path());
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// What an organized show or movie folder contains
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TitleStats {
+    /// Folder name, e.g. `Breaking Bad (2008)`
+    pub name: String,
+    pub is_show: bool,
+    /// Seasons with at least one episode
+    pub seasons: BTreeSet<i32>,
+    /// Distinct episodes (0 for movies)
+    pub episodes: usize,
+    /// Total size of the media files, in bytes
+    pub size: u64,
+    /// Number of media files per extension
+    pub extensions: BTreeMap<String, usize>,
+}
+
+/// Count the media files, episodes and seasons in a show or movie folder
+pub fn title_stats(dir: &Path) -> Result<TitleStats> {
+    let mut stats = TitleStats {
+        name: dir
+            .file_name()
+            .context("Failed to get directory name")?
+            .to_string_lossy()
+            .into_owned(),
+        is_show: is_show_dir(dir),
+        ..Default::default()
+    };
+
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Some(ext) = parse_extension(entry.path()) else {
+            continue;
+        };
+        stats.size += entry.metadata()?.len();
+        *stats.extensions.entry(ext).or_default() += 1;
+    }
+
+    if stats.is_show {
+        let episodes = existing_episodes(dir)?;
+        stats.seasons = episodes.iter().map(|(season, _)| *season).collect();
+        stats.episodes = episodes.len();
+    }
+    Ok(stats)
+}
+
+/// Whether an episode has aired by the given date
+fn has_aired(episode: &TvSeasonEpisode, today: NaiveDate) -> bool {
+    episode
+        .air_date
+        .as_deref()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+        .is_some_and(|date| date <= today)
+}
+
+/// Aired episodes of a show that are not in the library
+pub fn missing_episodes<'a>(
+    show: &'a Show,
+    existing: &HashSet<(i32, i32)>,
+    today: NaiveDate,
+) -> Vec<&'a TvSeasonEpisode> {
+    show.seasons
+        .iter()
+        .flat_map(|season| &season.episodes)
+        .filter(|episode| has_aired(episode, today))
+        .filter(|episode| !existing.contains(&(episode.season_number, episode.episode_number)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmdb::TvSeason;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn episode(season_number: i32, episode_number: i32, air_date: Option<&str>) -> TvSeasonEpisode {
+        TvSeasonEpisode {
+            id: season_number * 100 + episode_number,
+            season_number,
+            episode_number,
+            name: format!("Episode {}", episode_number),
+            overview: String::new(),
+            air_date: air_date.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_existing_episodes() {
+        let temp_dir = TempDir::new().unwrap();
+        let season = temp_dir.path().join("Season 01");
+        let featurettes = temp_dir.path().join("Featurettes");
+        fs::create_dir_all(&season).unwrap();
+        fs::create_dir_all(&featurettes).unwrap();
+        for path in [
+            season.join("Show - S01E01 - Pilot.mkv"),
+            season.join("Show - S01E01 - Pilot.srt"),
+            season.join("Show - S01E03 - The Sisters.mkv"),
+            season.join("folder.jpg"),
+            featurettes.join("Making S01E02.mkv"),
+        ] {
+            fs::write(path, "").unwrap();
+        }
+
+        assert_eq!(
+            existing_episodes(temp_dir.path()).unwrap(),
+            HashSet::from([(1, 1), (1, 3)])
+        );
+    }
+
+    #[test]
+    fn test_is_show_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let show = temp_dir.path().join("Show (2008)");
+        let movie = temp_dir.path().join("Movie (1999)");
+        fs::create_dir_all(show.join("Season 01")).unwrap();
+        fs::create_dir_all(movie.join("Featurettes")).unwrap();
+
+        assert!(is_show_dir(&show));
+        assert!(!is_show_dir(&movie));
+    }
+
+    #[test]
+    fn test_title_dirs_letter_folders() {
+        let temp_dir = TempDir::new().unwrap();
+        let wire = temp_dir.path().join("W").join("Wire, The (2002)");
+        let numbered = temp_dir.path().join("#").join("24 (2001)");
+        let up = temp_dir.path().join("Up (2009)");
+        for dir in [&wire, &numbered, &up] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        assert_eq!(
+            title_dirs(temp_dir.path()).unwrap(),
+            vec![numbered, up, wire]
+        );
+    }
+
+    #[test]
+    fn test_title_stats() {
+        let temp_dir = TempDir::new().unwrap();
+        let show = temp_dir.path().join("Show (2008)");
+        fs::create_dir_all(show.join("Season 01")).unwrap();
+        fs::create_dir_all(show.join("Season 03")).unwrap();
+        fs::create_dir_all(temp_dir.path().join(".mediar-trash")).unwrap();
+        for (path, contents) in [
+            ("Season 01/Show - S01E01 - Pilot.mkv", "12345"),
+            ("Season 01/Show - S01E01 - Pilot.srt", "1"),
+            ("Season 01/Show - S01E02 - Two.mkv", "123"),
+            ("Season 03/Show - S03E01 - Three.mp4", "12"),
+            ("Season 03/folder.jpg", "123456789"),
+        ] {
+            fs::write(show.join(path), contents).unwrap();
+        }
+
+        assert_eq!(title_dirs(temp_dir.path()).unwrap(), vec![show.clone()]);
+
+        let stats = title_stats(&show).unwrap();
+        assert_eq!(stats.name, "Show (2008)");
+        assert!(stats.is_show);
+        assert_eq!(stats.seasons, BTreeSet::from([1, 3]));
+        assert_eq!(stats.episodes, 3);
+        assert_eq!(stats.size, 11);
+        assert_eq!(
+            stats.extensions,
+            BTreeMap::from([
+                ("mkv".to_string(), 2),
+                ("mp4".to_string(), 1),
+                ("srt".to_string(), 1)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_missing_episodes() {
+        let show = Show {
+            id: 1,
+            name: "Show".to_string(),
+            original_name: None,
+            overview: String::new(),
+            year: 2020,
+            first_air_date: "2020-01-01".to_string(),
+            number_of_episodes: 4,
+            number_of_seasons: 1,
+            seasons: vec![TvSeason {
+                id: 1,
+                season_number: 1,
+                name: "Season 1".to_string(),
+                overview: String::new(),
+                air_date: None,
+                episodes: vec![
+                    episode(1, 1, Some("2020-01-01")),
+                    episode(1, 2, Some("2020-01-08")),
+                    episode(1, 3, Some("2030-01-01")),
+                    episode(1, 4, None),
+                ],
+            }],
+        };
+
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let missing = missing_episodes(&show, &HashSet::from([(1, 1)]), today);
+        let codes: Vec<_> = missing
+            .iter()
+            .map(|episode| (episode.season_number, episode.episode_number))
+            .collect();
+        assert_eq!(codes, vec![(1, 2)]);
+    }
+}
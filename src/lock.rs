@@ -0,0 +1,123 @@
+use anyhow::{Context, Result, anyhow};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// An advisory lock held on a source while it is organized, released when dropped
+///
+/// Locks live outside the source, in the cache directory, one per source keyed by its
+/// canonical path, so they never show up among the files being organized.
+#[derive(Debug)]
+pub struct SourceLock {
+    _file: File,
+}
+
+/// Where locks are kept
+fn lock_dir() -> Result<PathBuf> {
+    Ok(dirs::cache_dir()
+        .context("Failed to find a cache directory for lock files")?
+        .join("mediar")
+        .join("locks"))
+}
+
+/// The lock file for a source, named after a hash of its canonical path
+pub fn lock_path(dir: &Path, source: &Path) -> PathBuf {
+    let canonical = fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf());
+    let digest = Sha256::digest(canonical.to_string_lossy().as_bytes());
+    let name: String = digest[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    dir.join(format!("{}.lock", name))
+}
+
+impl SourceLock {
+    /// Lock a source, waiting for another run to finish with it or failing right away
+    pub fn acquire(source: &Path, wait: bool) -> Result<Self> {
+        Self::acquire_in(&lock_dir()?, source, wait)
+    }
+
+    fn acquire_in(dir: &Path, source: &Path, wait: bool) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = lock_path(dir, source);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("Failed to open lock file {}", path.display()))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) if wait => {
+                eprintln!(
+                    "Waiting for another mediar run ({}) to finish with {}",
+                    holder(&path),
+                    source.display()
+                );
+                file.lock()?;
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(anyhow!(
+                    "Another mediar run ({}) is organizing {}; pass --wait to wait for it",
+                    holder(&path),
+                    source.display()
+                ));
+            }
+            Err(TryLockError::Error(err)) => {
+                return Err(err).with_context(|| format!("Failed to lock {}", path.display()));
+            }
+        }
+
+        // Tell the next run who holds the lock
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// The process holding a lock, as recorded in its lock file
+fn holder(path: &Path) -> String {
+    match fs::read_to_string(path) {
+        Ok(pid) if !pid.trim().is_empty() => format!("pid {}", pid.trim()),
+        _ => "unknown pid".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lock_path_per_source() {
+        let dir = Path::new("/cache/locks");
+        let a = lock_path(dir, Path::new("/downloads/a"));
+        assert_eq!(a, lock_path(dir, Path::new("/downloads/a")));
+        assert_ne!(a, lock_path(dir, Path::new("/downloads/b")));
+        assert_eq!(a.parent(), Some(dir));
+    }
+
+    #[test]
+    fn test_second_lock_fails_until_released() {
+        let temp_dir = TempDir::new().unwrap();
+        let locks = temp_dir.path().join("locks");
+        let source = temp_dir.path().join("downloads");
+
+        let lock = SourceLock::acquire_in(&locks, &source, false).unwrap();
+        let err = SourceLock::acquire_in(&locks, &source, false).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&format!("pid {}", std::process::id())),
+            "{}",
+            err
+        );
+
+        drop(lock);
+        assert!(SourceLock::acquire_in(&locks, &source, false).is_ok());
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use std::{fs::OpenOptions, path::Path, sync::Mutex};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    fmt,
+    prelude::*,
+};
+
+/// How much is logged to stderr: warnings by default, errors only with `--quiet`, and
+/// more with each `-v`
+pub fn stderr_level(verbose: u8, quiet: bool) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) => LevelFilter::ERROR,
+        (false, 0) => LevelFilter::WARN,
+        (false, 1) => LevelFilter::INFO,
+        (false, 2) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    }
+}
+
+/// Only mediar's own events, not those of the HTTP stack underneath
+fn own_events(level: LevelFilter) -> Targets {
+    Targets::new().with_target(env!("CARGO_CRATE_NAME"), level)
+}
+
+/// Log to stderr at the chosen verbosity and, with a log file, everything down to debug
+/// as one JSON object per line
+pub fn init(verbose: u8, quiet: bool, ansi: bool, log_file: Option<&Path>) -> Result<()> {
+    let stderr = fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .with_ansi(ansi)
+        .without_time()
+        .with_filter(own_events(stderr_level(verbose, quiet)));
+    let file = log_file
+        .map(|path| {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {}", path.display()))?;
+            Ok::<_, anyhow::Error>(
+                fmt::layer()
+                    .json()
+                    .with_writer(Mutex::new(file))
+                    .with_ansi(false)
+                    .with_filter(own_events(LevelFilter::DEBUG)),
+            )
+        })
+        .transpose()?;
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .try_init()
+        .context("Failed to set up logging")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stderr_level() {
+        assert_eq!(stderr_level(0, false), LevelFilter::WARN);
+        assert_eq!(stderr_level(1, false), LevelFilter::INFO);
+        assert_eq!(stderr_level(2, false), LevelFilter::DEBUG);
+        assert_eq!(stderr_level(5, false), LevelFilter::TRACE);
+        assert_eq!(stderr_level(2, true), LevelFilter::ERROR);
+    }
+}
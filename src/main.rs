@@ -1,36 +1,600 @@
+mod archive;
+mod auth;
+mod checksum;
+mod clean;
+mod config;
+mod exit;
+mod filesystem;
+mod graphics;
+mod hook;
+mod interrupt;
+mod journal;
+mod library;
+mod lock;
+mod logging;
+mod marker;
+mod matching;
+mod metadata;
+mod metrics;
+mod naming;
+mod notify;
+#[macro_use]
+mod output;
+mod personal;
+mod plan;
+mod preview;
+mod prompt;
+mod remote;
+mod rules;
+mod scan;
+mod script;
+mod serve;
+mod state;
+mod sync;
 mod tmdb;
+mod transfer;
+mod trash;
+mod upgrade;
 mod video;
 
 use crate::{
-    tmdb::{Movie, MovieSearchResult, Show, TmdbClient, TvSearchResult},
-    video::{ContentType, parse_content_type, parse_episode_id, parse_extension, parse_title},
+    checksum::ChecksumFormat,
+    clean::Retention,
+    config::{Config, SeasonMap, TmdbConfig},
+    exit::Status,
+    filesystem::TargetFs,
+    hook::RunSummary,
+    journal::{Entry, Journal, JournalFile},
+    library::TitleStats,
+    lock::SourceLock,
+    marker::Marker,
+    matching::{
+        DEFAULT_MIN_CONFIDENCE, EPISODE_TITLE_CONFIDENCE, MatchMemory, best_match, confidence,
+        match_episode_title,
+    },
+    metadata::LocalMetadata,
+    naming::{EpisodeStyle, IdTag, TitleStyle, title_folder},
+    notify::{NotifyConfig, Organized},
+    output::{Event, OutputFormat},
+    plan::{
+        Destination, ExecutionSettings, LinkFallback, Media, Mode, Plan, PlanFile,
+        PlannedOperation, SkipReason, device_of, group_by_device,
+    },
+    preview::Preview,
+    remote::RemoteSource,
+    rules::{RuleAction, RuleInput, Rules},
+    scan::PathFilter,
+    script::{NamingScript, ScriptInput},
+    serve::{OrganizeRequest, RunReport, Runner},
+    state::StateDb,
+    sync::SyncReason,
+    tmdb::{
+        Credential, DEFAULT_SEARCH_LIMIT, EpisodeOrder, ExternalIds, Genre, Movie,
+        MovieSearchResult, MultiSearchResult, Show, TmdbClient, TvSearchResult, TvSeason,
+        TvSeasonEpisode, year_of,
+    },
+    transfer::ReflinkFallback,
+    upgrade::UpgradePolicy,
+    video::{
+        ContentType, DISC, ExternalId, ExtraKind, detect_resolution, disc_path, episode_id,
+        external_id, is_sidecar, parse_content_type, parse_edition, parse_episode, parse_extension,
+        parse_external_id, parse_extra, parse_part, parse_title, parse_year, part_marker,
+        sidecar_video, tagged_extension, title_from, year_from,
+    },
 };
 use anyhow::{Context, Result, anyhow};
-use clap::{Parser, Subcommand};
+use clap::{Args as _, FromArgMatches, Parser, Subcommand, ValueEnum, builder::BoolishValueParser};
 use colored::{ColoredString, Colorize};
-use inquire::{Confirm, Select};
+use futures::{StreamExt, stream};
+use inquire::{Confirm, MultiSelect, Select};
 use sanitize_filename::sanitize;
 use std::{
-    collections::HashSet,
-    fs,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt, fs,
+    io::{self, IsTerminal},
+    net::SocketAddr,
     path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, SystemTime},
 };
 use tabled::{Table, Tabled, settings::Style};
 use textwrap::{Options, termwidth, wrap};
-use walkdir::WalkDir;
-
-#[derive(Debug, Clone)]
-enum Mode {
-    Move,
-    Copy,
-    Link,
-}
 
 enum Content {
     Show(Show),
     Movie(Movie),
 }
 
+/// Which kinds of titles `search` looks for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SearchType {
+    Tv,
+    Movie,
+    /// Both TV shows and movies
+    #[default]
+    All,
+}
+
+impl fmt::Display for SearchType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchType::Tv => write!(f, "{}", ContentType::Show),
+            SearchType::Movie => write!(f, "{}", ContentType::Movie),
+            SearchType::All => write!(f, "Either"),
+        }
+    }
+}
+
+/// How movie editions are appended to file names
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+enum EditionStyle {
+    /// Plex edition tags: `Title (Year) {edition-Director's Cut}`
+    #[default]
+    Plex,
+    /// Plain suffix: `Title (Year) - Director's Cut`
+    Suffix,
+}
+
+impl EditionStyle {
+    fn format(&self, edition: &str) -> String {
+        match self {
+            EditionStyle::Plex => format!(" {{edition-{}}}", edition),
+            EditionStyle::Suffix => format!(" - {}", edition),
+        }
+    }
+}
+
+/// What distinguishes versions of a movie that would otherwise get the same name
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum VersionSuffix {
+    /// The resolution, as Plex labels versions: `Title (Year) - 1080p`
+    Quality,
+}
+
+/// What to do with trailers found in the source
+#[derive(Debug, Default, Clone, Copy, ValueEnum)]
+enum TrailerPolicy {
+    /// Place trailers in a `Trailers/` folder inside the title directory
+    #[default]
+    Folder,
+    /// Place the trailer next to the main file as `Title (Year)-trailer.ext`
+    Inline,
+    /// Leave trailers where they are
+    Skip,
+}
+
+/// Options shared by the organize flows
+#[derive(Debug, Default, Clone)]
+struct OrganizeOptions {
+    /// Skip confirmation prompt
+    auto_confirm: bool,
+    edition_style: EditionStyle,
+    /// Move sources even if they are hard-linked elsewhere
+    allow_hardlinked: bool,
+    /// Name episodes missing from TMDB with a placeholder title instead of failing
+    placeholder_missing: bool,
+    /// Include/exclude patterns applied while scanning the source
+    filter: PathFilter,
+    /// Name new episodes like the ones already in the target show directory
+    match_existing_style: bool,
+    trailers: TrailerPolicy,
+    /// Rules from the config file, evaluated for every source file
+    rules: Rules,
+    /// Provider ID tag for show and movie folder names
+    id_tag: Option<IdTag>,
+    /// Execute only a random sample of this many operations
+    sample: Option<usize>,
+    /// Run operations on different destination devices in parallel
+    parallel_devices: bool,
+    /// Script that rewrites the destinations of episodes and movies
+    script: Option<NamingScript>,
+    /// For moves, stage sources in `.mediar-trash/` for this many days instead of moving them
+    trash_retention_days: Option<u32>,
+    /// Filesystem whose name limits destinations must respect
+    target_fs: Option<TargetFs>,
+    /// Save the plan here instead of executing it
+    plan_out: Option<PathBuf>,
+    /// Let the user deselect individual operations before executing
+    select_operations: bool,
+    /// Let the user correct or exclude planned files before executing
+    review: bool,
+    /// How planned operations are shown before confirming
+    preview: Preview,
+    /// Record failed operations and continue instead of stopping at the first one
+    keep_going: bool,
+    /// Roll back the run's completed operations if one fails
+    atomic: bool,
+    /// For reflinks, what to do when the filesystem cannot clone
+    reflink_fallback: ReflinkFallback,
+    /// Maximum bytes per second for each copy
+    rate_limit: Option<u64>,
+    /// Which TMDB title names shows and movies
+    title_style: TitleStyle,
+    /// Transliterate titles to Latin script
+    transliterate: bool,
+    /// Move leading articles to the end of folder names, e.g. `Wire, The (2002)`
+    sort_articles: bool,
+    /// Put title folders in first-letter folders, e.g. `W/Wire, The (2002)`
+    letter_folders: bool,
+    /// Replace or add a version next to existing targets when the source is better quality
+    upgrade: Option<UpgradePolicy>,
+    /// Suffix movie files so several versions of a movie can coexist
+    version_suffix: Option<VersionSuffix>,
+    /// Added to every parsed season number
+    season_offset: i32,
+    /// Added to every parsed episode number
+    episode_offset: i32,
+    /// Per-season renumbering from the show's config, used instead of the offsets
+    season_map: Vec<SeasonMap>,
+    /// For hard links, what to do when source and target are on different filesystems
+    link_fallback: LinkFallback,
+    /// Database of organized files: matching sources are skipped, new ones recorded
+    state_db: Option<PathBuf>,
+    /// Shell commands run after a successful run, from the config and `--post-hook`
+    post_hooks: Vec<String>,
+    /// Shell commands run for each completed operation, from the config and `--file-hook`
+    file_hooks: Vec<String>,
+    /// Skip files whose size changes over this long, as still being written
+    stable_for: Option<Duration>,
+    /// Remote the planned sources are downloaded from; they are local placeholders
+    remote: Option<RemoteSource>,
+    /// Record checksums of completed targets in a manifest in each target folder
+    write_checksums: Option<ChecksumFormat>,
+}
+
+impl OrganizeOptions {
+    /// Options to execute saved operations with
+    fn from_settings(settings: ExecutionSettings, auto_confirm: bool) -> Self {
+        Self {
+            auto_confirm,
+            allow_hardlinked: settings.allow_hardlinked,
+            parallel_devices: settings.parallel_devices,
+            trash_retention_days: settings.trash_retention_days,
+            keep_going: settings.keep_going,
+            atomic: settings.atomic,
+            reflink_fallback: settings.reflink_fallback,
+            link_fallback: settings.link_fallback,
+            rate_limit: settings.rate_limit,
+            state_db: settings.state_db,
+            post_hooks: settings.post_hooks,
+            file_hooks: settings.file_hooks,
+            write_checksums: settings.write_checksums,
+            ..Default::default()
+        }
+    }
+
+    /// The options saved with operations, for `mediar apply` and `mediar resume`
+    fn settings(&self) -> ExecutionSettings {
+        ExecutionSettings {
+            allow_hardlinked: self.allow_hardlinked,
+            parallel_devices: self.parallel_devices,
+            trash_retention_days: self.trash_retention_days,
+            keep_going: self.keep_going,
+            atomic: self.atomic,
+            reflink_fallback: self.reflink_fallback,
+            link_fallback: self.link_fallback,
+            rate_limit: self.rate_limit,
+            state_db: self.state_db.clone(),
+            post_hooks: self.post_hooks.clone(),
+            file_hooks: self.file_hooks.clone(),
+            write_checksums: self.write_checksums,
+        }
+    }
+
+    /// Renumber a parsed episode to TMDB's numbering
+    fn map_episode(&self, season: i32, episode: i32) -> (i32, i32) {
+        match self.season_map.iter().find(|map| map.season == season) {
+            Some(map) => (
+                map.to_season.unwrap_or(season),
+                episode + map.episode_offset,
+            ),
+            None => (season + self.season_offset, episode + self.episode_offset),
+        }
+    }
+
+    /// Folder of a show or movie below the target, e.g. `W/Wire, The (2002)` with every option
+    fn title_dir(&self, name: &str, year: i32, id: i32) -> PathBuf {
+        let name = if self.sort_articles {
+            naming::move_article(name)
+        } else {
+            name.to_string()
+        };
+        let folder = sanitize(title_folder(&name, year, id, self.id_tag));
+        if self.letter_folders {
+            Path::new(&naming::letter_folder(&folder)).join(folder)
+        } else {
+            PathBuf::from(folder)
+        }
+    }
+}
+
+/// Fixes made while reviewing a plan, keyed by source file
+#[derive(Debug, Default)]
+struct Corrections {
+    /// Season and episode numbers to use instead of the parsed ones
+    episodes: HashMap<PathBuf, (i32, i32)>,
+    excluded: HashSet<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct OrganizeArgs {
+    /// Source directories or files, or globs such as `"downloads/Show*"`, then the target
+    /// directory if there is more than one path
+    #[arg(value_name = "PATH", required = true)]
+    paths: Vec<String>,
+    #[arg(long)]
+    tv_id: Option<i32>,
+    #[arg(long)]
+    movie_id: Option<i32>,
+    /// Episode numbering the files follow, using TMDB's DVD or absolute episode groups
+    /// [default: aired]
+    #[arg(long, value_enum)]
+    order: Option<EpisodeOrder>,
+    /// How to name movie editions such as Director's Cut or Extended
+    #[arg(long, value_enum, default_value_t)]
+    edition_style: EditionStyle,
+    /// Name episodes that are not on TMDB yet "TBA" instead of failing
+    #[arg(long)]
+    placeholder_missing: bool,
+    /// Only organize files matching this glob (repeatable)
+    #[arg(long)]
+    include: Vec<String>,
+    /// Skip files and directories matching this glob, e.g. `Extras/` or `*.trailer.mkv` (repeatable)
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Look at most this many folders deep below the source, both to detect the title and to
+    /// find files
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+    /// Descend into symlinked folders too
+    #[arg(long)]
+    follow_symlinks: bool,
+    /// Organize hidden files and look in hidden folders, whose names start with `.`
+    #[arg(long)]
+    hidden: bool,
+    /// Name new episodes like the episodes already in the target show directory
+    #[arg(long)]
+    match_existing_style: bool,
+    /// Where to put trailers (`*-trailer.*` files and `Trailers/` folders)
+    #[arg(long, value_enum, default_value_t)]
+    trailers: TrailerPolicy,
+    /// Pick the detected title without prompting when the best match is confident enough
+    #[arg(long)]
+    auto: bool,
+    /// Minimum match confidence (0.0-1.0) for --auto (default: 0.85)
+    #[arg(long, value_parser = matching::parse_confidence)]
+    min_confidence: Option<f64>,
+    /// Take the top search result without prompting
+    #[arg(long)]
+    first: bool,
+    /// Search results to offer when picking a title, paging through TMDB (default: 20)
+    #[arg(long, value_name = "COUNT")]
+    search_limit: Option<usize>,
+    /// Append a TMDB ID tag to show and movie folder names
+    #[arg(long, value_enum)]
+    id_tag: Option<IdTag>,
+    /// Plan everything but only execute a random sample of N operations
+    #[arg(long, value_name = "N")]
+    sample: Option<usize>,
+    /// Process each destination disk in its own thread (one file at a time per disk)
+    #[arg(long)]
+    parallel_devices: bool,
+    /// Adapt names to this filesystem's limits (detected from the target if omitted)
+    #[arg(long, value_enum)]
+    target_fs: Option<TargetFs>,
+    /// Write the plan to this JSON file instead of executing it (run it with `mediar apply`)
+    #[arg(long, value_name = "FILE")]
+    plan_out: Option<PathBuf>,
+    /// Only consider files untouched for this long, e.g. `10m`; newer ones may still be
+    /// downloading or unpacking
+    #[arg(long, value_name = "DURATION", value_parser = scan::parse_duration)]
+    min_age: Option<Duration>,
+    /// Wait this long after planning, e.g. `30s`, and skip files whose size changed meanwhile
+    #[arg(long, value_name = "DURATION", value_parser = scan::parse_duration)]
+    stable_for: Option<Duration>,
+    /// Extract RAR archives holding videos with `unrar`, organize the extracted files and
+    /// delete them afterwards
+    #[arg(long, conflicts_with = "plan_out")]
+    extract_archives: bool,
+    /// Pick the operations to run from a checklist instead of confirming all of them
+    #[arg(long)]
+    select: bool,
+    /// Review the plan first, correcting episode numbers or excluding files
+    #[arg(long)]
+    review: bool,
+    /// How to show the planned operations
+    #[arg(long, value_enum, default_value_t)]
+    preview: Preview,
+    /// Keep going after a failed operation and summarize the failures at the end
+    #[arg(long, conflicts_with = "atomic")]
+    keep_going: bool,
+    /// Undo the operations already done in this run if any operation fails
+    #[arg(long)]
+    atomic: bool,
+    /// Name shows and movies by their localized title, original title, or both
+    /// [default: localized]
+    #[arg(long, value_enum)]
+    title_style: Option<TitleStyle>,
+    /// Transliterate titles to Latin script, e.g. `Брат` to `Brat`
+    #[arg(long)]
+    transliterate: bool,
+    /// Move leading articles to the end of folder names, e.g. `Wire, The (2002)`
+    #[arg(long)]
+    sort_articles: bool,
+    /// Put show and movie folders in first-letter folders, e.g. `W/Wire, The (2002)`
+    #[arg(long)]
+    letter_folders: bool,
+    /// Add this to every parsed season number before looking up the episode on TMDB
+    /// [default: 0]
+    #[arg(long, allow_negative_numbers = true)]
+    season_offset: Option<i32>,
+    /// Add this to every parsed episode number before looking up the episode on TMDB
+    /// [default: 0]
+    #[arg(long, allow_negative_numbers = true)]
+    episode_offset: Option<i32>,
+    /// Suffix movie files with their resolution so different-quality rips can coexist
+    #[arg(long, value_enum)]
+    version_suffix: Option<VersionSuffix>,
+    /// When a target exists, replace it (or add a version) if the source is better quality
+    #[arg(long, value_enum, value_name = "POLICY")]
+    upgrade: Option<UpgradePolicy>,
+    /// Throttle copies to this rate, e.g. `50MB/s` or `800KiB/s`
+    #[arg(long, value_name = "RATE", value_parser = transfer::parse_rate)]
+    limit_rate: Option<u64>,
+    /// Skip files organized before and record new ones in this SQLite database
+    #[arg(long, value_name = "PATH")]
+    state_db: Option<PathBuf>,
+    /// Shell command to run after a successful run, e.g. to start a Plex scan (repeatable)
+    #[arg(long = "post-hook", value_name = "CMD")]
+    post_hooks: Vec<String>,
+    /// Shell command to run for each completed operation, given the source and target paths as
+    /// `$1` and `$2` (repeatable)
+    #[arg(long = "file-hook", value_name = "CMD")]
+    file_hooks: Vec<String>,
+    /// Record the checksums of organized files in a `SHA256SUMS` (or `checksums.sfv`) in each
+    /// folder, for `mediar checksum verify`
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "sha256"
+    )]
+    write_checksums: Option<ChecksumFormat>,
+}
+
+#[derive(clap::Args, Debug)]
+struct LinkArgs {
+    #[command(flatten)]
+    organize: OrganizeArgs,
+    /// What to do when the source and target are on different filesystems
+    #[arg(long, value_enum, default_value_t)]
+    fallback: LinkFallback,
+}
+
+#[derive(clap::Args, Debug)]
+struct ImportArgs {
+    /// Drop folder holding one folder (or file) per completed download
+    drop_folder: String,
+    /// Library root; defaults to `tv_target` and `movie_target` from the config
+    target: Option<String>,
+    /// Database recording imported releases and files; defaults to `state_db` from the
+    /// config, then `state.db` in mediar's data directory
+    #[arg(long, value_name = "PATH")]
+    state_db: Option<PathBuf>,
+    /// Minimum confidence for matching a release without prompting (0.0-1.0)
+    #[arg(long, value_parser = matching::parse_confidence)]
+    min_confidence: Option<f64>,
+}
+
+#[derive(clap::Args, Debug)]
+struct PersonalArgs {
+    /// Folder of camera footage and other home videos
+    source: String,
+    /// Library root the dated folders are created in
+    target: String,
+    /// How files are put into place
+    #[arg(long, value_enum, default_value = "link")]
+    mode: Mode,
+    /// Where each file goes under the target; fields are `{year}`, `{month}`, `{day}`,
+    /// `{folder}` (the folder it was found in) and `{name}` (its file name)
+    #[arg(long, value_name = "TEMPLATE")]
+    date_template: Option<String>,
+    /// Only organize files matching this glob (repeatable)
+    #[arg(long)]
+    include: Vec<String>,
+    /// Skip files and directories matching this glob (repeatable)
+    #[arg(long)]
+    exclude: Vec<String>,
+    /// Keep going after a failed operation and summarize the failures at the end
+    #[arg(long)]
+    keep_going: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct ReflinkArgs {
+    #[command(flatten)]
+    organize: OrganizeArgs,
+    /// What to do when the filesystem cannot clone a file
+    #[arg(long, value_enum, default_value_t)]
+    fallback: ReflinkFallback,
+}
+
+#[derive(clap::Args, Debug)]
+struct MoveArgs {
+    #[command(flatten)]
+    organize: OrganizeArgs,
+    /// Move files even if they are hard-linked elsewhere (e.g., still seeding)
+    #[arg(long)]
+    allow_hardlinked: bool,
+    /// Link or copy into the target, verify, then stage sources in a dated `.mediar-trash/`
+    #[arg(long)]
+    trash: bool,
+}
+
+impl OrganizeArgs {
+    /// The sources to organize: every path but the last when several are given
+    fn sources(&self) -> &[String] {
+        match self.paths.split_last() {
+            Some((_, sources)) if !sources.is_empty() => sources,
+            _ => &self.paths,
+        }
+    }
+
+    /// The target directory, given as the last of several paths
+    fn target(&self) -> Option<&str> {
+        self.paths
+            .split_last()
+            .filter(|(_, sources)| !sources.is_empty())
+            .map(|(target, _)| target.as_str())
+    }
+}
+
+impl TryFrom<&OrganizeArgs> for OrganizeOptions {
+    type Error = anyhow::Error;
+
+    fn try_from(args: &OrganizeArgs) -> Result<Self> {
+        Ok(Self {
+            auto_confirm: prompt::assume_yes(),
+            edition_style: args.edition_style,
+            placeholder_missing: args.placeholder_missing,
+            filter: PathFilter::new(&args.include, &args.exclude)?
+                .with_min_age(args.min_age)
+                .with_traversal(args.max_depth, args.follow_symlinks)
+                .with_hidden(args.hidden),
+            stable_for: args.stable_for,
+            match_existing_style: args.match_existing_style,
+            trailers: args.trailers,
+            id_tag: args.id_tag,
+            sample: args.sample,
+            parallel_devices: args.parallel_devices,
+            target_fs: args.target_fs,
+            plan_out: args.plan_out.clone(),
+            select_operations: args.select,
+            review: args.review,
+            preview: args.preview,
+            keep_going: args.keep_going,
+            atomic: args.atomic,
+            rate_limit: args.limit_rate,
+            state_db: args.state_db.clone(),
+            post_hooks: args.post_hooks.clone(),
+            file_hooks: args.file_hooks.clone(),
+            write_checksums: args.write_checksums,
+            title_style: args.title_style.unwrap_or_default(),
+            transliterate: args.transliterate,
+            sort_articles: args.sort_articles,
+            letter_folders: args.letter_folders,
+            upgrade: args.upgrade,
+            version_suffix: args.version_suffix,
+            season_offset: args.season_offset.unwrap_or(0),
+            episode_offset: args.episode_offset.unwrap_or(0),
+            ..Default::default()
+        })
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Search for TV shows and movies
@@ -43,42 +607,161 @@ enum Commands {
         /// Filter by minimum popularity (default: 1.0)
         #[arg(long, default_value = "1.0")]
         min_popularity: f64,
+        /// Maximum number of TV and of movie results to fetch, paging through TMDB as needed
+        #[arg(long, default_value_t = DEFAULT_SEARCH_LIMIT)]
+        limit: usize,
+        /// Search only TV shows or only movies
+        #[arg(long = "type", value_enum, default_value_t)]
+        search_type: SearchType,
+        /// Only titles first aired or released in this year
+        #[arg(long)]
+        year: Option<i32>,
+        /// Pick a result, then organize a directory with it
+        #[arg(long)]
+        pick: bool,
     },
     /// Move files to the target directory
-    Move {
-        source: String,
-        target: Option<String>,
+    Move(MoveArgs),
+    /// Copy files to the target directory
+    Copy(OrganizeArgs),
+    /// Create hard links in the target directory
+    Link(LinkArgs),
+    /// Create copy-on-write clones in the target directory (btrfs, XFS, APFS)
+    Reflink(ReflinkArgs),
+    /// Show details for a TV show or movie
+    Info {
         #[arg(long)]
         tv_id: Option<i32>,
         #[arg(long)]
         movie_id: Option<i32>,
-        /// Skip confirmation prompt
-        #[arg(short, long)]
-        yes: bool,
     },
-    /// Copy files to the target directory
-    Copy {
-        source: String,
-        target: Option<String>,
+    /// List the episodes of a TV show
+    Episodes {
         #[arg(long)]
-        tv_id: Option<i32>,
+        tv_id: i32,
+        /// Only list this season
         #[arg(long)]
-        movie_id: Option<i32>,
-        /// Skip confirmation prompt
-        #[arg(short, long)]
-        yes: bool,
+        season: Option<i32>,
     },
-    /// Create hard links in the target directory
-    Link {
-        source: String,
-        target: Option<String>,
+    /// Execute a plan saved with `--plan-out`
+    Apply {
+        /// Plan file, e.g. `plan.json`
+        plan: String,
+    },
+    /// Finish a run interrupted with Ctrl-C
+    Resume {
+        /// The run's source directory, or its `.mediar-journal.json`
+        path: String,
+    },
+    /// Check an organized library's file names against TMDB and the naming scheme
+    Verify {
+        /// Library directory containing show and movie folders
+        library: String,
+        /// Rename mismatched files to their expected names
+        #[arg(long)]
+        fix: bool,
+        /// Database to resume an interrupted verify from; defaults to `state_db` from the config
+        #[arg(long, value_name = "PATH")]
+        state_db: Option<PathBuf>,
+    },
+    /// Re-rename an organized show after its TMDB metadata changed
+    Refresh {
+        /// The show directory, e.g. `Shows/Breaking Bad (2008)`; without one, every show with
+        /// episodes named by `--placeholder-missing` in the state database
+        show_dir: Option<String>,
+        /// TMDB ID of the show (detected from the directory name if omitted)
+        #[arg(long, requires = "show_dir")]
+        tv_id: Option<i32>,
+        /// Database placeholder episodes were recorded in; defaults to `state_db` from the config
+        #[arg(long, value_name = "PATH")]
+        state_db: Option<PathBuf>,
+    },
+    /// Summarize the shows, movies, episodes and file types in an organized library
+    Stats {
+        /// Library directory containing show and movie folders
+        library: String,
+    },
+    /// Report aired episodes that are missing from an organized show directory
+    Missing {
+        /// The show directory, e.g. `Shows/Breaking Bad (2008)`
+        show_dir: String,
+        /// TMDB ID of the show (detected from the directory name if omitted)
         #[arg(long)]
         tv_id: Option<i32>,
+    },
+    /// Delete release junk (`RARBG.txt`, `.nfo`, `.exe`, screenshots, samples) under a directory
+    Clean {
+        /// Directory to clean, e.g. a download folder kept for seeding
+        dir: String,
+        /// Only list the files that would be deleted
         #[arg(long)]
-        movie_id: Option<i32>,
-        /// Skip confirmation prompt
-        #[arg(short, long)]
-        yes: bool,
+        dry_run: bool,
+    },
+    /// Hard-link each new release in a drop folder into the library, like Sonarr/Radarr's
+    /// completed-download handling
+    Import(ImportArgs),
+    /// Run an HTTP server that organizes downloads on request, e.g. from a torrent client's
+    /// webhook
+    Serve {
+        /// Address and port to listen on
+        #[arg(long, default_value = serve::DEFAULT_LISTEN)]
+        listen: SocketAddr,
+        /// Require `Authorization: Bearer <TOKEN>` on requests
+        #[arg(long, env = "MEDIAR_SERVE_TOKEN")]
+        token: Option<String>,
+    },
+    /// Report whether `mediar serve` is running, what it has queued and how recent runs went
+    Status {
+        /// Address of the server to ask
+        #[arg(long, default_value = serve::DEFAULT_LISTEN)]
+        server: String,
+        /// Token the server requires, as for `serve --token`
+        #[arg(long, env = "MEDIAR_SERVE_TOKEN")]
+        token: Option<String>,
+    },
+    /// Organize home videos into dated folders by when they were recorded, without TMDB
+    Personal(PersonalArgs),
+    /// Copy files missing from (or changed on) an rclone remote, e.g. `gdrive:Media`
+    Sync {
+        /// Organized local library
+        library: String,
+        /// rclone remote and path to sync to
+        remote: String,
+    },
+    /// Work with the checksum manifests written by `--write-checksums`
+    Checksum {
+        #[command(subcommand)]
+        command: ChecksumCommand,
+    },
+    /// Store or remove the TMDB API token
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AuthCommand {
+    /// Check a TMDB API token and store it in the OS keychain, or in the config directory
+    /// where there is none
+    Login {
+        /// The API Read Access Token or v3 API key; prompted for if omitted
+        #[arg(long, env = "TMDB_API_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        /// Store the token in the config directory even if there is a keychain
+        #[arg(long)]
+        file: bool,
+    },
+    /// Remove the stored token
+    Logout,
+}
+
+#[derive(Subcommand, Debug)]
+enum ChecksumCommand {
+    /// Re-hash the files listed in a library's manifests and report any that changed or are gone
+    Verify {
+        /// Library directory to search for `SHA256SUMS` and `checksums.sfv` manifests
+        library: String,
     },
 }
 
@@ -93,7 +776,7 @@ fn print_wrapped(start: ColoredString, text: ColoredString) {
             .subsequent_indent(&indent),
     );
     for line in lines {
-        println!("{}", line);
+        say!("{}", line);
     }
 }
 
@@ -102,6 +785,61 @@ fn print_wrapped(start: ColoredString, text: ColoredString) {
 struct Args {
     #[command(subcommand)]
     command: Commands,
+    /// Language for TMDB metadata such as episode and movie titles (e.g., de-DE)
+    #[arg(long, global = true)]
+    metadata_language: Option<String>,
+    /// Answer yes to every confirmation prompt
+    #[arg(short, long, global = true, env = "MEDIAR_ASSUME_YES", value_parser = BoolishValueParser::new())]
+    yes: bool,
+    /// Never prompt; fail instead when a choice or confirmation is needed
+    #[arg(long, global = true, env = "MEDIAR_NO_INPUT", value_parser = BoolishValueParser::new())]
+    no_input: bool,
+    /// When to color output
+    #[arg(long, global = true, value_enum, default_value_t)]
+    color: ColorChoice,
+    /// Never color output, same as `--color never`
+    #[arg(long, global = true, conflicts_with = "color")]
+    no_color: bool,
+    /// Log more detail to stderr: -v for each operation, -vv for TMDB requests and skipped files
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Log only errors to stderr
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Also write a detailed run log to this file, one JSON object per line
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<PathBuf>,
+    /// What to write to stdout
+    #[arg(long, global = true, value_enum, default_value_t)]
+    output: OutputFormat,
+    /// Wait for another run organizing the same source to finish instead of exiting
+    #[arg(long, global = true)]
+    wait: bool,
+    /// Use only TMDB data cached by earlier runs, failing for shows and movies never fetched
+    #[arg(long, global = true, env = "MEDIAR_OFFLINE", value_parser = BoolishValueParser::new())]
+    offline: bool,
+}
+
+/// When output is colored
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    /// Color when stdout is a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color, given `NO_COLOR` and whether stdout is a terminal
+    fn enabled(self, no_color_env: Option<&str>, is_terminal: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            // Per no-color.org, an empty NO_COLOR does not count
+            Self::Auto => no_color_env.is_none_or(str::is_empty) && is_terminal,
+        }
+    }
 }
 
 /// Print all operations with pagination for large lists
@@ -110,6 +848,7 @@ fn print_operations(mode: &Mode, operations: &[(PathBuf, PathBuf)]) -> Result<()
 
     for (index, (old, new)) in operations.iter().enumerate() {
         if index == MAX_DISPLAY
+            && prompt::interactive()
             && !Confirm::new("Show all operations?")
                 .with_default(false)
                 .prompt()?
@@ -130,205 +869,1290 @@ fn print_operations(mode: &Mode, operations: &[(PathBuf, PathBuf)]) -> Result<()
                 print_wrapped("Link ".clear(), old.to_string_lossy().dimmed().blue());
                 print_wrapped("  ↪  ".bold(), new.to_string_lossy().bold().blue());
             }
+            Mode::Reflink => {
+                print_wrapped("Clone ".clear(), old.to_string_lossy().dimmed().cyan());
+                print_wrapped("  ↪  ".bold(), new.to_string_lossy().bold().cyan());
+            }
+            Mode::Symlink => {
+                print_wrapped("Symlink ".clear(), old.to_string_lossy().dimmed().magenta());
+                print_wrapped("  ↪  ".bold(), new.to_string_lossy().bold().magenta());
+            }
         }
     }
 
     Ok(())
 }
 
+/// Print planned operations in the chosen preview style
+fn print_preview(mode: &Mode, operations: &[(PathBuf, PathBuf)], preview: Preview) -> Result<()> {
+    match preview {
+        Preview::List => print_operations(mode, operations),
+        Preview::Tree => {
+            let targets: Vec<PathBuf> = operations.iter().map(|(_, new)| new.clone()).collect();
+            for line in preview::render_tree(&targets) {
+                say!("{}", line);
+            }
+            Ok(())
+        }
+    }
+}
+
 /// Execute a file operation based on mode
-fn execute_operation(mode: &Mode, old: PathBuf, new: PathBuf) -> Result<()> {
+fn execute_operation(
+    mode: &Mode,
+    old: PathBuf,
+    new: PathBuf,
+    options: &OrganizeOptions,
+) -> Result<()> {
+    // Deep episode paths can pass Windows' MAX_PATH limit
+    let (old, new) = (filesystem::long_path(&old), filesystem::long_path(&new));
     let parent = new.parent().context("Failed to get parent")?;
     fs::create_dir_all(parent)?;
 
     match mode {
-        Mode::Copy => {
-            fs::copy(old, new)?;
-        }
+        Mode::Copy => match &options.remote {
+            Some(remote) => remote.pull(&old, &new, options.rate_limit)?,
+            None => {
+                transfer::resumable_copy(&old, &new, options.rate_limit)?;
+            }
+        },
         Mode::Move => {
             fs::rename(old, new)?;
         }
         Mode::Link => {
             fs::hard_link(old, new)?;
         }
+        Mode::Reflink => {
+            transfer::reflink(&old, &new, options.reflink_fallback, options.rate_limit)?;
+        }
+        Mode::Symlink => {
+            let original = std::path::absolute(&old)?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(original, new)?;
+            #[cfg(windows)]
+            std::os::windows::fs::symlink_file(original, new)?;
+        }
     }
     Ok(())
 }
 
+/// Let the user deselect individual operations, returning the ones to execute
+fn select_operations(
+    mode: &Mode,
+    operations: Vec<(PathBuf, PathBuf)>,
+) -> Result<Vec<(PathBuf, PathBuf)>> {
+    const PROMPT: &str = "Select operations to execute:";
+    prompt::require_input(PROMPT, "drop --select")?;
+
+    let verb = match mode {
+        Mode::Copy => "Copy",
+        Mode::Move => "Move",
+        Mode::Link => "Link",
+        Mode::Reflink => "Clone",
+        Mode::Symlink => "Symlink",
+    };
+    let labels: Vec<String> = operations
+        .iter()
+        .map(|(old, new)| {
+            format!(
+                "{} {} ↪ {}",
+                verb,
+                old.file_name().unwrap_or_default().to_string_lossy(),
+                new.to_string_lossy()
+            )
+        })
+        .collect();
+
+    let selected: HashSet<usize> = MultiSelect::new(PROMPT, labels)
+        .with_all_selected_by_default()
+        .with_page_size(15)
+        .raw_prompt()?
+        .into_iter()
+        .map(|option| option.index)
+        .collect();
+
+    Ok(operations
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| selected.contains(index))
+        .map(|(_, operation)| operation)
+        .collect())
+}
+
+/// Let the user correct or exclude files in a plan, returning whether it needs replanning
+fn review_plan(plan: &Plan, corrections: &mut Corrections, episodes: bool) -> Result<bool> {
+    const PROMPT: &str = "Review operations:";
+    const DONE: &str = "✓ Continue with these operations";
+    prompt::require_input(PROMPT, "drop --review")?;
+
+    // Files that could not be matched to an episode can be fixed too
+    let unmatched = plan
+        .skipped
+        .iter()
+        .filter_map(|(path, reason)| match reason {
+            SkipReason::NoMatch(reason) if episodes => {
+                Some((path, format!("(skipped: {})", reason)))
+            }
+            _ => None,
+        });
+    let entries: Vec<(&PathBuf, String)> = plan
+        .operations
+        .iter()
+        .map(|(old, new)| (old, new.to_string_lossy().into_owned()))
+        .chain(unmatched)
+        .collect();
+
+    let mut choices = vec![DONE.to_string()];
+    choices.extend(entries.iter().map(|(old, new)| {
+        format!(
+            "{} ↪ {}",
+            old.file_name().unwrap_or_default().to_string_lossy(),
+            new
+        )
+    }));
+
+    loop {
+        let choice = Select::new(PROMPT, choices.clone())
+            .with_page_size(15)
+            .raw_prompt()?;
+        let Some(old) = choice.index.checked_sub(1).map(|index| entries[index].0) else {
+            return Ok(false);
+        };
+
+        let mut actions = vec!["Exclude this file", "Back"];
+        if episodes {
+            actions.insert(0, "Change season and episode");
+        }
+        match Select::new(&choice.value, actions).prompt()? {
+            "Change season and episode" => {
+                let numbers = inquire::Text::new("Season and episode (e.g. S01E05):")
+                    .with_validator(|input: &str| {
+                        Ok(match parse_episode(Path::new(input)) {
+                            Ok(_) => inquire::validator::Validation::Valid,
+                            Err(_) => inquire::validator::Validation::Invalid(
+                                "Enter an episode code like S01E05".into(),
+                            ),
+                        })
+                    })
+                    .prompt()?;
+                corrections
+                    .episodes
+                    .insert(old.clone(), parse_episode(Path::new(&numbers))?);
+                return Ok(true);
+            }
+            "Exclude this file" => {
+                corrections.excluded.insert(old.clone());
+                return Ok(true);
+            }
+            _ => continue,
+        }
+    }
+}
+
 /// Prompt user for confirmation unless auto-confirmed
 fn confirm_operations(auto_confirm: bool) -> Result<bool> {
-    if auto_confirm {
+    if auto_confirm || prompt::assume_yes() {
         return Ok(true);
     }
 
+    prompt::require_input(
+        "Proceed with operations?",
+        "pass --yes or set MEDIAR_ASSUME_YES=1",
+    )?;
     Ok(Confirm::new("Proceed with operations?")
         .with_default(true)
         .prompt()?)
 }
 
-/// Common function to collect operations from source directory
-fn collect_operations<F>(source: &Path, mut path_builder: F) -> Result<Vec<(PathBuf, PathBuf)>>
-where
-    F: FnMut(&Path, &str) -> Result<Option<PathBuf>>,
-{
-    let mut operations: Vec<(PathBuf, PathBuf)> = Vec::new();
-    let mut seen_outputs: HashSet<PathBuf> = HashSet::new();
-
-    for entry in WalkDir::new(source).sort_by_file_name() {
-        let entry = entry?;
-        let old = entry.path().to_path_buf();
-
-        let Some(ext) = parse_extension(&old) else {
+/// Report the files left out of a plan, summarizing routine skips
+fn print_skipped(skipped: &[(PathBuf, SkipReason)]) {
+    let mut quiet = 0;
+    let mut up_to_date = 0;
+    for (path, reason) in skipped {
+        Event::Skipped {
+            source: path,
+            reason: reason.to_string(),
+        }
+        .emit();
+        if reason.is_quiet() {
+            quiet += 1;
             continue;
-        };
-
-        let Some(new) = path_builder(&old, &ext)? else {
+        }
+        // An organized library would otherwise list every file it holds
+        if *reason == SkipReason::UpToDate {
+            up_to_date += 1;
             continue;
-        };
-
-        if old != new {
-            if !new.exists() {
-                // Check if this output path has already been seen
-                if seen_outputs.contains(&new) {
-                    return Err(anyhow!(
-                        "Multiple input files map to the same output: {}",
-                        new.display()
-                    ));
-                }
-                seen_outputs.insert(new.clone());
-                operations.push((old, new));
-            } else {
-                print_wrapped("Skip ".clear(), old.to_string_lossy().yellow());
-                print_wrapped(
-                    "  ↪  ".bold(),
-                    format!("{} already exists", new.to_string_lossy())
-                        .bold()
-                        .yellow(),
-                );
-            }
         }
+        print_wrapped("Skip ".clear(), path.to_string_lossy().yellow());
+        print_wrapped("  ↪  ".bold(), reason.to_string().bold().yellow());
+    }
+    if quiet > 0 {
+        say!(
+            "{}",
+            format!("Ignored {} unsupported file(s)", quiet).dimmed()
+        );
+    }
+    if up_to_date > 0 {
+        say!(
+            "{} {} file(s) already up to date",
+            "✓".bold().green(),
+            up_to_date
+        );
     }
-
-    Ok(operations)
 }
 
-/// Execute all operations with confirmation
-fn execute_operations(
-    mode: &Mode,
-    operations: Vec<(PathBuf, PathBuf)>,
-    auto_confirm: bool,
-) -> Result<()> {
-    if operations.is_empty() {
-        println!("{} No files to process.", "✗".bold().yellow());
-        return Ok(());
+/// Number of hard links to a file (always 1 where this cannot be determined)
+fn link_count(path: &Path) -> Result<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Ok(fs::metadata(path)?.nlink())
     }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(1)
+    }
+}
 
-    print_operations(mode, &operations)?;
-
-    if !confirm_operations(auto_confirm)? {
-        println!("{} Cancelled.", "✗".bold().yellow());
-        return Ok(());
+/// Refuse to move files that are still hard-linked elsewhere, such as a torrent client's directory
+fn check_hardlinked_sources(operations: &[(PathBuf, PathBuf)]) -> Result<()> {
+    let mut hardlinked = 0;
+    for (old, _) in operations {
+        if link_count(old)? > 1 {
+            print_wrapped("Warn ".clear(), old.to_string_lossy().yellow());
+            print_wrapped(
+                "  ↪  ".bold(),
+                "has other hard links; moving it will break seeding"
+                    .bold()
+                    .yellow(),
+            );
+            hardlinked += 1;
+        }
     }
 
-    for (old, new) in operations {
-        execute_operation(mode, old, new)?;
+    if hardlinked > 0 {
+        return Err(anyhow!(
+            "{} source file(s) are hard-linked elsewhere. Use `mediar link` or `mediar copy` instead, or pass --allow-hardlinked to move them anyway",
+            hardlinked
+        ));
     }
 
-    println!("{} Done.", "✓".bold().green());
     Ok(())
 }
 
-fn organize_tv(
-    mode: Mode,
-    source: &Path,
-    target: Option<&Path>,
-    show: &Show,
-    auto_confirm: bool,
-) -> Result<()> {
-    let target = target
-        .or_else(|| Path::parent(source))
-        .context("Failed to determine target")?;
+/// Skip sources recorded in the state database by an earlier run
+fn skip_processed(plan: &mut Plan, options: &OrganizeOptions) -> Result<()> {
+    match &options.state_db {
+        Some(path) => StateDb::open(path)?.skip_processed(plan),
+        None => Ok(()),
+    }
+}
 
-    let episodes = show.episodes();
-    let title = sanitize(format!("{} ({})", show.name, show.year));
+/// With `--upgrade`, plan better sources over the existing targets they would skip
+fn upgrade_existing(plan: &mut Plan, options: &OrganizeOptions) -> Result<()> {
+    let Some(policy) = options.upgrade else {
+        return Ok(());
+    };
 
-    let operations = collect_operations(source, |old, ext| {
-        let episode_id = match parse_episode_id(old) {
-            Ok(episode_id) => episode_id,
-            Err(err) => {
-                print_wrapped("Skip ".clear(), old.to_string_lossy().yellow());
-                print_wrapped("  ↪  ".bold(), err.to_string().bold().yellow());
-                return Ok(None);
-            }
+    for upgrade in upgrade::apply(plan, policy)? {
+        let label = match policy {
+            UpgradePolicy::Replace => "Upgrade ",
+            UpgradePolicy::Version => "Add version ",
         };
+        print_wrapped(label.clear(), upgrade.existing.to_string_lossy().yellow());
+        print_wrapped(
+            "  ↪  ".bold(),
+            format!("{} → {}", upgrade.from, upgrade.to).bold().yellow(),
+        );
+    }
+    Ok(())
+}
 
-        let episode = episodes
-            .get(&episode_id)
-            .context(format!("Unable to get metadata for {:?}", episode_id))?;
-
-        let new = target
-            .to_path_buf()
-            .join(&title)
-            .join(format!("Season {:02}", episode.season_number))
-            .join(sanitize(format!(
-                "{} - {} - {}.{}",
-                show.name, episode_id, episode.name, ext
-            )));
+/// Rename destinations the target filesystem would reject, reporting each change
+fn adapt_to_target_fs(plan: &mut Plan, target: &Path, options: &OrganizeOptions) -> Result<()> {
+    let Some(target_fs) = options.target_fs.or_else(|| TargetFs::detect(target)) else {
+        return Ok(());
+    };
 
-        Ok(Some(new))
-    })?;
+    for alteration in filesystem::preflight(plan, target, target_fs)? {
+        print_wrapped("Adapt ".clear(), alteration.from.to_string_lossy().yellow());
+        print_wrapped(
+            "  ↪  ".bold(),
+            format!(
+                "{} ({})",
+                alteration.to.to_string_lossy(),
+                alteration.reasons.join(", ")
+            )
+            .bold()
+            .yellow(),
+        );
+    }
+    Ok(())
+}
 
-    execute_operations(&mode, operations, auto_confirm)
+fn emit_planned(mode: &Mode, operations: &[(PathBuf, PathBuf)]) {
+    for (old, new) in operations {
+        Event::Planned {
+            mode: *mode,
+            source: old,
+            target: new,
+        }
+        .emit();
+    }
 }
 
-fn organize_movie(
-    mode: Mode,
-    source: &Path,
-    target: Option<&Path>,
-    movie: &Movie,
-    auto_confirm: bool,
+/// Show a plan and write it to a plan file instead of executing it
+fn save_plan(
+    path: &Path,
+    plan_file: PlanFile,
+    plan: &Plan,
+    options: &OrganizeOptions,
 ) -> Result<()> {
-    let target = target
-        .or_else(|| Path::parent(source))
-        .context("Failed to determine target")?;
+    print_skipped(&plan.skipped);
+    print_preview(&plan_file.mode, &plan.operations, options.preview)?;
+    emit_planned(&plan_file.mode, &plan.operations);
+    plan_file.save(path)?;
+    say!(
+        "Saved {} operation(s) to {}; run `mediar apply {}` to execute them",
+        plan.operations.len(),
+        path.display(),
+        path.display()
+    );
+    Ok(())
+}
 
-    let year = movie
-        .release_date
-        .split('-')
-        .next()
-        .and_then(|y| y.parse::<i32>().ok())
-        .unwrap_or(0);
+/// Execute a plan file written by `--plan-out`
+fn apply_plan(path: &Path, wait: bool) -> Result<()> {
+    let plan_file = PlanFile::load(path)?;
+    let _lock = SourceLock::acquire(&plan_file.source, wait)?;
+    let mode = plan_file.mode;
+    let options = OrganizeOptions::from_settings(plan_file.settings.clone(), prompt::assume_yes());
+    execute_operations(&mode, plan_file.into_plan()?, &options)
+}
 
-    let title = sanitize(format!("{} ({})", movie.title, year));
+/// Finish a run that was interrupted, from its journal or source directory
+fn resume_run(path: &Path, auto_confirm: bool) -> Result<()> {
+    let path = if path.is_dir() {
+        JournalFile::path(path)
+    } else {
+        path.to_path_buf()
+    };
+    let journal = JournalFile::load(&path)?;
+    say!(
+        "Resuming: {} operation(s) already completed, {} left",
+        journal.completed.len(),
+        journal.remaining.len()
+    );
 
-    let operations = collect_operations(source, |_old, ext| {
-        let new = target
-            .to_path_buf()
-            .join(&title)
-            .join(sanitize(format!("{} ({}).{}", movie.title, year, ext)));
+    let options = OrganizeOptions::from_settings(journal.settings, auto_confirm);
+    let plan = Plan::from_operations(journal.source, journal.remaining, &journal.replaced)?;
+    execute_operations(&journal.mode, plan, &options)?;
+    // The run above replaces the journal next to its source and removes it when it finishes
+    if path.exists() {
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove journal {}", path.display()))?;
+    }
+    Ok(())
+}
 
-        Ok(Some(new))
-    })?;
+/// Execute all operations with confirmation
+fn execute_operations(mode: &Mode, mut plan: Plan, options: &OrganizeOptions) -> Result<()> {
+    print_skipped(&plan.skipped);
+
+    if let Some(count) = options.sample {
+        let planned = plan.operations.len();
+        plan.sample(count, &mut rand::rng());
+        say!(
+            "{}",
+            format!(
+                "Sampling {} of {} planned operation(s)",
+                plan.operations.len(),
+                planned
+            )
+            .bold()
+        );
+    }
+    plan.order_by_device();
+    let operations = std::mem::take(&mut plan.operations);
 
-    execute_operations(&mode, operations, auto_confirm)
-}
+    if operations.is_empty() {
+        say!("{} No files to process.", "✗".bold().yellow());
+        exit::record(Status::NothingToDo);
+        return Ok(());
+    }
 
-#[derive(Tabled)]
-struct SearchResultDisplay {
-    #[tabled(rename = "ID")]
-    id: i32,
-    #[tabled(rename = "")]
-    r#type: String,
-    #[tabled(rename = "Name")]
-    name: String,
-    #[tabled(rename = "🌐")]
-    language: String,
-    #[tabled(rename = "⭐")]
+    if matches!(mode, Mode::Move) && !options.allow_hardlinked {
+        check_hardlinked_sources(&operations)?;
+    }
+
+    let operations = if options.select_operations {
+        select_operations(mode, operations)?
+    } else {
+        print_preview(mode, &operations, options.preview)?;
+        if confirm_operations(options.auto_confirm)? {
+            operations
+        } else {
+            Vec::new()
+        }
+    };
+    if operations.is_empty() {
+        say!("{} Cancelled.", "✗".bold().yellow());
+        exit::record(Status::Cancelled);
+        return Ok(());
+    }
+    emit_planned(mode, &operations);
+
+    let today = chrono::Local::now().date_naive();
+    let trash_retention_days = options
+        .trash_retention_days
+        .filter(|_| matches!(mode, Mode::Move));
+    let journal = Mutex::new(Journal::default());
+    // Saved as operations finish, so a run that stops can be resumed; a remote run's plan is of
+    // placeholders, and running the same command again picks it up
+    let progress = options
+        .remote
+        .is_none()
+        .then(|| JournalFile {
+            mode: *mode,
+            source: plan.source.clone(),
+            settings: ExecutionSettings {
+                trash_retention_days,
+                ..options.settings()
+            },
+            completed: Vec::new(),
+            remaining: operations
+                .iter()
+                .map(|(source, target)| PlannedOperation {
+                    source: source.clone(),
+                    target: target.clone(),
+                })
+                .collect(),
+            replaced: operations
+                .iter()
+                .map(|(_, target)| target)
+                .filter(|target| plan.replaced.contains(*target))
+                .cloned()
+                .collect(),
+        })
+        .and_then(|journal_file| match journal_file.save() {
+            Ok(_) => Some(Mutex::new(journal_file)),
+            Err(err) => {
+                tracing::warn!(error = %format!("{:#}", err), "failed to write the journal, the run cannot be resumed");
+                None
+            }
+        });
+    let state = options
+        .state_db
+        .as_deref()
+        .map(StateDb::open)
+        .transpose()?
+        .map(Mutex::new);
+    let perform = |old: PathBuf, new: PathBuf| -> Result<Entry> {
+        let parent = new.parent().context("Failed to get parent")?;
+        Ok(if trash_retention_days.is_some() {
+            fs::create_dir_all(parent)?;
+            trash::stage_move(&plan.source, &old, &new, today, options.rate_limit)?;
+            Entry::Staged {
+                trashed: trash::trash_path(&plan.source, &old, today),
+                source: old,
+                target: new,
+            }
+        } else {
+            execute_operation(mode, old.clone(), new.clone(), options)?;
+            match mode {
+                Mode::Move => Entry::Moved {
+                    source: old,
+                    target: new,
+                },
+                Mode::Copy | Mode::Link | Mode::Reflink | Mode::Symlink => Entry::Created {
+                    source: old,
+                    target: new,
+                },
+            }
+        })
+    };
+    let execute = |old: PathBuf, new: PathBuf| -> Result<()> {
+        let parent = new.parent().context("Failed to get parent")?;
+        journal.lock().unwrap().record_dirs(parent);
+        // A target being upgraded is set aside rather than removed, so it survives a failure
+        let replaced = if plan.replaced.contains(&new) {
+            let aside = Entry::Moved {
+                source: new.clone(),
+                target: journal::aside_path(&new),
+            };
+            fs::rename(&new, aside.target())
+                .with_context(|| format!("Failed to set {} aside to replace it", new.display()))?;
+            Some(aside)
+        } else {
+            None
+        };
+        let entry = match perform(old, new) {
+            Ok(entry) => entry,
+            Err(err) => {
+                if let Some(aside) = replaced
+                    && let Err(restore_err) = aside.undo()
+                {
+                    tracing::error!(error = %format!("{:#}", restore_err), "failed to restore replaced file");
+                }
+                return Err(err);
+            }
+        };
+        // Journaled before anything else can fail, so --atomic, an interrupt and `resume` all
+        // know the operation was done, and --atomic undoes it when a hook fails
+        {
+            let mut journal = journal.lock().unwrap();
+            if let Some(aside) = replaced {
+                journal.record_replaced(aside);
+            }
+            journal.record(entry.clone());
+        }
+        if let Some(progress) = &progress
+            && let Err(err) = progress.lock().unwrap().complete(entry.target())
+        {
+            tracing::warn!(error = %format!("{:#}", err), "failed to update the journal");
+        }
+        tracing::info!(
+            mode = ?mode,
+            source = %entry.source().display(),
+            target = %entry.target().display(),
+            "executed operation"
+        );
+        Event::Executed {
+            mode: *mode,
+            source: entry.source(),
+            target: entry.target(),
+        }
+        .emit();
+        metrics::file_organized(*mode);
+        let file_hooks = options
+            .file_hooks
+            .iter()
+            .try_for_each(|command| hook::run_for_file(command, entry.source(), entry.target()));
+        // Not recording a source only means a later run does not skip it, or `refresh` does
+        // not find its placeholder
+        if let Some(state) = &state {
+            let state = state.lock().unwrap();
+            let recorded = state.record(entry.source(), entry.target()).and_then(|()| {
+                if plan.placeholders.contains(entry.target()) {
+                    state.record_placeholder(entry.target())?;
+                }
+                Ok(())
+            });
+            if let Err(err) = recorded {
+                tracing::warn!(source = %entry.source().display(), error = %format!("{:#}", err), "failed to record in the state database");
+            }
+        }
+        file_hooks
+    };
+    // Without --keep-going the first failure stops the run
+    let execute_batch = |batch: Vec<(PathBuf, PathBuf)>| -> Result<Vec<(PathBuf, anyhow::Error)>> {
+        let mut failures = Vec::new();
+        for (old, new) in batch {
+            interrupt::check()?;
+            if let Err(err) = execute(old.clone(), new) {
+                tracing::error!(source = %old.display(), error = %format!("{:#}", err), "operation failed");
+                metrics::operation_failed();
+                Event::Failed {
+                    source: &old,
+                    error: format!("{:#}", err),
+                }
+                .emit();
+                if !options.keep_going {
+                    return Err(err);
+                }
+                print_wrapped("Fail ".clear(), old.to_string_lossy().red());
+                print_wrapped("  ↪  ".bold(), format!("{:#}", err).bold().red());
+                failures.push((old, err));
+            }
+        }
+        Ok(failures)
+    };
+
+    let total = operations.len();
+    let executing = interrupt::Executing::start();
+    let result = if options.parallel_devices {
+        let groups = group_by_device(operations);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = groups
+                .into_iter()
+                .map(|group| scope.spawn(|| execute_batch(group)))
+                .collect();
+            handles
+                .into_iter()
+                .try_fold(Vec::new(), |mut failures, handle| {
+                    failures.extend(
+                        handle
+                            .join()
+                            .map_err(|_| anyhow!("Worker thread panicked"))??,
+                    );
+                    Ok::<_, anyhow::Error>(failures)
+                })
+        })
+    } else {
+        execute_batch(operations)
+    };
+    drop(executing);
+    let progress = progress.map(|progress| progress.into_inner().unwrap());
+    let failures = match result {
+        Ok(failures) => failures,
+        Err(err) if options.atomic => {
+            let journal = journal.into_inner().unwrap();
+            let completed = journal.len();
+            for (entry, rollback_err) in journal.rollback() {
+                print_wrapped("Fail ".clear(), entry.target().to_string_lossy().red());
+                print_wrapped("  ↪  ".bold(), format!("{:#}", rollback_err).bold().red());
+            }
+            if let Some(progress) = &progress {
+                remove_journal(progress);
+            }
+            return Err(err.context(format!("Rolled back {} completed operation(s)", completed)));
+        }
+        Err(err) => {
+            journal.into_inner().unwrap().discard_replaced();
+            if let Some(progress) = &progress {
+                print_resume_hint(progress, total);
+            }
+            return Err(err);
+        }
+    };
+    let mut journal = journal.into_inner().unwrap();
+    journal.discard_replaced();
+    if let Some(progress) = &progress {
+        if failures.is_empty() {
+            remove_journal(progress);
+        } else {
+            print_resume_hint(progress, total);
+        }
+    }
+
+    if let Some(retention_days) = trash_retention_days {
+        for removed in trash::prune(&plan.source, retention_days, today)? {
+            print_wrapped("Purge ".clear(), removed.to_string_lossy().dimmed());
+        }
+    }
+
+    let targets: Vec<PathBuf> = journal
+        .entries()
+        .iter()
+        .map(|entry| entry.target().to_path_buf())
+        .collect();
+    notify::record(Organized {
+        media: plan.media.clone(),
+        source: plan.source.clone(),
+        targets: targets.clone(),
+        failed: failures.len(),
+    });
+
+    if let Some(format) = options.write_checksums {
+        for manifest in checksum::write(&targets, format)? {
+            print_wrapped("Checksum ".clear(), manifest.to_string_lossy().dimmed());
+        }
+    }
+
+    if options.keep_going {
+        print_summary(total - failures.len(), &failures, plan.skipped.len());
+        if !failures.is_empty() {
+            exit::record(Status::PartialFailure);
+            return Err(anyhow!(
+                "{} of {} operation(s) failed",
+                failures.len(),
+                total
+            ));
+        }
+    }
+
+    say!("{} Done.", "✓".bold().green());
+    exit::record(Status::Done);
+
+    if !options.post_hooks.is_empty() {
+        let summary = RunSummary {
+            mode: *mode,
+            source: &plan.source,
+            media: plan.media.as_ref(),
+            targets,
+            skipped: plan.skipped.len(),
+        };
+        for command in &options.post_hooks {
+            print_wrapped("Hook ".clear(), command.normal());
+            hook::run(command, &summary)?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete the journal of a run that has nothing left to resume
+fn remove_journal(journal_file: &JournalFile) {
+    let path = JournalFile::path(&journal_file.source);
+    if let Err(err) = fs::remove_file(&path) {
+        tracing::warn!(path = %path.display(), %err, "failed to remove the journal");
+    }
+}
+
+/// Point at the journal of a run that stopped before finishing its operations
+fn print_resume_hint(journal_file: &JournalFile, total: usize) {
+    say!(
+        "{} Completed {} of {} operation(s); run `mediar resume {}` to do the other {}",
+        "✗".bold().yellow(),
+        journal_file.completed.len(),
+        total,
+        JournalFile::path(&journal_file.source).display(),
+        journal_file.remaining.len()
+    );
+}
+
+#[derive(Tabled)]
+struct SummaryDisplay {
+    #[tabled(rename = "Succeeded")]
+    succeeded: usize,
+    #[tabled(rename = "Failed")]
+    failed: usize,
+    #[tabled(rename = "Skipped")]
+    skipped: usize,
+}
+
+#[derive(Tabled)]
+struct FailureDisplay {
+    #[tabled(rename = "File")]
+    file: String,
+    #[tabled(rename = "Error")]
+    error: String,
+}
+
+/// Summarize a `--keep-going` run, listing each failure
+fn print_summary(succeeded: usize, failures: &[(PathBuf, anyhow::Error)], skipped: usize) {
+    let summary = [SummaryDisplay {
+        succeeded,
+        failed: failures.len(),
+        skipped,
+    }];
+    say!("\n{}", Table::new(summary).with(Style::rounded()));
+
+    if !failures.is_empty() {
+        let failures: Vec<_> = failures
+            .iter()
+            .map(|(old, err)| FailureDisplay {
+                file: old.to_string_lossy().into_owned(),
+                error: format!("{:#}", err),
+            })
+            .collect();
+        say!("{}", Table::new(failures).with(Style::rounded()));
+    }
+}
+
+#[derive(Tabled)]
+struct QueuedDisplay {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Attempts")]
+    attempts: usize,
+    #[tabled(rename = "Next retry")]
+    retry_at: String,
+    #[tabled(rename = "Error")]
+    error: String,
+}
+
+#[derive(Tabled)]
+struct RunDisplay {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "At")]
+    at: String,
+    #[tabled(rename = "Error")]
+    outcome: String,
+}
+
+/// Report on the `mediar serve` listening at `server`
+async fn print_server_status(server: &str, token: Option<&str>) -> Result<()> {
+    let Some(status) = serve::fetch_status(server, token).await? else {
+        say!(
+            "{} No mediar server is running at {}.",
+            "✗".bold().yellow(),
+            server
+        );
+        exit::record(Status::NothingToDo);
+        return Ok(());
+    };
+    say!(
+        "{} mediar serve is running at {}.",
+        "✓".bold().green(),
+        server
+    );
+
+    match &status.last_success {
+        Some(run) => print_wrapped(
+            "Last successful run: ".clear(),
+            format!("{} at {} ({})", run.path, run.at, run.outcome).normal(),
+        ),
+        None => say!("No successful run since the server started."),
+    }
+
+    if status.queued.is_empty() {
+        say!("Nothing is waiting for metadata.");
+    } else {
+        say!("\n{} waiting for metadata:", status.queued.len());
+        let queued = status.queued.into_iter().map(|entry| QueuedDisplay {
+            path: entry.path,
+            attempts: entry.attempts,
+            retry_at: entry.retry_at,
+            error: entry.error,
+        });
+        say!("{}", Table::new(queued).with(Style::rounded()));
+    }
+
+    if !status.recent_errors.is_empty() {
+        say!("\nRecent errors:");
+        let errors = status.recent_errors.into_iter().map(|run| RunDisplay {
+            path: run.path,
+            at: run.at,
+            outcome: run.outcome,
+        });
+        say!("{}", Table::new(errors).with(Style::rounded()));
+    }
+    Ok(())
+}
+
+/// Target path for bonus content, filed under its extras folder in the title directory
+///
+/// Returns `None` for regular content.
+fn extra_path(
+    title_dir: &Path,
+    title: &str,
+    source: &Path,
+    old: &Path,
+    ext: &str,
+    trailers: TrailerPolicy,
+) -> Option<Destination> {
+    let relative = old.strip_prefix(source).unwrap_or(old);
+    let kind = parse_extra(relative)?;
+    let file_name = old.file_name()?.to_string_lossy();
+
+    Some(match (kind, trailers) {
+        (ExtraKind::Trailers, TrailerPolicy::Skip) => {
+            Destination::Skip(SkipReason::Ignored("trailers are skipped"))
+        }
+        (ExtraKind::Trailers, TrailerPolicy::Inline) => {
+            Destination::To(title_dir.join(sanitize(format!("{}-trailer.{}", title, ext))))
+        }
+        _ => Destination::To(title_dir.join(kind.folder()).join(sanitize(file_name))),
+    })
+}
+
+/// Evaluate the configured rules for a source file
+fn rule_action<'a>(
+    options: &'a OrganizeOptions,
+    source: &Path,
+    old: &Path,
+    content: ContentType,
+    title: &str,
+) -> Option<&'a RuleAction> {
+    let relative = old.strip_prefix(source).unwrap_or(old);
+    options.rules.evaluate(&RuleInput {
+        content,
+        title,
+        relative,
+        resolution: options
+            .rules
+            .uses_resolution()
+            .then(|| detect_resolution(old))
+            .flatten(),
+    })
+}
+
+/// Pass a destination through the naming script, if one is configured
+fn script_path(
+    options: &OrganizeOptions,
+    new: PathBuf,
+    input: impl FnOnce() -> ScriptInput,
+) -> Result<PathBuf> {
+    match &options.script {
+        Some(script) => script.apply(&new, &input()),
+        None => Ok(new),
+    }
+}
+
+/// Episode title used for episodes that are not on TMDB yet
+const PLACEHOLDER_TITLE: &str = "TBA";
+
+/// Hard links cannot cross filesystems; fail early or fall back before planning
+fn link_mode(mode: Mode, source: &Path, target: &Path, fallback: LinkFallback) -> Result<Mode> {
+    if mode != Mode::Link || device_of(source) == device_of(target) {
+        return Ok(mode);
+    }
+    let (fallback_mode, action) = match fallback {
+        LinkFallback::Fail => {
+            return Err(anyhow!(
+                "Cannot hard link from {} to {}: they are on different filesystems; \
+                 use `copy`, or pass `--fallback copy` or `--fallback symlink`",
+                source.display(),
+                target.display()
+            ));
+        }
+        LinkFallback::Copy => (Mode::Copy, "copying"),
+        LinkFallback::Symlink => (Mode::Symlink, "symlinking"),
+    };
+    say!(
+        "{}",
+        format!(
+            "Source and target are on different filesystems; {} instead of hard linking",
+            action
+        )
+        .yellow()
+    );
+    Ok(fallback_mode)
+}
+
+/// Plan a show's files into the target, ready to execute, or `None` once the plan is saved
+/// with `--plan-out`
+fn prepare_tv(
+    mode: Mode,
+    source: &Path,
+    target: Option<&Path>,
+    show: &Show,
+    options: &OrganizeOptions,
+) -> Result<Option<(Mode, Plan)>> {
+    let target = target
+        .or_else(|| Path::parent(source))
+        .context("Failed to determine target")?;
+    let mode = link_mode(mode, source, target, options.link_fallback)?;
+
+    let mut corrections = Corrections::default();
+    let mut plan = loop {
+        let plan = plan_tv(source, target, show, options, &corrections)?;
+        if !options.review || !review_plan(&plan, &mut corrections, true)? {
+            break plan;
+        }
+    };
+    skip_processed(&mut plan, options)?;
+    if let Some(settle) = options.stable_for {
+        plan.skip_growing(settle);
+    }
+    upgrade_existing(&mut plan, options)?;
+    adapt_to_target_fs(&mut plan, target, options)?;
+    if let Some(path) = &options.plan_out {
+        save_plan(
+            path,
+            PlanFile::new(mode, ContentType::Show, show.id, &plan, options.settings()),
+            &plan,
+            options,
+        )?;
+        return Ok(None);
+    }
+    plan.media = Some(Media {
+        content: ContentType::Show,
+        tmdb_id: show.id,
+        title: show.name.clone(),
+    });
+    Ok(Some((mode, plan)))
+}
+
+/// Infer the episode of a file without an SxxEyy code from the episode title in its name
+///
+/// Low-confidence matches are only used if the user confirms them.
+fn episode_from_title(
+    old: &Path,
+    show: &Show,
+    episodes: &[&TvSeasonEpisode],
+    options: &OrganizeOptions,
+) -> Result<Option<(i32, i32)>> {
+    let Some(file_title) = parse_title(old) else {
+        return Ok(None);
+    };
+    let Some((episode, score)) =
+        match_episode_title(&file_title, &show.name, episodes, |episode| &episode.name)
+    else {
+        return Ok(None);
+    };
+
+    if score < EPISODE_TITLE_CONFIDENCE {
+        if options.auto_confirm || !prompt::interactive() {
+            return Ok(None);
+        }
+        let question = format!(
+            "Is {} {} - {}?",
+            old.file_name().unwrap_or_default().to_string_lossy(),
+            episode_id(episode.season_number, episode.episode_number),
+            episode.name
+        );
+        if !Confirm::new(&question).with_default(false).prompt()? {
+            return Ok(None);
+        }
+    }
+    Ok(Some((episode.season_number, episode.episode_number)))
+}
+
+/// Plan where each file of a show goes under the target directory
+fn plan_tv(
+    source: &Path,
+    target: &Path,
+    show: &Show,
+    options: &OrganizeOptions,
+    corrections: &Corrections,
+) -> Result<Plan> {
+    let episodes = show.episodes();
+    let mut episode_list: Vec<&TvSeasonEpisode> = episodes.values().copied().collect();
+    episode_list.sort_by_key(|episode| (episode.season_number, episode.episode_number));
+    let name = options.title_style.title(
+        &show.name,
+        show.original_name.as_deref(),
+        options.transliterate,
+    );
+    let title = format!("{} ({})", name, show.year);
+    let folder = options.title_dir(&name, show.year, show.id);
+
+    let style = if options.match_existing_style {
+        EpisodeStyle::detect_in(&target.join(&folder)).unwrap_or_default()
+    } else {
+        EpisodeStyle::default()
+    };
+
+    let mut placeholders = HashSet::new();
+    let mut plan = Plan::collect(source, &options.filter, |old, ext| {
+        if corrections.excluded.contains(old) {
+            return Ok(Destination::Skip(SkipReason::Ignored(
+                "excluded during review",
+            )));
+        }
+        if ext == DISC {
+            return Ok(Destination::Skip(SkipReason::Ignored(
+                "disc rips are only organized as movies",
+            )));
+        }
+        let action = rule_action(options, source, old, ContentType::Show, &show.name);
+        if action.is_some_and(|action| action.skip) {
+            return Ok(Destination::Skip(SkipReason::Ignored("skipped by rule")));
+        }
+        let show_dir = action
+            .and_then(|action| action.target.as_deref())
+            .unwrap_or(target)
+            .join(&folder);
+
+        if let Some(new) = extra_path(&show_dir, &title, source, old, ext, options.trailers) {
+            return Ok(new);
+        }
+
+        let parsed = match corrections.episodes.get(old) {
+            Some(numbers) => Ok(*numbers),
+            None => {
+                parse_episode(old).map(|(season, episode)| options.map_episode(season, episode))
+            }
+        };
+        let (season_number, episode_number) = match parsed {
+            Ok(numbers) => numbers,
+            Err(err) => match episode_from_title(old, show, &episode_list, options)? {
+                Some(numbers) => numbers,
+                None => return Ok(Destination::Skip(SkipReason::NoMatch(err.to_string()))),
+            },
+        };
+        let episode_id = episode_id(season_number, episode_number);
+
+        let episode_name = match episodes.get(&episode_id) {
+            Some(episode) => episode.name.as_str(),
+            // Newly aired episodes may not be on TMDB yet
+            None if options.placeholder_missing => PLACEHOLDER_TITLE,
+            None => return Err(anyhow!("Unable to get metadata for {:?}", episode_id)),
+        };
+        let placeholder = !episodes.contains_key(&episode_id);
+
+        let new = show_dir
+            .join(format!("Season {:02}", season_number))
+            .join(sanitize(style.format(
+                &name,
+                season_number,
+                episode_number,
+                episode_name,
+                &tagged_extension(old, ext),
+            )));
+        let new = script_path(options, new, || ScriptInput {
+            content: ContentType::Show,
+            title: name.clone(),
+            year: show.year,
+            id: show.id,
+            season: Some(season_number),
+            episode: Some(episode_number),
+            episode_title: Some(episode_name.to_string()),
+            edition: None,
+            source: old.strip_prefix(source).unwrap_or(old).to_path_buf(),
+        })?;
+
+        if placeholder {
+            placeholders.insert(new.clone());
+        }
+        Ok(Destination::To(new))
+    })?;
+    plan.placeholders = placeholders;
+    Ok(plan)
+}
+
+/// Plan a movie's files into the target, ready to execute, or `None` once the plan is saved
+/// with `--plan-out`
+fn prepare_movie(
+    mode: Mode,
+    source: &Path,
+    target: Option<&Path>,
+    movie: &Movie,
+    options: &OrganizeOptions,
+) -> Result<Option<(Mode, Plan)>> {
+    let target = target
+        .or_else(|| Path::parent(source))
+        .context("Failed to determine target")?;
+    let mode = link_mode(mode, source, target, options.link_fallback)?;
+
+    let mut corrections = Corrections::default();
+    let mut plan = loop {
+        let plan = plan_movie(source, target, movie, options, &corrections)?;
+        if !options.review || !review_plan(&plan, &mut corrections, false)? {
+            break plan;
+        }
+    };
+    skip_processed(&mut plan, options)?;
+    if let Some(settle) = options.stable_for {
+        plan.skip_growing(settle);
+    }
+    upgrade_existing(&mut plan, options)?;
+    adapt_to_target_fs(&mut plan, target, options)?;
+    if let Some(path) = &options.plan_out {
+        save_plan(
+            path,
+            PlanFile::new(
+                mode,
+                ContentType::Movie,
+                movie.id,
+                &plan,
+                options.settings(),
+            ),
+            &plan,
+            options,
+        )?;
+        return Ok(None);
+    }
+    plan.media = Some(Media {
+        content: ContentType::Movie,
+        tmdb_id: movie.id,
+        title: movie.title.clone(),
+    });
+    Ok(Some((mode, plan)))
+}
+
+/// Plan where each file of a movie goes under the target directory
+fn plan_movie(
+    source: &Path,
+    target: &Path,
+    movie: &Movie,
+    options: &OrganizeOptions,
+    corrections: &Corrections,
+) -> Result<Plan> {
+    let year = movie
+        .release_date
+        .split('-')
+        .next()
+        .and_then(|y| y.parse::<i32>().ok())
+        .unwrap_or(0);
+
+    let name = options.title_style.title(
+        &movie.title,
+        movie.original_title.as_deref(),
+        options.transliterate,
+    );
+    let title = format!("{} ({})", name, year);
+    let folder = options.title_dir(&name, year, movie.id);
+
+    // Titles like "Part 1" are part of the movie name, not a multi-part rip
+    let title_has_part = part_marker(&movie.title).is_some();
+
+    Plan::collect(source, &options.filter, |old, ext| {
+        if corrections.excluded.contains(old) {
+            return Ok(Destination::Skip(SkipReason::Ignored(
+                "excluded during review",
+            )));
+        }
+        let action = rule_action(options, source, old, ContentType::Movie, &movie.title);
+        if action.is_some_and(|action| action.skip) {
+            return Ok(Destination::Skip(SkipReason::Ignored("skipped by rule")));
+        }
+        let title_dir = action
+            .and_then(|action| action.target.as_deref())
+            .unwrap_or(target)
+            .join(&folder);
+
+        // Disc rips keep their layout, e.g. `Title (Year)/BDMV/...`, as Kodi and Plex expect
+        if ext == DISC
+            && let Some(disc) = disc_path(old.strip_prefix(source).unwrap_or(old))
+        {
+            return Ok(Destination::To(title_dir.join(disc)));
+        }
+
+        if let Some(new) = extra_path(&title_dir, &title, source, old, ext, options.trailers) {
+            return Ok(new);
+        }
+
+        let edition_name = action
+            .and_then(|action| action.edition.as_deref())
+            .or_else(|| parse_edition(old));
+        let edition = edition_name
+            .map(|edition| options.edition_style.format(edition))
+            .unwrap_or_default();
+
+        let part = parse_part(old)
+            .filter(|_| !title_has_part)
+            .map(|part| format!(" - {}", part))
+            .unwrap_or_default();
+
+        let version = match options.version_suffix {
+            // A sidecar takes its video's resolution, so the two are still paired once renamed
+            Some(VersionSuffix::Quality) => is_sidecar(old)
+                .then(|| sidecar_video(old))
+                .flatten()
+                .and_then(|video| detect_resolution(&video))
+                .or_else(|| detect_resolution(old))
+                .map(|resolution| format!(" - {}", resolution))
+                .unwrap_or_default(),
+            None => String::new(),
+        };
+
+        let new = title_dir.join(sanitize(format!(
+            "{} ({}){}{}{}.{}",
+            name,
+            year,
+            edition,
+            version,
+            part,
+            tagged_extension(old, ext)
+        )));
+        let new = script_path(options, new, || ScriptInput {
+            content: ContentType::Movie,
+            title: name.clone(),
+            year,
+            id: movie.id,
+            season: None,
+            episode: None,
+            episode_title: None,
+            edition: edition_name.map(str::to_string),
+            source: old.strip_prefix(source).unwrap_or(old).to_path_buf(),
+        })?;
+
+        Ok(Destination::To(new))
+    })
+}
+
+#[derive(Tabled)]
+struct SearchResultDisplay {
+    #[tabled(rename = "ID")]
+    id: i32,
+    #[tabled(rename = "")]
+    r#type: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "🌐")]
+    language: String,
+    #[tabled(rename = "⭐")]
     popularity: String,
     #[tabled(rename = "Year")]
     year: String,
     #[tabled(rename = "TMDB Link")]
     link: String,
+    #[tabled(skip)]
+    content_type: ContentType,
 }
 
 impl From<TvSearchResult> for SearchResultDisplay {
@@ -350,6 +2174,7 @@ impl From<TvSearchResult> for SearchResultDisplay {
                 .and_then(|date| date.split('-').next().map(|s| s.to_string()))
                 .unwrap_or_default(),
             link: format!("https://www.themoviedb.org/tv/{}", result.id),
+            content_type: ContentType::Show,
         }
     }
 }
@@ -373,10 +2198,79 @@ impl From<MovieSearchResult> for SearchResultDisplay {
                 .and_then(|date| date.split('-').next().map(|s| s.to_string()))
                 .unwrap_or_default(),
             link: format!("https://www.themoviedb.org/movie/{}", result.id),
+            content_type: ContentType::Movie,
         }
     }
 }
 
+/// Choose a search result, a mode and directories, then organize as if given `--tv-id`/`--movie-id`
+async fn pick_and_organize(
+    client: &TmdbClient,
+    config: &Config,
+    results: &[SearchResultDisplay],
+    memory: &mut MatchMemory,
+    wait: bool,
+) -> Result<()> {
+    prompt::require_input(
+        "Select a result:",
+        "pass its ID to link, copy or move with --tv-id or --movie-id",
+    )?;
+    let rows = choices(results, |result| {
+        format!(
+            "{} {} ({}) - ID: {}",
+            result.r#type, result.name, result.year, result.id
+        )
+    });
+    let result = &results[Select::new("Select a result:", rows).prompt()?.index];
+
+    let modes = vec!["link", "copy", "move", "reflink"];
+    let mode = match Select::new("How should files be organized?", modes).prompt()? {
+        "copy" => Mode::Copy,
+        "move" => Mode::Move,
+        "reflink" => Mode::Reflink,
+        _ => Mode::Link,
+    };
+    let source = inquire::Text::new("Source directory:")
+        .with_validator(|input: &str| {
+            Ok(if Path::new(input).exists() {
+                inquire::validator::Validation::Valid
+            } else {
+                inquire::validator::Validation::Invalid("No such file or directory".into())
+            })
+        })
+        .prompt()?;
+    let target = inquire::Text::new("Target directory:")
+        .with_help_message("Leave empty to use the configured library")
+        .prompt()?;
+
+    let args = picked_args(&source, &target, result.content_type, result.id)?;
+    let options = OrganizeOptions::try_from(&args)?;
+    let _lock = SourceLock::acquire(Path::new(&source), wait)?;
+    organize(client, config, mode, args, options, memory).await
+}
+
+/// Arguments for organizing `source` as the picked title, as if given on the command line
+fn picked_args(
+    source: &str,
+    target: &str,
+    content_type: ContentType,
+    id: i32,
+) -> Result<OrganizeArgs> {
+    let id_flag = match content_type {
+        ContentType::Show => "--tv-id",
+        ContentType::Movie => "--movie-id",
+    };
+    let id = id.to_string();
+    let mut argv = vec!["mediar", source];
+    if !target.trim().is_empty() {
+        argv.push(target.trim());
+    }
+    argv.extend([id_flag, id.as_str()]);
+    let matches =
+        OrganizeArgs::augment_args(clap::Command::new("mediar")).try_get_matches_from(argv)?;
+    Ok(OrganizeArgs::from_arg_matches(&matches)?)
+}
+
 fn filter_and_sort_search_results(
     results: Vec<SearchResultDisplay>,
     language: &Option<String>,
@@ -436,103 +2330,434 @@ fn filter_and_sort_search_results(
     filtered
 }
 
+/// How to avoid interactive selection when prompts are unavailable
+const SELECTION_HINT: &str = "pass --tv-id or --movie-id, or use --auto or --first";
+
+/// Characters of a result's overview shown in selection prompts
+const OVERVIEW_SNIPPET_LEN: usize = 60;
+
+/// Shorten text to at most `max` characters, ending in `…` when cut
+fn snippet(text: &str, max: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// One line describing a search result in a selection prompt
+fn result_row(
+    name: &str,
+    year: Option<i32>,
+    language: Option<&str>,
+    id: i32,
+    popularity: Option<f64>,
+    overview: &str,
+) -> String {
+    let mut row = format!(
+        "{} ({}) - {} - ID: {} - Popularity: {:.1}",
+        name,
+        year.map_or_else(|| "N/A".to_string(), |year| year.to_string()),
+        language.unwrap_or("N/A"),
+        id,
+        popularity.unwrap_or(0.0)
+    );
+    if !overview.trim().is_empty() {
+        row.push_str(" - ");
+        row.push_str(&snippet(overview, OVERVIEW_SNIPPET_LEN));
+    }
+    row
+}
+
+/// A row in a selection prompt, remembering which item it stands for
+struct Choice {
+    index: usize,
+    label: String,
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// Label items for a selection prompt
+fn choices<T>(items: &[T], label: impl Fn(&T) -> String) -> Vec<Choice> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| Choice {
+            index,
+            label: label(item),
+        })
+        .collect()
+}
+
 /// Generic interactive selection helper for search results
-fn select_from_results<T>(
-    results: &[T],
+fn select_from_results<'a, T>(
+    results: &'a [T],
     prompt: &str,
     no_results_msg: &str,
     format_option: impl Fn(&T) -> String,
     get_name: impl Fn(&T) -> &str,
     get_id: impl Fn(&T) -> i32,
-) -> Result<i32> {
+    first: bool,
+) -> Result<&'a T> {
     if results.is_empty() {
         return Err(anyhow!("{}", no_results_msg));
     }
 
-    // Format options for selection
-    let options: Vec<String> = results.iter().map(&format_option).collect();
-
-    let selection = Select::new(prompt, options).with_page_size(10).prompt()?;
-
-    // Extract the ID from the selection
-    let selected_index = results
-        .iter()
-        .position(|result| format_option(result) == selection)
-        .context("Failed to find selected item")?;
+    let selected_index = if first {
+        0
+    } else {
+        prompt::require_input(prompt, SELECTION_HINT)?;
+        Select::new(prompt, choices(results, format_option))
+            .with_page_size(10)
+            .with_help_message("↑↓ to move, type to filter, enter to select")
+            .prompt()?
+            .index
+    };
 
     let selected_result = &results[selected_index];
-    println!(
+    say!(
         "Selected: {} (ID: {})",
         get_name(selected_result).green(),
         get_id(selected_result)
     );
 
-    Ok(get_id(selected_result))
+    Ok(selected_result)
 }
 
-/// Interactive selection for TV shows
-async fn select_tv_show(client: &TmdbClient, query: &str) -> Result<Show> {
-    let response = client.search_tv(query).await?;
-
-    let id = select_from_results(
-        &response.results,
-        "Select a TV show:",
-        &format!("No TV shows found for query: {}", query),
-        |result| {
-            let year = result
-                .first_air_date
-                .as_ref()
-                .and_then(|date| date.split('-').next())
-                .unwrap_or("N/A");
-            format!(
-                "{} ({}) - ID: {} - Popularity: {:.1}",
-                result.name,
-                year,
-                result.id,
-                result.popularity.unwrap_or(0.0)
-            )
-        },
-        |result| &result.name,
-        |result| result.id,
-    )?;
+/// How search results are chosen during auto-detection
+#[derive(Debug, Default, Clone, Copy)]
+struct SelectionOptions {
+    /// Minimum confidence to pick a result without prompting (`--auto`)
+    auto_confidence: Option<f64>,
+    /// Take the top search result without prompting (`--first`)
+    first: bool,
+    /// Search results to fetch (`--search-limit`, default: one page)
+    search_limit: Option<usize>,
+}
+
+impl SelectionOptions {
+    /// Whether the detected type and title are used without prompting
+    fn trust_detected(&self) -> bool {
+        self.first || self.auto_confidence.is_some()
+    }
+}
+
+/// Move results released in the given year to the front, keeping TMDB's order otherwise
+fn prefer_year<T>(results: &mut [T], year: Option<i32>, result_year: impl Fn(&T) -> Option<i32>) {
+    if year.is_some() {
+        results.sort_by_key(|result| result_year(result) != year);
+    }
+}
+
+fn print_auto_match(name: &str, id: i32, score: f64) {
+    say!(
+        "Matched: {} (ID: {}, {:.0}% confidence)",
+        name.green(),
+        id,
+        score * 100.0
+    );
+}
+
+/// Interactive selection for TV shows
+async fn select_tv_show(
+    client: &TmdbClient,
+    query: &str,
+    year: Option<i32>,
+    selection: &SelectionOptions,
+) -> Result<Show> {
+    let limit = selection.search_limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let mut response = client.search_tv(query, year, limit).await?;
+    if response.results.is_empty() && year.is_some() {
+        // The year in the filename may be off, e.g. a season's year rather than the premiere
+        response = client.search_tv(query, None, limit).await?;
+    }
+    prefer_year(&mut response.results, year, TvSearchResult::year);
+
+    if let Some(min_confidence) = selection.auto_confidence
+        && let Some((result, score)) = best_match(&response.results, min_confidence, |result| {
+            confidence(
+                query,
+                year,
+                &result.name,
+                result.year(),
+                result.popularity.unwrap_or(0.0),
+            )
+        })
+    {
+        print_auto_match(&result.name, result.id, score);
+        return client.show(result.id).await;
+    }
 
-    client.show(id).await
+    // Similar titles are told apart by their details before one is used
+    loop {
+        let id = select_from_results(
+            &response.results,
+            "Select a TV show:",
+            &format!("No TV shows found for query: {}", query),
+            |result| {
+                result_row(
+                    &result.name,
+                    result.year(),
+                    result.original_language.as_deref(),
+                    result.id,
+                    result.popularity,
+                    &result.overview,
+                )
+            },
+            |result| &result.name,
+            |result| result.id,
+            selection.first,
+        )?
+        .id;
+        if selection.first || prompt::assume_yes() {
+            return client.show(id).await;
+        }
+        // The details shown are reused for the show, so only its seasons are fetched after
+        let series = client.series(id).await?;
+        if confirm_pick(
+            &series.name,
+            "First aired",
+            &series.first_air_date,
+            &series.genres,
+            &series.overview,
+            poster_preview(client, series.poster_path.as_deref()).await,
+        )? {
+            return client.show_of(series).await;
+        }
+    }
 }
 
 /// Interactive selection for movies
-async fn select_movie(client: &TmdbClient, query: &str) -> Result<Movie> {
-    let response = client.search_movie(query).await?;
-
-    let id = select_from_results(
-        &response.results,
-        "Select a movie:",
-        &format!("No movies found for query: {}", query),
-        |result| {
-            let year = result
-                .release_date
-                .as_ref()
-                .and_then(|date| date.split('-').next())
-                .unwrap_or("N/A");
-            format!(
-                "{} ({}) - ID: {} - Popularity: {:.1}",
-                result.title,
+async fn select_movie(
+    client: &TmdbClient,
+    query: &str,
+    year: Option<i32>,
+    selection: &SelectionOptions,
+) -> Result<Movie> {
+    let limit = selection.search_limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let mut response = client.search_movie(query, year, limit).await?;
+    if response.results.is_empty() && year.is_some() {
+        response = client.search_movie(query, None, limit).await?;
+    }
+    prefer_year(&mut response.results, year, MovieSearchResult::year);
+
+    if let Some(min_confidence) = selection.auto_confidence
+        && let Some((result, score)) = best_match(&response.results, min_confidence, |result| {
+            confidence(
+                query,
                 year,
-                result.id,
-                result.popularity.unwrap_or(0.0)
+                &result.title,
+                result.year(),
+                result.popularity.unwrap_or(0.0),
             )
-        },
-        |result| &result.title,
-        |result| result.id,
-    )?;
+        })
+    {
+        print_auto_match(&result.title, result.id, score);
+        return client.movie(result.id).await;
+    }
+
+    loop {
+        let id = select_from_results(
+            &response.results,
+            "Select a movie:",
+            &format!("No movies found for query: {}", query),
+            |result| {
+                result_row(
+                    &result.title,
+                    result.year(),
+                    result.original_language.as_deref(),
+                    result.id,
+                    result.popularity,
+                    &result.overview,
+                )
+            },
+            |result| &result.title,
+            |result| result.id,
+            selection.first,
+        )?
+        .id;
+        let movie = client.movie(id).await?;
+        if selection.first || prompt::assume_yes() {
+            return Ok(movie);
+        }
+        let poster = poster_preview(client, movie.poster_path.as_deref()).await;
+        if confirm_pick(
+            &movie.title,
+            "Released",
+            &movie.release_date,
+            &movie.genres,
+            &movie.overview,
+            poster,
+        )? {
+            return Ok(movie);
+        }
+    }
+}
+
+/// Interactive selection among shows and movies ranked together by TMDB
+async fn select_any(
+    client: &TmdbClient,
+    query: &str,
+    year: Option<i32>,
+    selection: &SelectionOptions,
+) -> Result<Content> {
+    let limit = selection.search_limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let mut results = client.search_multi(query, limit).await?.results;
+    prefer_year(&mut results, year, MultiSearchResult::year);
+
+    if let Some(min_confidence) = selection.auto_confidence
+        && let Some((result, score)) = best_match(&results, min_confidence, |result| {
+            confidence(
+                query,
+                year,
+                result.name(),
+                result.year(),
+                result.popularity(),
+            )
+        })
+    {
+        print_auto_match(result.name(), result.id(), score);
+        return fetch_content(client, result).await;
+    }
+
+    loop {
+        let result = select_from_results(
+            &results,
+            "Select a TV show or movie:",
+            &format!("No TV shows or movies found for query: {}", query),
+            |result| match result {
+                MultiSearchResult::Tv(show) => format!(
+                    "📺 {}",
+                    result_row(
+                        &show.name,
+                        show.year(),
+                        show.original_language.as_deref(),
+                        show.id,
+                        show.popularity,
+                        &show.overview,
+                    )
+                ),
+                MultiSearchResult::Movie(movie) => format!(
+                    "🎬 {}",
+                    result_row(
+                        &movie.title,
+                        movie.year(),
+                        movie.original_language.as_deref(),
+                        movie.id,
+                        movie.popularity,
+                        &movie.overview,
+                    )
+                ),
+                MultiSearchResult::Other => String::new(),
+            },
+            MultiSearchResult::name,
+            MultiSearchResult::id,
+            selection.first,
+        )?;
+        if selection.first || prompt::assume_yes() {
+            return fetch_content(client, result).await;
+        }
+        match result {
+            MultiSearchResult::Tv(show) => {
+                let series = client.series(show.id).await?;
+                if confirm_pick(
+                    &series.name,
+                    "First aired",
+                    &series.first_air_date,
+                    &series.genres,
+                    &series.overview,
+                    poster_preview(client, series.poster_path.as_deref()).await,
+                )? {
+                    return Ok(Content::Show(client.show_of(series).await?));
+                }
+            }
+            _ => {
+                let movie = client.movie(result.id()).await?;
+                if confirm_pick(
+                    &movie.title,
+                    "Released",
+                    &movie.release_date,
+                    &movie.genres,
+                    &movie.overview,
+                    poster_preview(client, movie.poster_path.as_deref()).await,
+                )? {
+                    return Ok(Content::Movie(movie));
+                }
+            }
+        }
+    }
+}
+
+/// Fetch the show or movie a multi-search result stands for
+async fn fetch_content(client: &TmdbClient, result: &MultiSearchResult) -> Result<Content> {
+    Ok(match result {
+        MultiSearchResult::Tv(show) => Content::Show(client.show(show.id).await?),
+        _ => Content::Movie(client.movie(result.id()).await?),
+    })
+}
+
+/// A title's poster as terminal graphics, when the terminal can draw images
+///
+/// Posters are a nicety, so one that cannot be fetched or drawn is left out.
+async fn poster_preview(client: &TmdbClient, poster_path: Option<&str>) -> Option<String> {
+    let protocol = graphics::Protocol::current()?;
+    let image = client.poster(poster_path?).await.ok()?;
+    protocol.render(&image, graphics::POSTER_COLUMNS).ok()
+}
 
-    client.movie(id).await
+/// Show a picked title's details and ask whether to use it
+fn confirm_pick(
+    name: &str,
+    date_label: &str,
+    date: &str,
+    genres: &[Genre],
+    overview: &str,
+    poster: Option<String>,
+) -> Result<bool> {
+    let label = |label: &str| format!("{:<12}", label).bold();
+    say!();
+    if let Some(poster) = poster {
+        say!("{}", poster);
+    }
+    print_wrapped(label("Title"), name.green());
+    if !date.is_empty() {
+        print_wrapped(label(date_label), date.normal());
+    }
+    if !genres.is_empty() {
+        let genres: Vec<&str> = genres.iter().map(|genre| genre.name.as_str()).collect();
+        print_wrapped(label("Genres"), genres.join(", ").normal());
+    }
+    if !overview.is_empty() {
+        print_wrapped(label("Overview"), overview.normal());
+    }
+    Ok(Confirm::new("Use this title?")
+        .with_default(true)
+        .prompt()?)
 }
 
+/// How many folders deep title detection looks for a video unless `--max-depth` says otherwise
+const DETECT_DEPTH: usize = 3;
+
 /// Auto-detect and select content (TV show or movie)
-async fn auto_detect_and_select(client: &TmdbClient, source: &Path) -> Result<Content> {
+async fn auto_detect_and_select(
+    client: &TmdbClient,
+    source: &Path,
+    filter: &PathFilter,
+    selection: &SelectionOptions,
+    memory: &mut MatchMemory,
+) -> Result<Content> {
     // Find a video file to analyze
     let mut sample_video: Option<PathBuf> = None;
-    for entry in WalkDir::new(source).max_depth(3) {
+    let walker = filter
+        .walk(source, DETECT_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !filter.is_hidden(entry.file_name()));
+    for entry in walker {
         let entry = entry?;
         if parse_extension(entry.path()).is_some() {
             sample_video = Some(entry.path().to_path_buf());
@@ -545,8 +2770,43 @@ async fn auto_detect_and_select(client: &TmdbClient, source: &Path) -> Result<Co
     let detected_title = parse_title(&sample_video).unwrap_or_default();
 
     let detected_type = parse_content_type(&sample_video);
+    let detected_year = parse_year(&sample_video);
+
+    // Release folders tagged with a provider ID need no search at all
+    if let Some(id) = parse_external_id(&sample_video) {
+        return fetch_by_external_id(client, id, detected_type).await;
+    }
+
+    // Reuse the choice made earlier in this run for the same title instead of prompting again
+    if let Some((content_type, id)) = memory.recall(&detected_title) {
+        return Ok(match content_type {
+            ContentType::Show => {
+                let show = client.show(id).await?;
+                say!("Using earlier match: {} (ID: {})", show.name.green(), id);
+                Content::Show(show)
+            }
+            ContentType::Movie => {
+                let movie = client.movie(id).await?;
+                say!("Using earlier match: {} (ID: {})", movie.title.green(), id);
+                Content::Movie(movie)
+            }
+        });
+    }
 
-    let selected_type = Select::new("Search for:", vec![ContentType::Show, ContentType::Movie])
+    // In auto or first mode, trust the detected type and title
+    let (selected_type, title) = if selection.trust_detected() && !detected_title.is_empty() {
+        say!("Detected: {} {}", detected_type, detected_title.green());
+        let selected_type = match detected_type {
+            ContentType::Show => SearchType::Tv,
+            ContentType::Movie => SearchType::Movie,
+        };
+        (selected_type, detected_title.clone())
+    } else {
+        prompt::require_input("Search for:", SELECTION_HINT)?;
+        let selected_type = Select::new(
+            "Search for:",
+            vec![SearchType::Tv, SearchType::Movie, SearchType::All],
+        )
         .with_starting_cursor(if detected_type == ContentType::Show {
             0
         } else {
@@ -554,190 +2814,1634 @@ async fn auto_detect_and_select(client: &TmdbClient, source: &Path) -> Result<Co
         })
         .prompt()?;
 
-    let title = inquire::Text::new(&format!("{} Title:", selected_type))
-        .with_initial_value(&detected_title)
-        .prompt()?;
+        let prompt = match selected_type {
+            SearchType::All => "Title:".to_string(),
+            _ => format!("{} Title:", selected_type),
+        };
+        let title = inquire::Text::new(&prompt)
+            .with_initial_value(&detected_title)
+            .prompt()?;
+
+        (selected_type, title)
+    };
 
-    match selected_type {
-        ContentType::Show => {
-            let show = select_tv_show(client, &title).await?;
-            Ok(Content::Show(show))
+    let content = match selected_type {
+        SearchType::Tv => {
+            Content::Show(select_tv_show(client, &title, detected_year, selection).await?)
         }
-        ContentType::Movie => {
-            let movie = select_movie(client, &title).await?;
-            Ok(Content::Movie(movie))
+        SearchType::Movie => {
+            Content::Movie(select_movie(client, &title, detected_year, selection).await?)
+        }
+        SearchType::All => select_any(client, &title, detected_year, selection).await?,
+    };
+
+    if !detected_title.is_empty() {
+        match &content {
+            Content::Show(show) => memory.remember(&detected_title, ContentType::Show, show.id),
+            Content::Movie(movie) => memory.remember(&detected_title, ContentType::Movie, movie.id),
         }
     }
+    Ok(content)
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let _ = dotenvy::dotenv();
-    let args = Args::parse();
+/// Fetch the show or movie named by an ID tag, using the detected type for TMDB IDs
+async fn fetch_by_external_id(
+    client: &TmdbClient,
+    id: ExternalId,
+    detected_type: ContentType,
+) -> Result<Content> {
+    let content = match id {
+        ExternalId::Tmdb(id) => match detected_type {
+            ContentType::Show => Content::Show(client.show(id).await?),
+            ContentType::Movie => Content::Movie(client.movie(id).await?),
+        },
+        ExternalId::Imdb(imdb_id) => {
+            let found = client.find_imdb(&imdb_id).await?;
+            let show_id = found.tv_results.first().map(|result| result.id);
+            let movie_id = found.movie_results.first().map(|result| result.id);
+            match (detected_type, show_id, movie_id) {
+                (ContentType::Show, Some(id), _) | (_, Some(id), None) => {
+                    Content::Show(client.show(id).await?)
+                }
+                (_, _, Some(id)) => Content::Movie(client.movie(id).await?),
+                (_, None, None) => return Err(anyhow!("Nothing on TMDB for IMDb ID {}", imdb_id)),
+            }
+        }
+    };
 
-    let client = TmdbClient::new()?;
+    match &content {
+        Content::Show(show) => say!("Tagged: {} (ID: {})", show.name.green(), show.id),
+        Content::Movie(movie) => say!("Tagged: {} (ID: {})", movie.title.green(), movie.id),
+    }
+    Ok(content)
+}
 
-    match args.command {
-        Commands::Search {
-            query,
-            language,
-            min_popularity,
-        } => {
-            // Search both TV and movies in parallel
-            let (tv_response, movie_response) =
-                tokio::join!(client.search_tv(&query), client.search_movie(&query));
+#[derive(Tabled)]
+struct SeasonDisplay {
+    #[tabled(rename = "Season")]
+    season: i32,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Episodes")]
+    episodes: usize,
+    #[tabled(rename = "Air Date")]
+    air_date: String,
+}
 
-            let tv_response = tv_response?;
-            let movie_response = movie_response?;
+impl From<&TvSeason> for SeasonDisplay {
+    fn from(season: &TvSeason) -> Self {
+        Self {
+            season: season.season_number,
+            name: season.name.clone(),
+            episodes: season.episodes.len(),
+            air_date: season.air_date.clone().unwrap_or_else(|| "N/A".to_string()),
+        }
+    }
+}
 
-            // Convert all results to SearchResultDisplay
-            let tv_results: Vec<SearchResultDisplay> = tv_response
-                .results
-                .into_iter()
-                .map(SearchResultDisplay::from)
-                .collect();
+#[derive(Tabled)]
+struct EpisodeDisplay {
+    #[tabled(rename = "Episode")]
+    code: String,
+    #[tabled(rename = "Title")]
+    title: String,
+    #[tabled(rename = "Air Date")]
+    air_date: String,
+}
 
-            let movie_results: Vec<SearchResultDisplay> = movie_response
-                .results
-                .into_iter()
-                .map(SearchResultDisplay::from)
-                .collect();
+impl From<&TvSeasonEpisode> for EpisodeDisplay {
+    fn from(episode: &TvSeasonEpisode) -> Self {
+        Self {
+            code: episode_id(episode.season_number, episode.episode_number),
+            title: episode.name.clone(),
+            air_date: episode
+                .air_date
+                .clone()
+                .unwrap_or_else(|| "N/A".to_string()),
+        }
+    }
+}
 
-            // Combine all results
-            let mut all_results = Vec::new();
-            all_results.extend(tv_results);
-            all_results.extend(movie_results);
+fn print_episodes(seasons: &[TvSeason]) {
+    let episodes: Vec<EpisodeDisplay> = seasons
+        .iter()
+        .flat_map(|season| &season.episodes)
+        .map(EpisodeDisplay::from)
+        .collect();
 
-            // Filter and sort combined results
-            let filtered_results =
-                filter_and_sort_search_results(all_results, &language, min_popularity, &query);
+    if episodes.is_empty() {
+        say!("No episodes found.");
+        return;
+    }
 
-            if filtered_results.is_empty() {
-                println!("No results found for: {}", query.yellow());
-                return Ok(());
-            }
+    let table = Table::new(&episodes).with(Style::rounded()).to_string();
+    say!("\n{}", table);
+}
 
-            let total_results = tv_response.total_results + movie_response.total_results;
+/// Human-readable size, e.g. `1.5 GiB`
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
 
-            let table = Table::new(&filtered_results)
-                .with(Style::rounded())
-                .to_string();
-            println!("\n{}", table);
-            println!(
-                "\nFound {} results ({} TV, {} movies)",
-                total_results, tv_response.total_results, movie_response.total_results
-            );
-            Ok(())
+#[derive(Tabled)]
+struct TitleStatsDisplay {
+    #[tabled(rename = "Title")]
+    name: String,
+    #[tabled(rename = "")]
+    r#type: String,
+    #[tabled(rename = "Seasons")]
+    seasons: String,
+    #[tabled(rename = "Episodes")]
+    episodes: String,
+    #[tabled(rename = "Size")]
+    size: String,
+}
+
+impl From<&TitleStats> for TitleStatsDisplay {
+    fn from(stats: &TitleStats) -> Self {
+        let (seasons, episodes) = if stats.is_show {
+            (
+                stats
+                    .seasons
+                    .iter()
+                    .map(|season| season.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                stats.episodes.to_string(),
+            )
+        } else {
+            (String::new(), String::new())
+        };
+        Self {
+            name: stats.name.clone(),
+            r#type: if stats.is_show { "📺" } else { "🎬" }.to_string(),
+            seasons,
+            episodes,
+            size: format_size(stats.size),
         }
-        Commands::Move {
-            source,
-            target,
-            tv_id,
-            movie_id,
-            yes,
-        } => {
-            let source = Path::new(&source);
-            let target = target.as_ref().map(Path::new);
+    }
+}
 
-            match (tv_id, movie_id) {
-                (Some(id), None) => {
-                    let show = client.show(id).await?;
-                    organize_tv(Mode::Move, source, target, &show, yes)
-                }
-                (None, Some(id)) => {
-                    let movie = client.movie(id).await?;
-                    organize_movie(Mode::Move, source, target, &movie, yes)
-                }
-                (Some(_), Some(_)) => Err(anyhow!("Cannot specify both --tv-id and --movie-id")),
-                (None, None) => {
-                    // Auto-detect and select
-                    match auto_detect_and_select(&client, source).await? {
-                        Content::Show(show) => organize_tv(Mode::Move, source, target, &show, yes),
-                        Content::Movie(movie) => {
-                            organize_movie(Mode::Move, source, target, &movie, yes)
-                        }
-                    }
-                }
-            }
+#[derive(Tabled)]
+struct ExtensionDisplay {
+    #[tabled(rename = "Extension")]
+    extension: String,
+    #[tabled(rename = "Files")]
+    files: usize,
+}
+
+fn print_library_stats(library: &Path) -> Result<()> {
+    let stats = library::title_dirs(library)?
+        .iter()
+        .map(|dir| library::title_stats(dir))
+        .collect::<Result<Vec<_>>>()?;
+
+    let titles: Vec<_> = stats.iter().map(TitleStatsDisplay::from).collect();
+    say!("{}", Table::new(&titles).with(Style::rounded()));
+
+    let mut extensions: BTreeMap<&str, usize> = BTreeMap::new();
+    for title in &stats {
+        for (ext, count) in &title.extensions {
+            *extensions.entry(ext).or_default() += count;
         }
-        Commands::Copy {
-            source,
-            target,
-            tv_id,
-            movie_id,
-            yes,
-        } => {
-            let source = Path::new(&source);
-            let target = target.as_ref().map(Path::new);
+    }
+    let extensions: Vec<_> = extensions
+        .into_iter()
+        .map(|(ext, files)| ExtensionDisplay {
+            extension: ext.to_string(),
+            files,
+        })
+        .collect();
+    say!("\n{}", Table::new(&extensions).with(Style::rounded()));
+
+    let shows: Vec<_> = stats.iter().filter(|title| title.is_show).collect();
+    say!(
+        "\n{} shows ({} episodes), {} movies, {} total",
+        shows.len(),
+        shows.iter().map(|show| show.episodes).sum::<usize>(),
+        stats.len() - shows.len(),
+        format_size(stats.iter().map(|title| title.size).sum())
+    );
+    Ok(())
+}
 
-            match (tv_id, movie_id) {
-                (Some(id), None) => {
-                    let show = client.show(id).await?;
-                    organize_tv(Mode::Copy, source, target, &show, yes)
-                }
-                (None, Some(id)) => {
-                    let movie = client.movie(id).await?;
-                    organize_movie(Mode::Copy, source, target, &movie, yes)
-                }
-                (Some(_), Some(_)) => Err(anyhow!("Cannot specify both --tv-id and --movie-id")),
-                (None, None) => {
-                    // Auto-detect and select
-                    match auto_detect_and_select(&client, source).await? {
-                        Content::Show(show) => organize_tv(Mode::Copy, source, target, &show, yes),
-                        Content::Movie(movie) => {
-                            organize_movie(Mode::Copy, source, target, &movie, yes)
-                        }
-                    }
-                }
-            }
+fn print_link(label: &str, url: String) {
+    say!("{} {}", format!("{}:", label).bold(), url.blue());
+}
+
+fn print_show_info(show: &Show, external_ids: &ExternalIds) {
+    say!("\n{}", format!("📺 {} ({})", show.name, show.year).bold());
+    print_wrapped("".clear(), show.overview.as_str().clear());
+
+    let seasons: Vec<SeasonDisplay> = show.seasons.iter().map(SeasonDisplay::from).collect();
+    let table = Table::new(&seasons).with(Style::rounded()).to_string();
+    say!("\n{}", table);
+    say!(
+        "\nFirst aired {}, {} seasons, {} episodes\n",
+        show.first_air_date,
+        show.number_of_seasons,
+        show.number_of_episodes
+    );
+
+    print_link("TMDB", format!("https://www.themoviedb.org/tv/{}", show.id));
+    if let Some(imdb_id) = &external_ids.imdb_id {
+        print_link("IMDb", format!("https://www.imdb.com/title/{}", imdb_id));
+    }
+    if let Some(tvdb_id) = external_ids.tvdb_id {
+        print_link(
+            "TVDB",
+            format!("https://thetvdb.com/dereferrer/series/{}", tvdb_id),
+        );
+    }
+}
+
+fn print_movie_info(movie: &Movie) {
+    let year = year_of(&movie.release_date).unwrap_or(0);
+    say!("\n{}", format!("🎬 {} ({})", movie.title, year).bold());
+    print_wrapped("".clear(), movie.overview.as_str().clear());
+    say!(
+        "\nReleased {}, language {}, popularity {:.1}\n",
+        movie.release_date,
+        movie.original_language,
+        movie.popularity
+    );
+
+    print_link(
+        "TMDB",
+        format!("https://www.themoviedb.org/movie/{}", movie.id),
+    );
+    if let Some(imdb_id) = &movie.imdb_id {
+        print_link("IMDb", format!("https://www.imdb.com/title/{}", imdb_id));
+    }
+}
+
+/// Find the TMDB show for an organized show directory
+async fn resolve_show_dir(
+    client: &TmdbClient,
+    show_dir: &Path,
+    tv_id: Option<i32>,
+    selection: &SelectionOptions,
+) -> Result<Show> {
+    if !show_dir.is_dir() {
+        return Err(anyhow!("Not a directory: {}", show_dir.display()));
+    }
+
+    let name = dir_name(show_dir)?;
+    if let Some(id) = tv_id.or_else(|| tmdb_tag(&name)) {
+        return client.show(id).await;
+    }
+
+    let title = title_from(&name).unwrap_or_default();
+    select_tv_show(client, &title, year_from(&name), selection).await
+}
+
+/// Find the TMDB movie for an organized movie directory
+async fn resolve_movie_dir(
+    client: &TmdbClient,
+    movie_dir: &Path,
+    selection: &SelectionOptions,
+) -> Result<Movie> {
+    let name = dir_name(movie_dir)?;
+    if let Some(id) = tmdb_tag(&name) {
+        return client.movie(id).await;
+    }
+
+    let title = title_from(&name).unwrap_or_default();
+    select_movie(client, &title, year_from(&name), selection).await
+}
+
+fn dir_name(dir: &Path) -> Result<String> {
+    Ok(dir
+        .file_name()
+        .context("Failed to get directory name")?
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// TMDB ID from a `{tmdb-N}` or `[tmdbid-N]` tag in a folder name
+fn tmdb_tag(name: &str) -> Option<i32> {
+    match external_id(name) {
+        Some(ExternalId::Tmdb(id)) => Some(id),
+        _ => None,
+    }
+}
+
+/// Options for re-checking names inside an already organized library
+fn library_options(config: &Config, auto_confirm: bool) -> OrganizeOptions {
+    OrganizeOptions {
+        auto_confirm,
+        // Keep each show's own episode naming; only titles, numbers and years are checked
+        match_existing_style: true,
+        id_tag: config.id_tag,
+        ..Default::default()
+    }
+}
+
+/// Title folders `verify` looks up on TMDB at once
+const VERIFY_LOOKUPS: usize = 4;
+
+/// Plan the expected names for one show or movie folder of a library
+async fn verify_title_dir(
+    client: &TmdbClient,
+    library: &Path,
+    dir: &Path,
+    options: &OrganizeOptions,
+    selection: &SelectionOptions,
+) -> Result<Plan> {
+    if library::is_show_dir(dir) {
+        let show = resolve_show_dir(client, dir, None, selection).await?;
+        plan_tv(dir, library, &show, options, &Corrections::default())
+    } else {
+        let movie = resolve_movie_dir(client, dir, selection).await?;
+        plan_movie(dir, library, &movie, options, &Corrections::default())
+    }
+}
+
+/// Report (and optionally rename) library files whose names differ from the expected ones
+///
+/// Up to [`VERIFY_LOOKUPS`] folders are looked up on TMDB at once. With a state database,
+/// correctly named folders are recorded as they are checked, so an interrupted verify picks up
+/// where it stopped.
+async fn verify_library(
+    client: &TmdbClient,
+    config: &Config,
+    library: &Path,
+    state: Option<&StateDb>,
+    fix: bool,
+    auto_confirm: bool,
+) -> Result<()> {
+    let options = &library_options(config, auto_confirm);
+    let selection = &SelectionOptions {
+        auto_confidence: Some(config.auto_confidence.unwrap_or(DEFAULT_MIN_CONFIDENCE)),
+        ..Default::default()
+    };
+
+    let mut dirs = Vec::new();
+    let mut resumed = 0;
+    for dir in library::title_dirs(library)? {
+        if let Some(state) = state
+            && state.is_verified(&dir)?
+        {
+            resumed += 1;
+        } else {
+            dirs.push(dir);
         }
-        Commands::Link {
-            source,
-            target,
-            tv_id,
-            movie_id,
-            yes,
-        } => {
-            let source = Path::new(&source);
-            let target = target.as_ref().map(Path::new);
+    }
+    if resumed > 0 {
+        say!(
+            "Resuming: {} folder(s) were verified by an interrupted run",
+            resumed
+        );
+    }
 
-            match (tv_id, movie_id) {
-                (Some(id), None) => {
-                    let show = client.show(id).await?;
-                    organize_tv(Mode::Link, source, target, &show, yes)
-                }
-                (None, Some(id)) => {
-                    let movie = client.movie(id).await?;
-                    organize_movie(Mode::Link, source, target, &movie, yes)
-                }
-                (Some(_), Some(_)) => Err(anyhow!("Cannot specify both --tv-id and --movie-id")),
-                (None, None) => {
-                    // Auto-detect and select
-                    match auto_detect_and_select(&client, source).await? {
-                        Content::Show(show) => organize_tv(Mode::Link, source, target, &show, yes),
-                        Content::Movie(movie) => {
-                            organize_movie(Mode::Link, source, target, &movie, yes)
-                        }
-                    }
+    let mut checks = stream::iter(&dirs)
+        .map(|dir| async move {
+            let result = verify_title_dir(client, library, dir, options, selection).await;
+            (dir, result)
+        })
+        .buffered(VERIFY_LOOKUPS);
+
+    let mut mismatched = Plan::default();
+    let mut failed = 0;
+    let mut checked = 0;
+    while let Some((dir, result)) = checks.next().await {
+        checked += 1;
+        let progress = format!("[{}/{}]", checked, dirs.len()).dimmed();
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        match result {
+            Ok(plan) if plan.operations.is_empty() => {
+                say!("{} {} {}", progress, "✓".bold().green(), name);
+                if let Some(state) = state {
+                    state.record_verified(dir)?;
                 }
             }
+            Ok(plan) => {
+                say!(
+                    "{} {} {} ({} file(s) misnamed)",
+                    progress,
+                    "✗".bold().yellow(),
+                    name,
+                    plan.operations.len()
+                );
+                mismatched.operations.extend(plan.operations);
+            }
+            Err(err) => {
+                say!("{} {} {}: {:#}", progress, "✗".bold().red(), name, err);
+                failed += 1;
+            }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::tmdb::{Movie, Show, TvSeason, TvSeasonEpisode};
-    use std::fs;
-    use tempfile::TempDir;
+    say!(
+        "\nChecked {} folder(s): {} misnamed file(s), {} folder(s) could not be checked",
+        dirs.len(),
+        mismatched.operations.len(),
+        failed
+    );
 
-    fn create_test_show() -> Show {
-        Show {
-            id: 42,
-            name: "Show Name".to_string(),
-            overview: "Test show".to_string(),
-            year: 2008,
+    if fix {
+        execute_operations(&Mode::Move, mismatched, options)?;
+    } else {
+        print_operations(&Mode::Move, &mismatched.operations)?;
+    }
+    // Folders that could not be checked are still owed; otherwise the next verify starts over
+    if let Some(state) = state
+        && failed == 0
+    {
+        state.forget_verified(library)?;
+    }
+    Ok(())
+}
+
+/// Rename the files of an organized show whose canonical names changed on TMDB
+async fn refresh_show(
+    client: &TmdbClient,
+    config: &Config,
+    show_dir: &Path,
+    tv_id: Option<i32>,
+    state: Option<&StateDb>,
+    auto_confirm: bool,
+) -> Result<()> {
+    let show = resolve_show_dir(client, show_dir, tv_id, &SelectionOptions::default()).await?;
+    let options = OrganizeOptions {
+        // Episodes still missing from TMDB keep their placeholder names
+        placeholder_missing: true,
+        ..library_options(config, auto_confirm)
+    };
+    rename_show(show_dir, &show, &options, state)
+}
+
+/// Rename a show's files to their names in `show`, then forget the placeholders that got a title
+fn rename_show(
+    show_dir: &Path,
+    show: &Show,
+    options: &OrganizeOptions,
+    state: Option<&StateDb>,
+) -> Result<()> {
+    let library = show_dir.parent().context("Failed to get parent")?;
+    let plan = plan_tv(show_dir, library, show, options, &Corrections::default())?;
+
+    if plan.operations.is_empty() {
+        say!("{} {} is up to date", "✓".bold().green(), show.name);
+    } else {
+        execute_operations(&Mode::Move, plan, options)?;
+    }
+    if let Some(state) = state {
+        state.forget_placeholders(show_dir)?;
+    }
+    Ok(())
+}
+
+/// Refresh every show with episodes recorded as named with a placeholder title
+///
+/// Each show is refreshed on its own and a failure does not stop the others.
+async fn refresh_placeholders(
+    client: &TmdbClient,
+    config: &Config,
+    state: &StateDb,
+    auto_confirm: bool,
+) -> Result<()> {
+    // Placeholders are episodes, in a season folder of their show
+    let show_dirs: BTreeSet<PathBuf> = state
+        .placeholders()?
+        .iter()
+        .filter_map(|target| Some(target.parent()?.parent()?.to_path_buf()))
+        .collect();
+    if show_dirs.is_empty() {
+        say!("{} No placeholder episodes to refresh", "✓".bold().green());
+        exit::record(Status::NothingToDo);
+        return Ok(());
+    }
+
+    let mut failures = 0;
+    for show_dir in show_dirs {
+        interrupt::check()?;
+        // The show was removed or renamed since; its placeholders are gone with it
+        if !show_dir.is_dir() {
+            state.forget_placeholders(&show_dir)?;
+            continue;
+        }
+        say!("\n{}", show_dir.to_string_lossy().bold());
+        if let Err(err) =
+            refresh_show(client, config, &show_dir, None, Some(state), auto_confirm).await
+        {
+            print_wrapped("Fail ".clear(), show_dir.to_string_lossy().red());
+            print_wrapped("  ↪  ".bold(), format!("{:#}", err).bold().red());
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        exit::record(Status::PartialFailure);
+        return Err(anyhow!("{} show(s) failed to refresh", failures));
+    }
+    Ok(())
+}
+
+async fn report_missing(client: &TmdbClient, show_dir: &Path, tv_id: Option<i32>) -> Result<()> {
+    let show = resolve_show_dir(client, show_dir, tv_id, &SelectionOptions::default()).await?;
+    let existing = library::existing_episodes(show_dir)?;
+    let today = chrono::Local::now().date_naive();
+    let missing = library::missing_episodes(&show, &existing, today);
+
+    if missing.is_empty() {
+        say!(
+            "{} All aired episodes of {} are present.",
+            "✓".bold().green(),
+            show.name
+        );
+        return Ok(());
+    }
+
+    let episodes: Vec<EpisodeDisplay> = missing.into_iter().map(EpisodeDisplay::from).collect();
+    let table = Table::new(&episodes).with(Style::rounded()).to_string();
+    say!("\n{}", table);
+    say!(
+        "\n{} {} aired episode(s) missing",
+        "✗".bold().yellow(),
+        episodes.len()
+    );
+    Ok(())
+}
+
+/// The release folders and files in a drop folder, leaving out hidden entries such as
+/// mediar's own journal and trash
+fn releases(drop_folder: &Path) -> Result<Vec<PathBuf>> {
+    let mut releases: Vec<PathBuf> = fs::read_dir(drop_folder)
+        .with_context(|| format!("Failed to read {}", drop_folder.display()))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|path| {
+            !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    releases.sort();
+    Ok(releases)
+}
+
+/// Arguments for linking one release automatically, as if given on the command line
+fn import_args(
+    release: &Path,
+    target: Option<&str>,
+    state_db: &Path,
+    min_confidence: Option<f64>,
+) -> Result<OrganizeArgs> {
+    let mut argv = vec!["mediar".to_string(), release.to_string_lossy().into_owned()];
+    argv.extend(target.map(str::to_string));
+    argv.extend(["--auto".to_string(), "--state-db".to_string()]);
+    argv.push(state_db.to_string_lossy().into_owned());
+    if let Some(min_confidence) = min_confidence {
+        argv.extend(["--min-confidence".to_string(), min_confidence.to_string()]);
+    }
+    let matches =
+        OrganizeArgs::augment_args(clap::Command::new("mediar")).try_get_matches_from(argv)?;
+    Ok(OrganizeArgs::from_arg_matches(&matches)?)
+}
+
+/// Hard-link every release in a drop folder that was not imported before
+///
+/// Each release is matched on its own and a failure does not stop the others. Imported
+/// releases are recorded in the state database and passed over on later scans.
+async fn import_releases(
+    client: &TmdbClient,
+    config: &Config,
+    args: ImportArgs,
+    memory: &mut MatchMemory,
+    wait: bool,
+) -> Result<()> {
+    let drop_folder = Path::new(&args.drop_folder);
+    let state_db = args
+        .state_db
+        .or(config.state_db.clone())
+        .or_else(|| dirs::data_dir().map(|dir| dir.join("mediar").join("state.db")))
+        .context("Failed to find a data directory for the state database; pass --state-db")?;
+    let db = StateDb::open(&state_db)?;
+
+    let mut imported = 0;
+    let mut already = 0;
+    let mut failures = Vec::new();
+    for release in releases(drop_folder)? {
+        interrupt::check()?;
+        if db.is_imported(&release)? {
+            already += 1;
+            continue;
+        }
+        say!("\n{}", release.to_string_lossy().bold());
+
+        let result = async {
+            let _lock = SourceLock::acquire(&release, wait)?;
+            let organize_args = import_args(
+                &release,
+                args.target.as_deref(),
+                &state_db,
+                args.min_confidence,
+            )?;
+            let options = OrganizeOptions {
+                auto_confirm: true,
+                ..OrganizeOptions::try_from(&organize_args)?
+            };
+            organize(client, config, Mode::Link, organize_args, options, memory).await
+        }
+        .await;
+        match result {
+            Ok(()) => {
+                db.record_import(&release)?;
+                imported += 1;
+            }
+            Err(err) => {
+                print_wrapped("Fail ".clear(), release.to_string_lossy().red());
+                print_wrapped("  ↪  ".bold(), format!("{:#}", err).bold().red());
+                failures.push(release);
+            }
+        }
+    }
+
+    say!(
+        "\nImported {} release(s), {} imported before, {} failed",
+        imported,
+        already,
+        failures.len()
+    );
+    if !failures.is_empty() {
+        exit::record(Status::PartialFailure);
+        return Err(anyhow!("{} release(s) failed to import", failures.len()));
+    }
+    if imported == 0 {
+        exit::record(Status::NothingToDo);
+    }
+    Ok(())
+}
+
+/// Arguments for organizing a `mediar serve` request, as if given on the command line
+fn request_args(request: &OrganizeRequest, plan_out: Option<&Path>) -> Result<OrganizeArgs> {
+    let mut argv = vec!["mediar".to_string(), request.path.clone()];
+    argv.extend(request.target.clone());
+    argv.push("--auto".to_string());
+    if let Some(id) = request.tv_id {
+        argv.extend(["--tv-id".to_string(), id.to_string()]);
+    }
+    if let Some(id) = request.movie_id {
+        argv.extend(["--movie-id".to_string(), id.to_string()]);
+    }
+    if let Some(path) = plan_out {
+        argv.extend([
+            "--plan-out".to_string(),
+            path.to_string_lossy().into_owned(),
+        ]);
+    }
+    let matches =
+        OrganizeArgs::augment_args(clap::Command::new("mediar")).try_get_matches_from(argv)?;
+    Ok(OrganizeArgs::from_arg_matches(&matches)?)
+}
+
+/// Organize one `mediar serve` request and report the events it produced
+fn serve_request(
+    config: &Config,
+    metadata_language: Option<String>,
+    offline: bool,
+    request: OrganizeRequest,
+) -> Result<RunReport> {
+    // The exit status and captured events are process-wide, so requests take turns
+    static RUNNING: Mutex<()> = Mutex::new(());
+    let _running = RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let client = TmdbClient::new(&config.tmdb, offline)?.with_language(metadata_language);
+    let plan_out = request
+        .dry_run
+        .then(|| std::env::temp_dir().join(format!("mediar-serve-{}.json", std::process::id())));
+    let args = request_args(&request, plan_out.as_deref())?;
+    let options = OrganizeOptions {
+        auto_confirm: true,
+        ..OrganizeOptions::try_from(&args)?
+    };
+    let _lock = SourceLock::acquire(Path::new(&request.path), false)?;
+
+    exit::reset();
+    output::start_capture();
+    let result = tokio::runtime::Handle::current().block_on(organize(
+        &client,
+        config,
+        request.mode,
+        args,
+        options,
+        &mut MatchMemory::default(),
+    ));
+    let events = output::take_captured();
+    // A request queued while TMDB is unavailable notifies once it finally runs
+    if !request.dry_run && !result.as_ref().is_err_and(tmdb::is_unavailable) {
+        tokio::runtime::Handle::current().block_on(send_notifications(&config.notify, &result));
+    }
+    if let Some(path) = &plan_out {
+        let _ = fs::remove_file(path);
+    }
+    result?;
+
+    let status = if request.dry_run {
+        "planned"
+    } else {
+        exit::current().as_str()
+    };
+    Ok(RunReport {
+        status: status.to_string(),
+        events,
+    })
+}
+
+/// Send library files that are missing or changed on the remote
+fn sync_library(library: &Path, remote: &str, auto_confirm: bool) -> Result<()> {
+    let local = sync::list_local(library)?;
+    let listing = sync::list_remote(remote)?;
+    let pending = sync::plan(&local, &listing);
+
+    if pending.is_empty() {
+        say!("{} Remote is up to date.", "✓".bold().green());
+        exit::record(Status::NothingToDo);
+        return Ok(());
+    }
+
+    for (relative, reason) in &pending {
+        let label = match reason {
+            SyncReason::Missing => "Send ".clear(),
+            SyncReason::Changed => "Update ".clear(),
+        };
+        print_wrapped(label, relative.to_string_lossy().dimmed().green());
+        print_wrapped(
+            "  ↪  ".bold(),
+            sync::remote_path(remote, relative).bold().green(),
+        );
+    }
+
+    if !confirm_operations(auto_confirm)? {
+        say!("{} Cancelled.", "✗".bold().yellow());
+        exit::record(Status::Cancelled);
+        return Ok(());
+    }
+
+    for (relative, _) in &pending {
+        sync::copy_to_remote(library, remote, relative)?;
+    }
+
+    say!("{} Done.", "✓".bold().green());
+    exit::record(Status::Done);
+    Ok(())
+}
+
+/// Validate a TMDB token and store it for later runs
+async fn login(settings: &TmdbConfig, token: Option<String>, to_file: bool) -> Result<()> {
+    let token = match token {
+        Some(token) => token,
+        None => {
+            prompt::require_input("TMDB API token:", "pass it with --token")?;
+            inquire::Password::new("TMDB API token:")
+                .without_confirmation()
+                .with_help_message(
+                    "The API Read Access Token or API key from themoviedb.org/settings/api",
+                )
+                .prompt()?
+        }
+    };
+    let token = token.trim().to_string();
+    TmdbClient::connect(settings, Credential::detect(token.clone()))?
+        .validate()
+        .await?;
+    match auth::store(&token, to_file)? {
+        auth::Stored::Keychain => say!("{} Stored the token in the keychain.", "✓".bold().green()),
+        auth::Stored::File(path) => say!(
+            "{} Stored the token in {}.",
+            "✓".bold().green(),
+            path.display()
+        ),
+    }
+    Ok(())
+}
+
+fn verify_checksums(library: &Path) -> Result<()> {
+    let results = checksum::verify(library)?;
+    if results.is_empty() {
+        say!("{} No checksum manifests found.", "✓".bold().green());
+        exit::record(Status::NothingToDo);
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for (file, outcome) in &results {
+        let label = match outcome {
+            checksum::Outcome::Ok => continue,
+            checksum::Outcome::Mismatch => "Corrupt ".clear(),
+            checksum::Outcome::Missing => "Missing ".clear(),
+        };
+        print_wrapped(label, file.to_string_lossy().red());
+        failed += 1;
+    }
+
+    if failed > 0 {
+        return Err(anyhow!(
+            "{} of {} file(s) failed verification",
+            failed,
+            results.len()
+        ));
+    }
+    say!(
+        "{} All {} file(s) match their checksums.",
+        "✓".bold().green(),
+        results.len()
+    );
+    exit::record(Status::Done);
+    Ok(())
+}
+
+fn clean_dir(dir: &Path, retention: &Retention, dry_run: bool, auto_confirm: bool) -> Result<()> {
+    let junk = clean::find_junk(dir, retention, SystemTime::now())?;
+    if junk.is_empty() {
+        say!("{} No junk files found.", "✓".bold().green());
+        exit::record(Status::NothingToDo);
+        return Ok(());
+    }
+
+    for (path, reason) in &junk {
+        print_wrapped(
+            format!("Delete ({}) ", reason).normal(),
+            path.to_string_lossy().dimmed().red(),
+        );
+    }
+    say!("{} junk file(s)", junk.len());
+
+    if dry_run {
+        return Ok(());
+    }
+    if !confirm_operations(auto_confirm)? {
+        say!("{} Cancelled.", "✗".bold().yellow());
+        exit::record(Status::Cancelled);
+        return Ok(());
+    }
+
+    clean::remove_junk(dir, &junk)?;
+    say!("{} Done.", "✓".bold().green());
+    exit::record(Status::Done);
+    Ok(())
+}
+
+/// Lock every source an organize command was given, expanding globs as [`organize`] does
+fn lock_sources(args: &OrganizeArgs, wait: bool) -> Result<Vec<SourceLock>> {
+    scan::expand_sources(args.sources())?
+        .iter()
+        .map(|source| SourceLock::acquire(Path::new(source), wait))
+        .collect()
+}
+
+/// A source's planned operations and the match to remember in it once they are done
+struct Prepared {
+    planned: Option<(Mode, Plan)>,
+    remember: Option<Marker>,
+}
+
+/// Organize every source given, matching each to its show or movie and running all their
+/// operations as one plan
+async fn organize(
+    client: &TmdbClient,
+    config: &Config,
+    mode: Mode,
+    args: OrganizeArgs,
+    options: OrganizeOptions,
+    memory: &mut MatchMemory,
+) -> Result<()> {
+    let sources = scan::expand_sources(args.sources())?;
+    let remote = match sources.as_slice() {
+        [source] => RemoteSource::parse(source)?,
+        _ if sources.iter().any(|source| source.starts_with("ssh://")) => {
+            return Err(anyhow!(
+                "Remote sources can only be organized one at a time"
+            ));
+        }
+        _ => None,
+    };
+    if sources.len() > 1 && options.plan_out.is_some() {
+        return Err(anyhow!("Plans can only be saved for a single source"));
+    }
+    if let Some(remote) = &remote {
+        if mode != Mode::Copy {
+            return Err(anyhow!(
+                "Remote sources can only be copied; use `mediar copy`"
+            ));
+        }
+        if options.plan_out.is_some() {
+            return Err(anyhow!("Plans cannot be saved for remote sources"));
+        }
+        if args.target().is_none() && config.tv_target.is_none() && config.movie_target.is_none() {
+            return Err(anyhow!("Give a target directory for a remote source"));
+        }
+        say!("Listing {} on {}", remote.path, remote.host);
+        let count = remote.mirror()?;
+        say!("Found {} media file(s)", count);
+    }
+    let options = OrganizeOptions {
+        rules: Rules::new(&config.rules).context("Invalid rule in config")?,
+        id_tag: options.id_tag.or(config.id_tag),
+        script: config
+            .naming_script
+            .as_deref()
+            .map(NamingScript::load)
+            .transpose()?,
+        trash_retention_days: options
+            .trash_retention_days
+            .map(|days| config.trash_retention_days.unwrap_or(days)),
+        state_db: options.state_db.or(config.state_db.clone()),
+        post_hooks: config
+            .post_hooks
+            .iter()
+            .cloned()
+            .chain(options.post_hooks)
+            .collect(),
+        file_hooks: config
+            .file_hooks
+            .iter()
+            .cloned()
+            .chain(options.file_hooks)
+            .collect(),
+        remote: remote.clone(),
+        ..options
+    };
+
+    // Sources put into place the same way run as one plan, with a single confirmation
+    let mut runs: Vec<(Mode, Plan)> = Vec::new();
+    let mut remember = Vec::new();
+    // The extracted files are deleted when this goes out of scope, once the run is over
+    let mut extracted = Vec::new();
+    for source in &sources {
+        let source = match &remote {
+            Some(remote) => remote.mirror.as_path(),
+            None => Path::new(source),
+        };
+        if args.extract_archives && remote.is_none() && source.is_dir() {
+            let archives = archive::extract_all(source)?;
+            for archive in &archives.archives {
+                print_wrapped("Extract ".clear(), archive.to_string_lossy().normal());
+            }
+            extracted.push(archives);
+        }
+        let prepared =
+            prepare_source(client, config, mode, source, &args, &options, memory).await?;
+        remember.extend(prepared.remember.map(|marker| (source, marker)));
+        let Some((mode, plan)) = prepared.planned else {
+            continue;
+        };
+        match runs.iter_mut().find(|(run_mode, _)| *run_mode == mode) {
+            Some((_, run)) => run.merge(plan)?,
+            None => runs.push((mode, plan)),
+        }
+    }
+    for (mode, plan) in runs {
+        execute_operations(&mode, plan, &options)?;
+    }
+
+    for (source, marker) in remember {
+        // Failing to remember the match should not fail a run that already completed
+        if let Err(err) = marker.save(source) {
+            say!(
+                "{} Could not save the match: {:#}",
+                "✗".bold().yellow(),
+                err
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The TMDB seasons a source has episodes of, or `None` if the whole show may be needed
+///
+/// Files without an SxxEyy code are matched by episode title against every episode, and
+/// corrections made during review can point anywhere, so both need all seasons.
+fn seasons_needed(
+    source: &Path,
+    config: &Config,
+    options: &OrganizeOptions,
+) -> Result<Option<BTreeSet<i32>>> {
+    if options.review {
+        return Ok(None);
+    }
+    let mut seasons = BTreeSet::new();
+    let mut all = false;
+    Plan::collect(source, &options.filter, |old, ext| {
+        let relative = old.strip_prefix(source).unwrap_or(old);
+        if ext != DISC && parse_extra(relative).is_none() {
+            match parse_episode(old) {
+                Ok((season, episode)) => {
+                    seasons.insert(options.map_episode(season, episode).0);
+                    // Which show's season map applies is only known once it is fetched
+                    seasons.extend(
+                        config
+                            .shows
+                            .values()
+                            .flat_map(|show| &show.season_map)
+                            .filter(|map| map.season == season)
+                            .map(|map| map.to_season.unwrap_or(season)),
+                    );
+                }
+                Err(_) => all = true,
+            }
+        }
+        Ok(Destination::Skip(SkipReason::Ignored(
+            "only scanned for seasons",
+        )))
+    })?;
+    Ok((!all).then_some(seasons))
+}
+
+/// The episode order and options for a source, each taken from the command line if given
+/// there and otherwise from the source's marker
+///
+/// An order given in neither is left to the show's config, once the show is known.
+fn with_saved(
+    args: &OrganizeArgs,
+    saved: &Marker,
+    options: &OrganizeOptions,
+) -> (Option<EpisodeOrder>, OrganizeOptions) {
+    let order = args.order.or(saved.order);
+    let options = OrganizeOptions {
+        title_style: args.title_style.or(saved.title_style).unwrap_or_default(),
+        season_offset: args.season_offset.or(saved.season_offset).unwrap_or(0),
+        episode_offset: args.episode_offset.or(saved.episode_offset).unwrap_or(0),
+        ..options.clone()
+    };
+    (order, options)
+}
+
+/// Match one source to its show or movie and plan its operations
+async fn prepare_source(
+    client: &TmdbClient,
+    config: &Config,
+    mode: Mode,
+    source: &Path,
+    args: &OrganizeArgs,
+    options: &OrganizeOptions,
+    memory: &mut MatchMemory,
+) -> Result<Prepared> {
+    let target = args.target().map(Path::new);
+    // Options given on the command line win over the ones saved for the directory
+    let marker = if source.is_dir() {
+        Marker::load(source)?
+    } else {
+        None
+    };
+    let saved = marker.clone().unwrap_or_default();
+    let (order, options) = with_saved(args, &saved, options);
+    // A long-running show needs only the seasons of the episodes at hand
+    let client = &client
+        .clone()
+        .with_seasons(seasons_needed(source, config, &options)?);
+
+    let (tv_id, movie_id) = match (args.tv_id, args.movie_id) {
+        (None, None) if saved.tv_id.is_some() || saved.movie_id.is_some() => {
+            say!(
+                "Using the match saved in {}",
+                Marker::path(source).display()
+            );
+            (saved.tv_id, saved.movie_id)
+        }
+        ids => ids,
+    };
+
+    let content = match (tv_id, movie_id) {
+        (Some(id), None) => Content::Show(client.show(id).await?),
+        (None, Some(id)) => Content::Movie(client.movie(id).await?),
+        (Some(_), Some(_)) => return Err(anyhow!("Cannot specify both --tv-id and --movie-id")),
+        (None, None) => match metadata::load_local(source)? {
+            Some(LocalMetadata::Show(show)) => Content::Show(show),
+            Some(LocalMetadata::Movie(movie)) => Content::Movie(movie),
+            None => {
+                let selection = SelectionOptions {
+                    auto_confidence: args.auto.then(|| {
+                        args.min_confidence
+                            .or(config.auto_confidence)
+                            .unwrap_or(DEFAULT_MIN_CONFIDENCE)
+                    }),
+                    first: args.first,
+                    search_limit: args.search_limit,
+                };
+                auto_detect_and_select(client, source, &options.filter, &selection, memory).await?
+            }
+        },
+    };
+
+    let remember = Marker {
+        tv_id: match &content {
+            Content::Show(show) => Some(show.id),
+            Content::Movie(_) => None,
+        },
+        movie_id: match &content {
+            Content::Movie(movie) => Some(movie.id),
+            Content::Show(_) => None,
+        },
+        order: order.filter(|&order| order != EpisodeOrder::Aired),
+        title_style: (options.title_style != TitleStyle::default()).then_some(options.title_style),
+        season_offset: (options.season_offset != 0).then_some(options.season_offset),
+        episode_offset: (options.episode_offset != 0).then_some(options.episode_offset),
+    };
+    // Content from local metadata has no TMDB ID worth remembering
+    let local = remember.tv_id == Some(0) || remember.movie_id == Some(0);
+    let save_marker = source.is_dir() && !local && options.plan_out.is_none();
+
+    let planned = match content {
+        Content::Show(mut show) => {
+            let show_config = config.show(&show.name);
+            let order = order
+                .or_else(|| show_config.and_then(|show_config| show_config.order))
+                .unwrap_or_default();
+            client.reorder(&mut show, order).await?;
+            let target = target
+                .or_else(|| show_config.and_then(|show_config| show_config.target.as_deref()))
+                .or(config.tv_target.as_deref());
+            let options = OrganizeOptions {
+                season_map: show_config
+                    .map(|show_config| show_config.season_map.clone())
+                    .unwrap_or_default(),
+                ..options
+            };
+            prepare_tv(mode, source, target, &show, &options)?
+        }
+        Content::Movie(movie) => {
+            let target = target.or(config.movie_target.as_deref());
+            prepare_movie(mode, source, target, &movie, &options)?
+        }
+    };
+
+    Ok(Prepared {
+        planned,
+        remember: (save_marker && marker.as_ref() != Some(&remember)).then_some(remember),
+    })
+}
+
+/// Plan where each home video goes under the target, by the date it was recorded
+fn plan_personal(
+    source: &Path,
+    target: &Path,
+    template: &str,
+    filter: &PathFilter,
+) -> Result<Plan> {
+    Plan::collect(source, filter, |old, ext| {
+        if ext == DISC {
+            return Ok(Destination::Skip(SkipReason::Ignored(
+                "disc rips are only organized as movies",
+            )));
+        }
+        let Some(date) = personal::date_of(old) else {
+            return Ok(Destination::Skip(SkipReason::NoMatch(
+                "no recording date".to_string(),
+            )));
+        };
+        let folder = old
+            .parent()
+            .and_then(Path::file_name)
+            .unwrap_or_default()
+            .to_string_lossy();
+        let name = old.file_name().unwrap_or_default().to_string_lossy();
+        Ok(Destination::To(
+            target.join(personal::render(template, date, &folder, &name)),
+        ))
+    })
+}
+
+/// Organize a folder of home videos by date, with the same planning and execution as TMDB
+/// titles
+fn organize_personal(config: &Config, args: PersonalArgs, wait: bool) -> Result<()> {
+    let source = Path::new(&args.source);
+    let target = Path::new(&args.target);
+    let template = args
+        .date_template
+        .or(config.date_template.clone())
+        .unwrap_or_else(|| personal::DEFAULT_TEMPLATE.to_string());
+    personal::check_template(&template)?;
+    let _lock = SourceLock::acquire(source, wait)?;
+
+    let options = OrganizeOptions {
+        auto_confirm: prompt::assume_yes(),
+        filter: PathFilter::new(&args.include, &args.exclude)?,
+        keep_going: args.keep_going,
+        state_db: config.state_db.clone(),
+        post_hooks: config.post_hooks.clone(),
+        file_hooks: config.file_hooks.clone(),
+        ..Default::default()
+    };
+    let mode = link_mode(args.mode, source, target, LinkFallback::default())?;
+    let mut plan = plan_personal(source, target, &template, &options.filter)?;
+    skip_processed(&mut plan, &options)?;
+    execute_operations(&mode, plan, &options)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => exit::code(None),
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            exit::code(Some(&err))
+        }
+    }
+}
+
+async fn run() -> Result<()> {
+    let _ = dotenvy::dotenv();
+    let args = Args::parse();
+    let color = if args.no_color {
+        ColorChoice::Never
+    } else {
+        args.color
+    };
+    output::configure(args.output);
+    // Human-readable output moves to stderr while stdout carries events
+    let human_output = if output::events() {
+        io::stderr().is_terminal()
+    } else {
+        io::stdout().is_terminal()
+    };
+    let colors = color.enabled(std::env::var("NO_COLOR").ok().as_deref(), human_output);
+    colored::control::set_override(colors);
+    logging::init(args.verbose, args.quiet, colors, args.log_file.as_deref())?;
+    // Cron jobs and other runs without a terminal cannot answer prompts
+    prompt::configure(args.yes, args.no_input || !io::stdin().is_terminal());
+    interrupt::install()?;
+    let config = Config::load()?;
+
+    let metadata_language = args.metadata_language.or(config.metadata_language.clone());
+    // Only commands that talk to TMDB need an API token
+    let offline = args.offline;
+    let tmdb_client = || -> Result<TmdbClient> {
+        Ok(TmdbClient::new(&config.tmdb, offline)?.with_language(metadata_language.clone()))
+    };
+
+    let mut memory = MatchMemory::default();
+
+    let wait = args.wait;
+    // Only runs that organize files report to the notification services
+    let organizes = matches!(
+        args.command,
+        Commands::Move(_)
+            | Commands::Copy(_)
+            | Commands::Link(_)
+            | Commands::Reflink(_)
+            | Commands::Apply { .. }
+            | Commands::Resume { .. }
+            | Commands::Import(_)
+            | Commands::Personal(_)
+    );
+    let notifications = if organizes {
+        config.notify.clone()
+    } else {
+        Vec::new()
+    };
+    let result = match args.command {
+        Commands::Search {
+            query,
+            language,
+            min_popularity,
+            limit,
+            search_type,
+            year,
+            pick,
+        } => {
+            let client = tmdb_client()?;
+            // One multi search ranks shows and movies together; it cannot filter by year
+            let (tv_response, movie_response) = if search_type == SearchType::All && year.is_none()
+            {
+                let (tv, movies) = client.search_multi(&query, limit).await?.split();
+                (Ok(Some(tv)), Ok(Some(movies)))
+            } else {
+                // Search TV and movies in parallel, skipping the type filtered out
+                tokio::join!(
+                    async {
+                        match search_type {
+                            SearchType::Movie => Ok(None),
+                            _ => client.search_tv(&query, year, limit).await.map(Some),
+                        }
+                    },
+                    async {
+                        match search_type {
+                            SearchType::Tv => Ok(None),
+                            _ => client.search_movie(&query, year, limit).await.map(Some),
+                        }
+                    }
+                )
+            };
+
+            let tv_response = tv_response?;
+            let movie_response = movie_response?;
+            let tv_total = tv_response.as_ref().map_or(0, |r| r.total_results);
+            let movie_total = movie_response.as_ref().map_or(0, |r| r.total_results);
+
+            // Convert all results to SearchResultDisplay
+            let tv_results: Vec<SearchResultDisplay> = tv_response
+                .into_iter()
+                .flat_map(|response| response.results)
+                .map(SearchResultDisplay::from)
+                .collect();
+
+            let movie_results: Vec<SearchResultDisplay> = movie_response
+                .into_iter()
+                .flat_map(|response| response.results)
+                .map(SearchResultDisplay::from)
+                .collect();
+
+            // Combine all results
+            let mut all_results = Vec::new();
+            all_results.extend(tv_results);
+            all_results.extend(movie_results);
+
+            // Filter and sort combined results
+            let filtered_results =
+                filter_and_sort_search_results(all_results, &language, min_popularity, &query);
+
+            if filtered_results.is_empty() {
+                say!("No results found for: {}", query.yellow());
+                return Ok(());
+            }
+
+            let total_results = tv_total + movie_total;
+
+            let table = Table::new(&filtered_results)
+                .with(Style::rounded())
+                .to_string();
+            say!("\n{}", table);
+            say!(
+                "\nFound {} results ({} TV, {} movies)",
+                total_results,
+                tv_total,
+                movie_total
+            );
+            if pick {
+                pick_and_organize(&client, &config, &filtered_results, &mut memory, wait).await?;
+            }
+            Ok(())
+        }
+        Commands::Move(args) => {
+            let options = OrganizeOptions {
+                allow_hardlinked: args.allow_hardlinked,
+                trash_retention_days: args.trash.then_some(trash::DEFAULT_RETENTION_DAYS),
+                ..OrganizeOptions::try_from(&args.organize)?
+            };
+            let _locks = lock_sources(&args.organize, wait)?;
+            organize(
+                &tmdb_client()?,
+                &config,
+                Mode::Move,
+                args.organize,
+                options,
+                &mut memory,
+            )
+            .await
+        }
+        Commands::Copy(args) => {
+            let options = OrganizeOptions::try_from(&args)?;
+            let _locks = lock_sources(&args, wait)?;
+            organize(
+                &tmdb_client()?,
+                &config,
+                Mode::Copy,
+                args,
+                options,
+                &mut memory,
+            )
+            .await
+        }
+        Commands::Link(args) => {
+            let options = OrganizeOptions {
+                link_fallback: args.fallback,
+                ..OrganizeOptions::try_from(&args.organize)?
+            };
+            let _locks = lock_sources(&args.organize, wait)?;
+            organize(
+                &tmdb_client()?,
+                &config,
+                Mode::Link,
+                args.organize,
+                options,
+                &mut memory,
+            )
+            .await
+        }
+        Commands::Reflink(args) => {
+            let options = OrganizeOptions {
+                reflink_fallback: args.fallback,
+                ..OrganizeOptions::try_from(&args.organize)?
+            };
+            let _locks = lock_sources(&args.organize, wait)?;
+            organize(
+                &tmdb_client()?,
+                &config,
+                Mode::Reflink,
+                args.organize,
+                options,
+                &mut memory,
+            )
+            .await
+        }
+        Commands::Info { tv_id, movie_id } => {
+            let client = tmdb_client()?;
+            match (tv_id, movie_id) {
+                (Some(id), None) => {
+                    let (show, external_ids) =
+                        tokio::try_join!(client.show(id), client.tv_external_ids(id))?;
+                    print_show_info(&show, &external_ids);
+                }
+                (None, Some(id)) => print_movie_info(&client.movie(id).await?),
+                _ => return Err(anyhow!("Specify exactly one of --tv-id and --movie-id")),
+            }
+            Ok(())
+        }
+        Commands::Episodes { tv_id, season } => {
+            let client = tmdb_client()?;
+            let seasons = match season {
+                Some(season) => vec![client.season(tv_id, season).await?],
+                None => client.show(tv_id).await?.seasons,
+            };
+            print_episodes(&seasons);
+            Ok(())
+        }
+        Commands::Apply { plan } => apply_plan(Path::new(&plan), wait),
+        Commands::Resume { path } => {
+            let path = Path::new(&path);
+            // A journal is kept in the source directory it belongs to
+            let source = if path.is_dir() {
+                path
+            } else {
+                path.parent().unwrap_or(path)
+            };
+            let _lock = SourceLock::acquire(source, wait)?;
+            resume_run(path, args.yes)
+        }
+        Commands::Verify {
+            library,
+            fix,
+            state_db,
+        } => {
+            let state = state_db
+                .or(config.state_db.clone())
+                .as_deref()
+                .map(StateDb::open)
+                .transpose()?;
+            verify_library(
+                &tmdb_client()?,
+                &config,
+                Path::new(&library),
+                state.as_ref(),
+                fix,
+                args.yes,
+            )
+            .await
+        }
+        Commands::Refresh {
+            show_dir,
+            tv_id,
+            state_db,
+        } => {
+            let state = state_db
+                .or(config.state_db.clone())
+                .as_deref()
+                .map(StateDb::open)
+                .transpose()?;
+            match show_dir {
+                Some(show_dir) => {
+                    refresh_show(
+                        &tmdb_client()?,
+                        &config,
+                        Path::new(&show_dir),
+                        tv_id,
+                        state.as_ref(),
+                        args.yes,
+                    )
+                    .await
+                }
+                None => {
+                    let state = state.context(
+                        "Pass a show directory, or --state-db to refresh placeholder episodes",
+                    )?;
+                    refresh_placeholders(&tmdb_client()?, &config, &state, args.yes).await
+                }
+            }
+        }
+        Commands::Stats { library } => print_library_stats(Path::new(&library)),
+        Commands::Missing { show_dir, tv_id } => {
+            report_missing(&tmdb_client()?, Path::new(&show_dir), tv_id).await
+        }
+        Commands::Clean { dir, dry_run } => clean_dir(
+            Path::new(&dir),
+            &Retention::from_config(&config)?,
+            dry_run,
+            args.yes,
+        ),
+        Commands::Sync { library, remote } => sync_library(Path::new(&library), &remote, args.yes),
+        Commands::Checksum {
+            command: ChecksumCommand::Verify { library },
+        } => verify_checksums(Path::new(&library)),
+        Commands::Auth {
+            command: AuthCommand::Login { token, file },
+        } => login(&config.tmdb, token, file).await,
+        Commands::Auth {
+            command: AuthCommand::Logout,
+        } => {
+            if auth::forget()? {
+                say!("{} Removed the stored TMDB token.", "✓".bold().green());
+            } else {
+                say!("{} No TMDB token was stored.", "✗".bold().yellow());
+            }
+            Ok(())
+        }
+        Commands::Serve { listen, token } => {
+            // No one is at the terminal to answer prompts for a request
+            prompt::configure(true, true);
+            let language = metadata_language.clone();
+            let runner: Runner =
+                Arc::new(move |request| serve_request(&config, language.clone(), offline, request));
+            serve::serve(listen, runner, token).await
+        }
+        Commands::Status { server, token } => print_server_status(&server, token.as_deref()).await,
+        Commands::Import(args) => {
+            import_releases(&tmdb_client()?, &config, args, &mut memory, wait).await
+        }
+        Commands::Personal(args) => organize_personal(&config, args, wait),
+    };
+    send_notifications(&notifications, &result).await;
+    result
+}
+
+/// Tell the configured services what the run organized, or that it failed
+async fn send_notifications(notifications: &[NotifyConfig], result: &Result<()>) {
+    let organized = notify::take_recorded();
+    if notifications.is_empty() {
+        return;
+    }
+    let error = result.as_ref().err().map(|err| format!("{:#}", err));
+    let Some((severity, text)) = notify::message(&organized, error.as_deref()) else {
+        return;
+    };
+    if let Err(err) = notify::send(notifications, severity, &text).await {
+        tracing::warn!(error = %format!("{:#}", err), "notification failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RuleConfig;
+    use crate::tmdb::{Movie, Show, TvSeason, TvSeasonEpisode};
+    use std::fs;
+    use tempfile::TempDir;
+    use walkdir::WalkDir;
+
+    fn auto_confirm() -> OrganizeOptions {
+        OrganizeOptions {
+            auto_confirm: true,
+            ..Default::default()
+        }
+    }
+
+    /// Plan and execute a show's files, as organizing a single source does
+    fn organize_tv(
+        mode: Mode,
+        source: &Path,
+        target: Option<&Path>,
+        show: &Show,
+        options: &OrganizeOptions,
+    ) -> Result<()> {
+        match prepare_tv(mode, source, target, show, options)? {
+            Some((mode, plan)) => execute_operations(&mode, plan, options),
+            None => Ok(()),
+        }
+    }
+
+    /// Plan and execute a movie's files, as organizing a single source does
+    fn organize_movie(
+        mode: Mode,
+        source: &Path,
+        target: Option<&Path>,
+        movie: &Movie,
+        options: &OrganizeOptions,
+    ) -> Result<()> {
+        match prepare_movie(mode, source, target, movie, options)? {
+            Some((mode, plan)) => execute_operations(&mode, plan, options),
+            None => Ok(()),
+        }
+    }
+
+    fn create_test_show() -> Show {
+        Show {
+            id: 42,
+            name: "Show Name".to_string(),
+            original_name: None,
+            overview: "Test show".to_string(),
+            year: 2008,
             first_air_date: "2008-01-20".to_string(),
             number_of_episodes: 4,
             number_of_seasons: 2,
@@ -747,6 +4451,7 @@ mod tests {
                     season_number: 1,
                     name: "Season 1".to_string(),
                     overview: "First season".to_string(),
+                    air_date: None,
                     episodes: vec![
                         TvSeasonEpisode {
                             id: 101,
@@ -754,6 +4459,7 @@ mod tests {
                             episode_number: 1,
                             name: "One".to_string(),
                             overview: "Pilot".to_string(),
+                            air_date: None,
                         },
                         TvSeasonEpisode {
                             id: 102,
@@ -761,6 +4467,7 @@ mod tests {
                             episode_number: 2,
                             name: "Two".to_string(),
                             overview: "Second episode".to_string(),
+                            air_date: None,
                         },
                     ],
                 },
@@ -769,6 +4476,7 @@ mod tests {
                     season_number: 2,
                     name: "Season 2".to_string(),
                     overview: "Second season".to_string(),
+                    air_date: None,
                     episodes: vec![
                         TvSeasonEpisode {
                             id: 201,
@@ -776,6 +4484,7 @@ mod tests {
                             episode_number: 1,
                             name: "Three".to_string(),
                             overview: "Season 2 premiere".to_string(),
+                            air_date: None,
                         },
                         TvSeasonEpisode {
                             id: 202,
@@ -783,6 +4492,7 @@ mod tests {
                             episode_number: 2,
                             name: "Four".to_string(),
                             overview: "Fourth episode".to_string(),
+                            air_date: None,
                         },
                     ],
                 },
@@ -790,297 +4500,2078 @@ mod tests {
         }
     }
 
-    fn test_episode_files() -> Vec<PathBuf> {
-        vec![
-            Path::new("s01").join("Show.S01E01.mkv").to_path_buf(),
-            Path::new("s01").join("Show.S01E01.srt").to_path_buf(),
-            Path::new("s01").join("02.Show.mp4").to_path_buf(),
-            Path::new("s02").join("Show.S02E01.avi").to_path_buf(),
-        ]
+    fn test_episode_files() -> Vec<PathBuf> {
+        vec![
+            Path::new("s01").join("Show.S01E01.mkv").to_path_buf(),
+            Path::new("s01").join("Show.S01E01.srt").to_path_buf(),
+            Path::new("s01").join("02.Show.mp4").to_path_buf(),
+            Path::new("s02").join("Show.S02E01.avi").to_path_buf(),
+        ]
+    }
+
+    fn test_other_files() -> Vec<PathBuf> {
+        vec![
+            Path::new("readme.txt").to_path_buf(),
+            Path::new("trailer.mp4").to_path_buf(),
+            Path::new("s02").join("Show.S02E01.thumb.jpg").to_path_buf(),
+        ]
+    }
+
+    fn test_files() -> Vec<PathBuf> {
+        [test_episode_files(), test_other_files()].concat()
+    }
+
+    fn create_test_files(base_dir: &Path, files: &[PathBuf]) {
+        for file_name in files {
+            let file_path = base_dir.join(file_name);
+            fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            fs::File::create(&file_path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_prefer_year() {
+        let mut results = [("UK", Some(2001)), ("US", Some(2005)), ("Other", None)];
+        prefer_year(&mut results, Some(2005), |(_, year)| *year);
+        assert_eq!(results.map(|(name, _)| name), ["US", "UK", "Other"]);
+
+        prefer_year(&mut results, None, |(_, year)| *year);
+        assert_eq!(results.map(|(name, _)| name), ["US", "UK", "Other"]);
+    }
+
+    #[test]
+    fn test_episode_display() {
+        let show = create_test_show();
+        let display = EpisodeDisplay::from(&show.seasons[1].episodes[0]);
+        assert_eq!(display.code, "S02E01");
+        assert_eq!(display.title, "Three");
+        assert_eq!(display.air_date, "N/A");
+    }
+
+    #[test]
+    fn test_color_choice_enabled() {
+        assert!(ColorChoice::Auto.enabled(None, true));
+        assert!(ColorChoice::Auto.enabled(Some(""), true));
+        assert!(!ColorChoice::Auto.enabled(Some("1"), true));
+        assert!(!ColorChoice::Auto.enabled(None, false));
+        assert!(ColorChoice::Always.enabled(Some("1"), false));
+        assert!(!ColorChoice::Never.enabled(None, true));
+    }
+
+    #[test]
+    fn test_result_row() {
+        assert_eq!(
+            result_row("Heat", Some(1995), Some("en"), 949, Some(25.04), ""),
+            "Heat (1995) - en - ID: 949 - Popularity: 25.0"
+        );
+        assert_eq!(
+            result_row(
+                "Heat",
+                None,
+                None,
+                949,
+                None,
+                "Obsessive master thief Neil McCauley leads a top-notch crew on various daring heists."
+            ),
+            "Heat (N/A) - N/A - ID: 949 - Popularity: 0.0 - \
+             Obsessive master thief Neil McCauley leads a top-notch crew…"
+        );
+    }
+
+    #[test]
+    fn test_choices() {
+        let rows = choices(&["a", "a", "b"], |item| item.to_uppercase());
+        assert_eq!(rows[1].index, 1);
+        assert_eq!(rows[1].to_string(), "A");
+    }
+
+    #[test]
+    fn test_select_from_results_first() {
+        let results = [("First", 1), ("Second", 2)];
+        let id = select_from_results(
+            &results,
+            "Select:",
+            "No results",
+            |(name, _)| name.to_string(),
+            |(name, _)| name,
+            |(_, id)| *id,
+            true,
+        )
+        .unwrap()
+        .1;
+        assert_eq!(id, 1);
+
+        let empty: [(&str, i32); 0] = [];
+        assert!(
+            select_from_results(
+                &empty,
+                "Select:",
+                "No results",
+                |(name, _)| name.to_string(),
+                |(name, _)| name,
+                |(_, id)| *id,
+                true,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_organize_with_autoconfirm() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        fs::create_dir_all(&source).unwrap();
+        create_test_files(&source, &test_files());
+
+        let show = create_test_show();
+
+        let result = organize_tv(Mode::Move, &source, Some(&target), &show, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize should succeed: {:?}",
+            result.err()
+        );
+
+        // With auto-confirm, files should be moved
+        for file_name in &test_episode_files() {
+            let original_path = source.join(file_name);
+            assert!(
+                !original_path.exists(),
+                "Original video file should be moved: {:?}",
+                file_name
+            );
+        }
+
+        let show_dir = target.join("Show Name (2008)");
+        assert!(show_dir.exists(), "Show directory should exist");
+    }
+
+    #[test]
+    fn test_organize_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        create_test_files(&source, &test_files());
+
+        let show = create_test_show();
+
+        let result = organize_tv(Mode::Copy, &source, Some(&target), &show, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize should succeed: {:?}",
+            result.err()
+        );
+
+        for file_name in &test_files() {
+            let original_path = source.join(file_name);
+            assert!(
+                original_path.exists(),
+                "Original file should still exist after copy: {:?}",
+                file_name
+            );
+        }
+
+        let show_dir = target.join("Show Name (2008)");
+        assert!(show_dir.exists(), "Show directory should exist");
+
+        let season1_dir = show_dir.join("Season 01");
+        assert!(season1_dir.exists(), "Season 1 directory should exist");
+
+        let season2_dir = show_dir.join("Season 02");
+        assert!(season2_dir.exists(), "Season 2 directory should exist");
+
+        for expected_file in [
+            season1_dir.join("Show Name - S01E01 - One.mkv"),
+            season1_dir.join("Show Name - S01E01 - One.srt"),
+            season1_dir.join("Show Name - S01E02 - Two.mp4"),
+            season2_dir.join("Show Name - S02E01 - Three.avi"),
+        ] {
+            assert!(
+                expected_file.exists(),
+                "Expected file should exist: {:?}",
+                expected_file
+            );
+        }
+    }
+
+    #[test]
+    fn test_link_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        assert_eq!(
+            link_mode(Mode::Link, &source, &target, LinkFallback::Fail).unwrap(),
+            Mode::Link
+        );
+
+        // procfs is its own filesystem wherever it is mounted
+        let other = Path::new("/proc");
+        if device_of(other) != device_of(&source) {
+            assert!(link_mode(Mode::Link, &source, other, LinkFallback::Fail).is_err());
+            assert_eq!(
+                link_mode(Mode::Link, &source, other, LinkFallback::Symlink).unwrap(),
+                Mode::Symlink
+            );
+            assert_eq!(
+                link_mode(Mode::Copy, &source, other, LinkFallback::Fail).unwrap(),
+                Mode::Copy
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_organize_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &test_files());
+
+        let result = organize_tv(
+            Mode::Symlink,
+            &source,
+            Some(&target),
+            &create_test_show(),
+            &auto_confirm(),
+        );
+        assert!(
+            result.is_ok(),
+            "organize should succeed: {:?}",
+            result.err()
+        );
+
+        let link = target.join("Show Name (2008)/Season 01/Show Name - S01E01 - One.mkv");
+        assert!(link.is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), source.join(&test_files()[0]));
+    }
+
+    #[test]
+    fn test_organize_reflink_fallback_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &test_files());
+
+        let options = OrganizeOptions {
+            reflink_fallback: ReflinkFallback::Copy,
+            ..auto_confirm()
+        };
+        let result = organize_tv(
+            Mode::Reflink,
+            &source,
+            Some(&target),
+            &create_test_show(),
+            &options,
+        );
+        assert!(
+            result.is_ok(),
+            "organize should succeed: {:?}",
+            result.err()
+        );
+
+        assert!(source.join(&test_files()[0]).exists());
+        assert!(
+            target
+                .join("Show Name (2008)/Season 01/Show Name - S01E01 - One.mkv")
+                .exists()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_organize_move_refuses_hardlinked_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        let seeding = temp_dir.path().join("seeding");
+
+        create_test_files(&source, &test_files());
+        fs::create_dir_all(&seeding).unwrap();
+        fs::hard_link(
+            source.join("s01").join("Show.S01E01.mkv"),
+            seeding.join("Show.S01E01.mkv"),
+        )
+        .unwrap();
+
+        let show = create_test_show();
+
+        let result = organize_tv(Mode::Move, &source, Some(&target), &show, &auto_confirm());
+
+        assert!(
+            result.is_err(),
+            "organize should refuse to move hard-linked sources"
+        );
+        assert!(
+            source.join("s01").join("Show.S01E01.mkv").exists(),
+            "Hard-linked source should not be moved"
+        );
+
+        let options = OrganizeOptions {
+            allow_hardlinked: true,
+            ..auto_confirm()
+        };
+        let result = organize_tv(Mode::Move, &source, Some(&target), &show, &options);
+
+        assert!(
+            result.is_ok(),
+            "organize should move hard-linked sources when allowed: {:?}",
+            result.err()
+        );
+        assert!(
+            seeding.join("Show.S01E01.mkv").exists(),
+            "Seeding copy should remain in place"
+        );
+    }
+
+    #[test]
+    fn test_organize_tv_extras() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        create_test_files(
+            &source,
+            &[
+                Path::new("s01").join("Show.S01E01.mkv").to_path_buf(),
+                Path::new("Featurettes")
+                    .join("Show.S01.Bloopers.mkv")
+                    .to_path_buf(),
+            ],
+        );
+
+        let show = create_test_show();
+
+        let result = organize_tv(Mode::Copy, &source, Some(&target), &show, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize should route extras: {:?}",
+            result.err()
+        );
+
+        let show_dir = target.join("Show Name (2008)");
+        for expected_file in [
+            show_dir
+                .join("Season 01")
+                .join("Show Name - S01E01 - One.mkv"),
+            show_dir.join("Featurettes").join("Show.S01.Bloopers.mkv"),
+        ] {
+            assert!(
+                expected_file.exists(),
+                "Expected file should exist: {:?}",
+                expected_file
+            );
+        }
+    }
+
+    #[test]
+    fn test_organize_match_existing_style() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let season1_dir = target.join("Show Name (2008)").join("Season 01");
+        create_test_files(
+            &season1_dir,
+            &[Path::new("Show.Name.S01E01.mkv").to_path_buf()],
+        );
+        create_test_files(&source, &[Path::new("Show.S01E02.mkv").to_path_buf()]);
+
+        let show = create_test_show();
+        let options = OrganizeOptions {
+            match_existing_style: true,
+            ..auto_confirm()
+        };
+
+        let result = organize_tv(Mode::Copy, &source, Some(&target), &show, &options);
+
+        assert!(
+            result.is_ok(),
+            "organize should succeed: {:?}",
+            result.err()
+        );
+
+        let expected_file = season1_dir.join("Show.Name.S01E02.mkv");
+        assert!(
+            expected_file.exists(),
+            "New episode should follow the existing naming style: {:?}",
+            expected_file
+        );
+    }
+
+    #[test]
+    fn test_plan_tv_episode_title_fallback() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(
+            &source,
+            &[
+                PathBuf::from("Show Name - Three.mkv"),
+                PathBuf::from("Show Name - Something Else.mkv"),
+            ],
+        );
+
+        let plan = plan_tv(
+            &source,
+            &target,
+            &create_test_show(),
+            &auto_confirm(),
+            &Corrections::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            plan.operations,
+            vec![(
+                source.join("Show Name - Three.mkv"),
+                target
+                    .join("Show Name (2008)")
+                    .join("Season 02")
+                    .join("Show Name - S02E01 - Three.mkv")
+            )]
+        );
+        assert!(matches!(plan.skipped[..], [(_, SkipReason::NoMatch(_))]));
+    }
+
+    #[test]
+    fn test_plan_tv_corrections() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &test_episode_files());
+
+        let mut corrections = Corrections::default();
+        corrections
+            .episodes
+            .insert(source.join("s01").join("02.Show.mp4"), (2, 2));
+        corrections
+            .excluded
+            .insert(source.join("s01").join("Show.S01E01.srt"));
+        let plan = plan_tv(
+            &source,
+            &target,
+            &create_test_show(),
+            &auto_confirm(),
+            &corrections,
+        )
+        .unwrap();
+
+        let season_02 = target.join("Show Name (2008)").join("Season 02");
+        assert_eq!(plan.operations.len(), 3);
+        assert!(plan.operations.contains(&(
+            source.join("s01").join("02.Show.mp4"),
+            season_02.join("Show Name - S02E02 - Four.mp4")
+        )));
+        assert!(plan.skipped.contains(&(
+            source.join("s01").join("Show.S01E01.srt"),
+            SkipReason::Ignored("excluded during review")
+        )));
+    }
+
+    #[test]
+    fn test_plan_tv_verifies_organized_show() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path();
+        let show_dir = library.join("Show Name (2008)");
+        create_test_files(
+            &show_dir,
+            &[
+                Path::new("Season 01").join("Show Name - S01E01 - One.mkv"),
+                Path::new("Season 01").join("Show Name - S01E02 - Old Title.mkv"),
+            ],
+        );
+
+        let options = OrganizeOptions {
+            match_existing_style: true,
+            ..auto_confirm()
+        };
+        let plan = plan_tv(
+            &show_dir,
+            library,
+            &create_test_show(),
+            &options,
+            &Corrections::default(),
+        )
+        .unwrap();
+
+        assert_eq!(plan.operations.len(), 1);
+        assert_eq!(
+            plan.operations[0].1,
+            show_dir
+                .join("Season 01")
+                .join("Show Name - S01E02 - Two.mkv")
+        );
+    }
+
+    #[test]
+    fn test_organize_missing_episode_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        create_test_files(&source, &[Path::new("Show.S02E03.mkv").to_path_buf()]);
+
+        let show = create_test_show();
+
+        let result = organize_tv(Mode::Copy, &source, Some(&target), &show, &auto_confirm());
+
+        assert!(
+            result.is_err(),
+            "organize should fail for episodes missing from TMDB"
+        );
+    }
+
+    #[test]
+    fn test_organize_missing_episode_placeholder() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        create_test_files(
+            &source,
+            &[
+                Path::new("Show.S02E02.mkv").to_path_buf(),
+                Path::new("Show.S02E03.mkv").to_path_buf(),
+            ],
+        );
+
+        let show = create_test_show();
+        let options = OrganizeOptions {
+            placeholder_missing: true,
+            ..auto_confirm()
+        };
+
+        let result = organize_tv(Mode::Copy, &source, Some(&target), &show, &options);
+
+        assert!(
+            result.is_ok(),
+            "organize should succeed with placeholders: {:?}",
+            result.err()
+        );
+
+        let season2_dir = target.join("Show Name (2008)").join("Season 02");
+        for expected_file in [
+            season2_dir.join("Show Name - S02E02 - Four.mkv"),
+            season2_dir.join("Show Name - S02E03 - TBA.mkv"),
+        ] {
+            assert!(
+                expected_file.exists(),
+                "Expected file should exist: {:?}",
+                expected_file
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_match_memory_reused() {
+        use axum::{Json, Router, routing::get};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // A stand-in for TMDB that counts searches
+        let searches = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/search/tv",
+                get({
+                    let searches = searches.clone();
+                    move || async move {
+                        searches.fetch_add(1, Ordering::SeqCst);
+                        Json(serde_json::json!({
+                            "page": 1, "total_pages": 1, "total_results": 1,
+                            "results": [{
+                                "id": 42, "name": "Show Name", "overview": "",
+                                "first_air_date": "2008-01-20", "popularity": 50.0,
+                            }],
+                        }))
+                    }
+                }),
+            )
+            .route(
+                "/tv/42",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "id": 42, "name": "Show Name", "overview": "",
+                        "first_air_date": "2008-01-20",
+                        "number_of_episodes": 0, "number_of_seasons": 0,
+                    }))
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let settings = TmdbConfig {
+            base_url: Some(format!("http://{}", listener.local_addr().unwrap())),
+            ..Default::default()
+        };
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = TmdbClient::connect(&settings, Credential::Token("token".to_string()))
+            .unwrap()
+            .without_cache();
+
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("Show.Name.S01.Part1");
+        let second = temp_dir.path().join("Show.Name.S01.Part2");
+        create_test_files(&first, &[PathBuf::from("Show.Name.S01E01.mkv")]);
+        create_test_files(&second, &[PathBuf::from("Show.Name.S01E02.mkv")]);
+
+        let selection = SelectionOptions {
+            auto_confidence: Some(0.5),
+            ..Default::default()
+        };
+        let mut memory = MatchMemory::default();
+        for source in [&first, &second] {
+            let content = auto_detect_and_select(
+                &client,
+                source,
+                &PathFilter::default(),
+                &selection,
+                &mut memory,
+            )
+            .await
+            .unwrap();
+            assert!(matches!(content, Content::Show(show) if show.id == 42));
+        }
+        // The second source uses the match made for the first instead of searching again
+        assert_eq!(searches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_library_resume() {
+        use axum::{Json, Router, extract::Path as UrlPath, http::StatusCode, routing::get};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // A stand-in for TMDB that counts lookups and cannot find Alien the first time
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let app = Router::new().route(
+            "/movie/{id}",
+            get({
+                let lookups = lookups.clone();
+                move |UrlPath(id): UrlPath<i32>| async move {
+                    let lookup = lookups.fetch_add(1, Ordering::SeqCst);
+                    let (title, date) = match id {
+                        949 => ("Heat", "1995-12-15"),
+                        _ if lookup < 2 => return Err(StatusCode::NOT_FOUND),
+                        _ => ("Alien", "1979-05-25"),
+                    };
+                    Ok(Json(serde_json::json!({
+                        "id": id, "title": title, "overview": "", "release_date": date,
+                        "original_language": "en", "popularity": 50.0, "imdb_id": null,
+                    })))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let settings = TmdbConfig {
+            base_url: Some(format!("http://{}", listener.local_addr().unwrap())),
+            ..Default::default()
+        };
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = TmdbClient::connect(&settings, Credential::Token("token".to_string()))
+            .unwrap()
+            .without_cache();
+
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("library");
+        let heat = library.join("Heat (1995) [tmdbid-949]");
+        let alien = library.join("Alien (1979) [tmdbid-348]");
+        create_test_files(&heat, &[PathBuf::from("Heat (1995).mkv")]);
+        create_test_files(&alien, &[PathBuf::from("Alien (1979).mkv")]);
+        let config = Config {
+            id_tag: Some(IdTag::Jellyfin),
+            ..Default::default()
+        };
+        let state = StateDb::open(&temp_dir.path().join("state.db")).unwrap();
+
+        // Alien could not be checked, so Heat is remembered for the next run
+        verify_library(&client, &config, &library, Some(&state), false, true)
+            .await
+            .unwrap();
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+        assert!(state.is_verified(&heat).unwrap());
+        assert!(!state.is_verified(&alien).unwrap());
+
+        // The next run only looks up Alien, then starts over once everything was checked
+        verify_library(&client, &config, &library, Some(&state), false, true)
+            .await
+            .unwrap();
+        assert_eq!(lookups.load(Ordering::SeqCst), 3);
+        assert!(!state.is_verified(&heat).unwrap());
+    }
+
+    #[test]
+    fn test_refresh_placeholder() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        let state_db = temp_dir.path().join("state.db");
+        create_test_files(&source, &[PathBuf::from("Show.S02E03.mkv")]);
+
+        let mut show = create_test_show();
+        let options = OrganizeOptions {
+            placeholder_missing: true,
+            state_db: Some(state_db.clone()),
+            ..auto_confirm()
+        };
+        organize_tv(Mode::Copy, &source, Some(&target), &show, &options).unwrap();
+
+        let show_dir = target.join("Show Name (2008)");
+        let placeholder = show_dir.join("Season 02/Show Name - S02E03 - TBA.mkv");
+        let state = StateDb::open(&state_db).unwrap();
+        assert_eq!(state.placeholders().unwrap(), vec![placeholder.clone()]);
+
+        // Still missing on TMDB: the placeholder is kept
+        let options = OrganizeOptions {
+            placeholder_missing: true,
+            ..library_options(&Config::default(), true)
+        };
+        rename_show(&show_dir, &show, &options, Some(&state)).unwrap();
+        assert_eq!(state.placeholders().unwrap(), vec![placeholder.clone()]);
+
+        show.seasons[1].episodes.push(TvSeasonEpisode {
+            id: 203,
+            season_number: 2,
+            episode_number: 3,
+            name: "Five".to_string(),
+            overview: "Aired at last".to_string(),
+            air_date: None,
+        });
+        rename_show(&show_dir, &show, &options, Some(&state)).unwrap();
+        assert!(!placeholder.exists());
+        assert!(
+            show_dir
+                .join("Season 02/Show Name - S02E03 - Five.mkv")
+                .exists()
+        );
+        assert!(state.placeholders().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_organize_rename_inplace() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+
+        create_test_files(&source, &test_files());
+
+        let show = create_test_show();
+
+        let result = organize_tv(Mode::Move, &source, None, &show, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize should succeed: {:?}",
+            result.err()
+        );
+
+        for file_name in &test_episode_files() {
+            let original_path = source.join(file_name);
+            assert!(
+                !original_path.exists(),
+                "Original video file should be moved/renamed: {:?}",
+                file_name
+            );
+        }
+
+        for file_name in &test_other_files() {
+            let original_path = source.join(file_name);
+            assert!(
+                original_path.exists(),
+                "Regular file should remain untouched: {:?}",
+                file_name
+            );
+        }
+
+        let parent = temp_dir.path();
+        let show_dir = parent.join("Show Name (2008)");
+        assert!(show_dir.exists(), "Show directory should exist in parent");
+
+        let season1_dir = show_dir.join("Season 01");
+        let season2_dir = show_dir.join("Season 02");
+        assert!(season1_dir.exists(), "Season 1 directory should exist");
+        assert!(season2_dir.exists(), "Season 2 directory should exist");
+
+        for expected_file in [
+            season1_dir.join("Show Name - S01E01 - One.mkv"),
+            season1_dir.join("Show Name - S01E01 - One.srt"),
+            season1_dir.join("Show Name - S01E02 - Two.mp4"),
+            season2_dir.join("Show Name - S02E01 - Three.avi"),
+        ] {
+            assert!(
+                expected_file.exists(),
+                "Renamed file should exist: {:?}",
+                expected_file
+            );
+        }
+    }
+
+    fn create_test_movie() -> Movie {
+        Movie {
+            id: 550,
+            title: "Movie Name".to_string(),
+            original_title: None,
+            overview: "Movie description".to_string(),
+            release_date: "1999-10-15".to_string(),
+            original_language: "en".to_string(),
+            popularity: 63.869,
+            imdb_id: None,
+            genres: Vec::new(),
+            poster_path: None,
+        }
+    }
+
+    #[test]
+    fn test_organize_movie_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let movie_files = vec![Path::new("Movie.Name.1080p.mkv").to_path_buf()];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = create_test_movie();
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed: {:?}",
+            result.err()
+        );
+
+        for file_name in &movie_files {
+            let original_path = source.join(file_name);
+            assert!(
+                original_path.exists(),
+                "Original file should still exist after copy: {:?}",
+                file_name
+            );
+        }
+
+        let movie_dir = target.join("Movie Name (1999)");
+        assert!(movie_dir.exists(), "Movie directory should exist");
+
+        let expected_file = movie_dir.join("Movie Name (1999).mkv");
+        assert!(
+            expected_file.exists(),
+            "Movie file should exist in target: {:?}",
+            expected_file
+        );
+    }
+
+    #[test]
+    fn test_picked_args() {
+        let args = picked_args("downloads/Heat", " ", ContentType::Movie, 949).unwrap();
+        assert_eq!(args.sources(), ["downloads/Heat"]);
+        assert_eq!(args.target(), None);
+        assert_eq!((args.tv_id, args.movie_id), (None, Some(949)));
+
+        let args = picked_args("downloads/Wire", "/media/tv", ContentType::Show, 1438).unwrap();
+        assert_eq!(args.target(), Some("/media/tv"));
+        assert_eq!(args.tv_id, Some(1438));
+    }
+
+    #[test]
+    fn test_organize_args_paths() {
+        let parse = |argv: &[&str]| {
+            let matches = OrganizeArgs::augment_args(clap::Command::new("mediar"))
+                .try_get_matches_from(argv)
+                .unwrap();
+            OrganizeArgs::from_arg_matches(&matches).unwrap()
+        };
+        let args = parse(&["mediar", "downloads/Show*"]);
+        assert_eq!(args.sources(), ["downloads/Show*"]);
+        assert_eq!(args.target(), None);
+
+        let args = parse(&["mediar", "downloads/Show.S01", "downloads/Show.S02", "/tv"]);
+        assert_eq!(args.sources(), ["downloads/Show.S01", "downloads/Show.S02"]);
+        assert_eq!(args.target(), Some("/tv"));
+    }
+
+    #[test]
+    fn test_with_saved() {
+        let parse = |argv: &[&str]| {
+            let matches = OrganizeArgs::augment_args(clap::Command::new("mediar"))
+                .try_get_matches_from(argv)
+                .unwrap();
+            OrganizeArgs::from_arg_matches(&matches).unwrap()
+        };
+        let saved = Marker {
+            order: Some(EpisodeOrder::Dvd),
+            title_style: Some(TitleStyle::Original),
+            season_offset: Some(2),
+            episode_offset: Some(1),
+            ..Default::default()
+        };
+
+        let args = parse(&["mediar", "downloads/Show"]);
+        let (order, options) =
+            with_saved(&args, &saved, &OrganizeOptions::try_from(&args).unwrap());
+        assert_eq!(order, Some(EpisodeOrder::Dvd));
+        assert_eq!(options.title_style, TitleStyle::Original);
+        assert_eq!((options.season_offset, options.episode_offset), (2, 1));
+
+        // Defaults given explicitly still win over the marker
+        let args = parse(&[
+            "mediar",
+            "downloads/Show",
+            "--order",
+            "aired",
+            "--title-style",
+            "localized",
+            "--season-offset",
+            "0",
+        ]);
+        let (order, options) =
+            with_saved(&args, &saved, &OrganizeOptions::try_from(&args).unwrap());
+        assert_eq!(order, Some(EpisodeOrder::Aired));
+        assert_eq!(options.title_style, TitleStyle::Localized);
+        assert_eq!((options.season_offset, options.episode_offset), (0, 1));
+    }
+
+    #[test]
+    fn test_merged_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("Show.Name.S01");
+        let second = temp_dir.path().join("Show.Name.S01.Part2");
+        let target = temp_dir.path().join("target");
+        create_test_files(&first, &[PathBuf::from("Show.Name.S01E01.mkv")]);
+        create_test_files(&second, &[PathBuf::from("Show.Name.S01E02.mkv")]);
+        let show = create_test_show();
+        let options = auto_confirm();
+
+        let (mode, mut plan) = prepare_tv(Mode::Copy, &first, Some(&target), &show, &options)
+            .unwrap()
+            .unwrap();
+        let (_, other) = prepare_tv(Mode::Copy, &second, Some(&target), &show, &options)
+            .unwrap()
+            .unwrap();
+        plan.merge(other).unwrap();
+        assert_eq!(plan.source, temp_dir.path());
+        execute_operations(&mode, plan, &options).unwrap();
+
+        let season = target.join("Show Name (2008)").join("Season 01");
+        assert_eq!(fs::read_dir(season).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_request_args() {
+        let request = OrganizeRequest {
+            path: "/downloads/Heat.1995".to_string(),
+            target: Some("/media/movies".to_string()),
+            mode: Mode::Copy,
+            tv_id: None,
+            movie_id: Some(949),
+            dry_run: true,
+        };
+        let args = request_args(&request, Some(Path::new("/tmp/plan.json"))).unwrap();
+        assert_eq!(args.sources(), ["/downloads/Heat.1995"]);
+        assert_eq!(args.target(), Some("/media/movies"));
+        assert_eq!(args.movie_id, Some(949));
+        assert!(args.auto);
+        assert_eq!(args.plan_out.as_deref(), Some(Path::new("/tmp/plan.json")));
+    }
+
+    #[test]
+    fn test_import_args() {
+        let args = import_args(
+            Path::new("/drop/Heat.1995.1080p"),
+            None,
+            Path::new("/state.db"),
+            None,
+        )
+        .unwrap();
+        assert_eq!(args.sources(), ["/drop/Heat.1995.1080p"]);
+        assert_eq!(args.target(), None);
+        assert!(args.auto);
+        assert_eq!(args.state_db.as_deref(), Some(Path::new("/state.db")));
+
+        let args = import_args(
+            Path::new("/drop/Show.S01"),
+            Some("/media"),
+            Path::new("/state.db"),
+            Some(0.9),
+        )
+        .unwrap();
+        assert_eq!(args.target(), Some("/media"));
+        assert_eq!(args.min_confidence, Some(0.9));
+    }
+
+    #[test]
+    fn test_plan_personal() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("italy");
+        let target = temp_dir.path().join("videos");
+        create_test_files(
+            &source,
+            &[
+                PathBuf::from("VID_20230614_181203.mp4"),
+                PathBuf::from("Venice/2023-06-20 Gondola.mov"),
+                PathBuf::from("notes.txt"),
+            ],
+        );
+
+        let plan = plan_personal(
+            &source,
+            &target,
+            personal::DEFAULT_TEMPLATE,
+            &PathFilter::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            plan.operations,
+            vec![
+                (
+                    source.join("VID_20230614_181203.mp4"),
+                    target.join("2023/2023-06-italy/VID_20230614_181203.mp4")
+                ),
+                (
+                    source.join("Venice/2023-06-20 Gondola.mov"),
+                    target.join("2023/2023-06-Venice/2023-06-20 Gondola.mov")
+                ),
+            ]
+        );
+        assert_eq!(plan.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_releases() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_files(
+            temp_dir.path(),
+            &[
+                PathBuf::from("Show.S01/a.mkv"),
+                PathBuf::from("Heat.1995.mkv"),
+                PathBuf::from(".mediar-journal.json"),
+            ],
+        );
+        assert_eq!(
+            releases(temp_dir.path()).unwrap(),
+            vec![
+                temp_dir.path().join("Heat.1995.mkv"),
+                temp_dir.path().join("Show.S01"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_map_episode() {
+        let options = OrganizeOptions {
+            season_offset: 1,
+            episode_offset: -1,
+            season_map: vec![SeasonMap {
+                season: 6,
+                to_season: Some(5),
+                episode_offset: 13,
+            }],
+            ..Default::default()
+        };
+        assert_eq!(options.map_episode(1, 2), (2, 1));
+        assert_eq!(options.map_episode(6, 1), (5, 14));
+        assert_eq!(OrganizeOptions::default().map_episode(3, 4), (3, 4));
+    }
+
+    #[test]
+    fn test_seasons_needed() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path();
+        for file in [
+            "Show.S02E01.mkv",
+            "Show.S02E02.en.srt",
+            "Show.S06E01.mkv",
+            "Featurettes/Making Of.mkv",
+        ] {
+            fs::create_dir_all(source.join(file).parent().unwrap()).unwrap();
+            fs::write(source.join(file), "video").unwrap();
+        }
+        let config = Config::parse(
+            r#"
+            [show."Other Show"]
+            season_map = [{ season = 6, to_season = 5 }]
+            "#,
+        )
+        .unwrap();
+        let options = OrganizeOptions {
+            season_offset: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            seasons_needed(source, &config, &options).unwrap(),
+            Some(BTreeSet::from([3, 5, 7]))
+        );
+
+        let review = OrganizeOptions {
+            review: true,
+            ..Default::default()
+        };
+        assert_eq!(seasons_needed(source, &config, &review).unwrap(), None);
+
+        fs::write(source.join("Pilot.mkv"), "video").unwrap();
+        assert_eq!(
+            seasons_needed(source, &config, &OrganizeOptions::default()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_title_dir() {
+        let options = OrganizeOptions {
+            sort_articles: true,
+            letter_folders: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            options.title_dir("The Wire", 2002, 1438),
+            Path::new("W/Wire, The (2002)")
+        );
+        assert_eq!(
+            OrganizeOptions::default().title_dir("The Wire", 2002, 1438),
+            Path::new("The Wire (2002)")
+        );
+    }
+
+    #[test]
+    fn test_plan_movie_original_title() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &[PathBuf::from("Brat.1997.1080p.mkv")]);
+
+        let movie = Movie {
+            title: "Brother".to_string(),
+            original_title: Some("Брат".to_string()),
+            release_date: "1997-12-12".to_string(),
+            ..create_test_movie()
+        };
+        let options = OrganizeOptions {
+            title_style: TitleStyle::Original,
+            transliterate: true,
+            ..auto_confirm()
+        };
+        let plan = plan_movie(&source, &target, &movie, &options, &Corrections::default()).unwrap();
+        assert_eq!(
+            plan.operations[0].1,
+            target.join("Brat (1997)").join("Brat (1997).mkv")
+        );
+    }
+
+    #[test]
+    fn test_organize_movie_upgrade_replace() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        let existing = target
+            .join("Movie Name (1999)")
+            .join("Movie Name (1999).mkv");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(existing.parent().unwrap()).unwrap();
+        fs::write(source.join("Movie.Name.1999.mkv"), "bigger file").unwrap();
+        fs::write(&existing, "small").unwrap();
+
+        let options = OrganizeOptions {
+            upgrade: Some(UpgradePolicy::Replace),
+            ..auto_confirm()
+        };
+        let result = organize_movie(
+            Mode::Copy,
+            &source,
+            Some(&target),
+            &create_test_movie(),
+            &options,
+        );
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed: {:?}",
+            result.err()
+        );
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "bigger file");
+        assert!(!journal::aside_path(&existing).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_organize_movie_upgrade_replace_rolled_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        let existing = target
+            .join("Movie Name (1999)")
+            .join("Movie Name (1999).mkv");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(existing.parent().unwrap()).unwrap();
+        fs::write(source.join("Movie.Name.1999.mkv"), "bigger file").unwrap();
+        fs::write(&existing, "small").unwrap();
+
+        let options = OrganizeOptions {
+            upgrade: Some(UpgradePolicy::Replace),
+            atomic: true,
+            file_hooks: vec!["false".to_string()],
+            ..auto_confirm()
+        };
+        let result = organize_movie(
+            Mode::Move,
+            &source,
+            Some(&target),
+            &create_test_movie(),
+            &options,
+        );
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "small");
+        assert_eq!(
+            fs::read_to_string(source.join("Movie.Name.1999.mkv")).unwrap(),
+            "bigger file"
+        );
+        assert!(!journal::aside_path(&existing).exists());
+    }
+
+    #[test]
+    fn test_organize_movie_upgrade_replace_plan_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        let existing = target
+            .join("Movie Name (1999)")
+            .join("Movie Name (1999).mkv");
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(existing.parent().unwrap()).unwrap();
+        fs::write(source.join("Movie.Name.1999.mkv"), "bigger file").unwrap();
+        fs::write(&existing, "small").unwrap();
+
+        let plan_path = temp_dir.path().join("plan.json");
+        let options = OrganizeOptions {
+            upgrade: Some(UpgradePolicy::Replace),
+            plan_out: Some(plan_path.clone()),
+            ..auto_confirm()
+        };
+        organize_movie(
+            Mode::Copy,
+            &source,
+            Some(&target),
+            &create_test_movie(),
+            &options,
+        )
+        .unwrap();
+        let plan_file = PlanFile::load(&plan_path).unwrap();
+        assert_eq!(plan_file.replaced, vec![existing.clone()]);
+
+        // The existing target is expected, so the operation is kept and replaces it
+        let plan = plan_file.into_plan().unwrap();
+        assert_eq!(plan.operations.len(), 1);
+        execute_operations(&Mode::Copy, plan, &auto_confirm()).unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "bigger file");
+        assert!(!journal::aside_path(&existing).exists());
+    }
+
+    #[test]
+    fn test_organize_movie_multiple_files_different_extensions() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let movie_files = vec![
+            Path::new("Movie.Name.1999.mkv").to_path_buf(),
+            Path::new("Movie.Name.1999.srt").to_path_buf(),
+        ];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = create_test_movie();
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed with multiple files of different extensions"
+        );
+
+        let movie_dir = target.join("Movie Name (1999)");
+        assert!(movie_dir.exists(), "Movie directory should exist");
+
+        let expected_mkv = movie_dir.join("Movie Name (1999).mkv");
+        assert!(
+            expected_mkv.exists(),
+            "Movie mkv file should exist in target: {:?}",
+            expected_mkv
+        );
+
+        let expected_srt = movie_dir.join("Movie Name (1999).srt");
+        assert!(
+            expected_srt.exists(),
+            "Movie srt file should exist in target: {:?}",
+            expected_srt
+        );
+    }
+
+    #[test]
+    fn test_organize_movie_duplicate_extension_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let movie_files = vec![
+            Path::new("Movie.Name.1999.1080p.mkv").to_path_buf(),
+            Path::new("Movie.Name.1999.720p.mkv").to_path_buf(),
+        ];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = create_test_movie();
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm());
+
+        assert!(
+            result.is_err(),
+            "organize_movie should fail when multiple files map to same output"
+        );
+
+        let error_msg = result.unwrap_err().to_string();
+        assert!(
+            error_msg.contains("Multiple input files map to the same output"),
+            "Error message should mention duplicate output: {}",
+            error_msg
+        );
+    }
+
+    #[test]
+    fn test_organize_movie_version_suffix() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(
+            &source,
+            &[
+                PathBuf::from("Movie.Name.1999.1080p.mkv"),
+                PathBuf::from("Movie.Name.1999.2160p.mkv"),
+            ],
+        );
+
+        let options = OrganizeOptions {
+            version_suffix: Some(VersionSuffix::Quality),
+            ..auto_confirm()
+        };
+        let result = organize_movie(
+            Mode::Copy,
+            &source,
+            Some(&target),
+            &create_test_movie(),
+            &options,
+        );
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed: {:?}",
+            result.err()
+        );
+
+        let movie_dir = target.join("Movie Name (1999)");
+        assert!(movie_dir.join("Movie Name (1999) - 1080p.mkv").exists());
+        assert!(movie_dir.join("Movie Name (1999) - 2160p.mkv").exists());
+    }
+
+    #[test]
+    fn test_organize_movie_version_suffix_sidecars() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(
+            &source,
+            &[
+                PathBuf::from("HD/Movie.Name.1999.1080p.mkv"),
+                PathBuf::from("HD/Movie.Name.1999.en.srt"),
+                PathBuf::from("UHD/Movie.Name.1999.2160p.mkv"),
+                PathBuf::from("UHD/Movie.Name.1999.2160p.rus.mka"),
+            ],
+        );
+
+        let options = OrganizeOptions {
+            version_suffix: Some(VersionSuffix::Quality),
+            ..auto_confirm()
+        };
+        organize_movie(
+            Mode::Copy,
+            &source,
+            Some(&target),
+            &create_test_movie(),
+            &options,
+        )
+        .unwrap();
+
+        let movie_dir = target.join("Movie Name (1999)");
+        assert!(movie_dir.join("Movie Name (1999) - 1080p.en.srt").exists());
+        assert!(movie_dir.join("Movie Name (1999) - 2160p.rus.mka").exists());
+    }
+
+    #[test]
+    fn test_organize_movie_editions() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let movie_files = vec![
+            Path::new("Movie.Name.1999.1080p.mkv").to_path_buf(),
+            Path::new("Movie.Name.1999.Directors.Cut.1080p.mkv").to_path_buf(),
+            Path::new("Movie.Name.1999.Extended.1080p.mkv").to_path_buf(),
+        ];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = create_test_movie();
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed with different editions: {:?}",
+            result.err()
+        );
+
+        let movie_dir = target.join("Movie Name (1999)");
+        for expected_file in [
+            movie_dir.join("Movie Name (1999).mkv"),
+            movie_dir.join("Movie Name (1999) {edition-Director's Cut}.mkv"),
+            movie_dir.join("Movie Name (1999) {edition-Extended}.mkv"),
+        ] {
+            assert!(
+                expected_file.exists(),
+                "Edition file should exist in target: {:?}",
+                expected_file
+            );
+        }
+    }
+
+    #[test]
+    fn test_organize_movie_disc_rip() {
+        let temp_dir = TempDir::new().unwrap();
+        let bluray = temp_dir.path().join("Movie.Name.1999.COMPLETE.BLURAY");
+        let iso = temp_dir.path().join("Movie.Name.1999.DVD");
+        let target = temp_dir.path().join("target");
+        create_test_files(
+            &bluray,
+            &[
+                PathBuf::from("BDMV/index.bdmv"),
+                PathBuf::from("BDMV/STREAM/00000.m2ts"),
+            ],
+        );
+        create_test_files(&iso, &[PathBuf::from("Movie.Name.1999.DVD.iso")]);
+        let movie = create_test_movie();
+
+        organize_movie(Mode::Copy, &bluray, Some(&target), &movie, &auto_confirm()).unwrap();
+        let movie_dir = target.join("Movie Name (1999)");
+        assert!(movie_dir.join("BDMV/index.bdmv").exists());
+        assert!(movie_dir.join("BDMV/STREAM/00000.m2ts").exists());
+
+        let dvd_target = temp_dir.path().join("dvd");
+        organize_movie(Mode::Copy, &iso, Some(&dvd_target), &movie, &auto_confirm()).unwrap();
+        assert!(
+            dvd_target
+                .join("Movie Name (1999)/Movie Name (1999).iso")
+                .exists()
+        );
     }
 
-    fn test_other_files() -> Vec<PathBuf> {
-        vec![
-            Path::new("readme.txt").to_path_buf(),
-            Path::new("trailer.mp4").to_path_buf(),
-            Path::new("s02").join("Show.S02E01.thumb.jpg").to_path_buf(),
-        ]
+    #[test]
+    fn test_organize_movie_sidecars() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(
+            &source,
+            &[
+                PathBuf::from("Movie.Name.1999.mkv"),
+                PathBuf::from("Movie.Name.1999.rus.mka"),
+                PathBuf::from("Movie.Name.1999.en.srt"),
+            ],
+        );
+        let movie = create_test_movie();
+
+        organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm()).unwrap();
+        let movie_dir = target.join("Movie Name (1999)");
+        assert!(movie_dir.join("Movie Name (1999).mkv").exists());
+        assert!(movie_dir.join("Movie Name (1999).rus.mka").exists());
+        assert!(movie_dir.join("Movie Name (1999).en.srt").exists());
     }
 
-    fn test_files() -> Vec<PathBuf> {
-        [test_episode_files(), test_other_files()].concat()
+    #[test]
+    fn test_organize_movie_edition_suffix_style() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let movie_files = vec![Path::new("Movie.Name.1999.Extended.Cut.mkv").to_path_buf()];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = create_test_movie();
+        let options = OrganizeOptions {
+            edition_style: EditionStyle::Suffix,
+            ..auto_confirm()
+        };
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &options);
+
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed: {:?}",
+            result.err()
+        );
+
+        let expected_file = target
+            .join("Movie Name (1999)")
+            .join("Movie Name (1999) - Extended.mkv");
+        assert!(
+            expected_file.exists(),
+            "Edition file should exist in target: {:?}",
+            expected_file
+        );
     }
 
-    fn create_test_files(base_dir: &Path, files: &[PathBuf]) {
-        for file_name in files {
-            let file_path = base_dir.join(file_name);
-            fs::create_dir_all(file_path.parent().unwrap()).unwrap();
-            fs::File::create(&file_path).unwrap();
+    #[test]
+    fn test_organize_movie_multi_part() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let movie_files = vec![
+            Path::new("Movie.Name.1999.CD1.avi").to_path_buf(),
+            Path::new("Movie.Name.1999.CD2.avi").to_path_buf(),
+        ];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = create_test_movie();
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed with multi-part files: {:?}",
+            result.err()
+        );
+
+        let movie_dir = target.join("Movie Name (1999)");
+        for expected_file in [
+            movie_dir.join("Movie Name (1999) - cd1.avi"),
+            movie_dir.join("Movie Name (1999) - cd2.avi"),
+        ] {
+            assert!(
+                expected_file.exists(),
+                "Part file should exist in target: {:?}",
+                expected_file
+            );
         }
     }
 
     #[test]
-    fn test_organize_with_autoconfirm() {
+    fn test_organize_movie_part_in_title() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        let movie_files = vec![Path::new("Movie.Name.Part.1.2010.mkv").to_path_buf()];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = Movie {
+            title: "Movie Name Part 1".to_string(),
+            release_date: "2010-11-19".to_string(),
+            ..create_test_movie()
+        };
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm());
+
+        assert!(
+            result.is_ok(),
+            "organize_movie should succeed: {:?}",
+            result.err()
+        );
+
+        let expected_file = target
+            .join("Movie Name Part 1 (2010)")
+            .join("Movie Name Part 1 (2010).mkv");
+        assert!(
+            expected_file.exists(),
+            "Movie file should not get a part suffix: {:?}",
+            expected_file
+        );
+    }
+
+    #[test]
+    fn test_organize_movie_exclude_patterns() {
         let temp_dir = TempDir::new().unwrap();
         let source = temp_dir.path().join("source");
         let target = temp_dir.path().join("target");
 
-        fs::create_dir_all(&source).unwrap();
-        create_test_files(&source, &test_files());
+        let movie_files = vec![
+            Path::new("Movie.Name.1999.mkv").to_path_buf(),
+            Path::new("Movie.Name.1999.trailer.mkv").to_path_buf(),
+            Path::new("Extras").join("Making.Of.mkv").to_path_buf(),
+        ];
 
-        let show = create_test_show();
+        create_test_files(&source, &movie_files);
 
-        let result = organize_tv(Mode::Move, &source, Some(&target), &show, true);
+        let movie = create_test_movie();
+        let options = OrganizeOptions {
+            filter: PathFilter::new(&[], &["Extras/".to_string(), "*.trailer.mkv".to_string()])
+                .unwrap(),
+            ..auto_confirm()
+        };
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &options);
 
         assert!(
             result.is_ok(),
-            "organize should succeed: {:?}",
+            "organize_movie should succeed when extras are excluded: {:?}",
             result.err()
         );
 
-        // With auto-confirm, files should be moved
-        for file_name in &test_episode_files() {
-            let original_path = source.join(file_name);
-            assert!(
-                !original_path.exists(),
-                "Original video file should be moved: {:?}",
-                file_name
-            );
-        }
-
-        let show_dir = target.join("Show Name (2008)");
-        assert!(show_dir.exists(), "Show directory should exist");
+        let movie_dir = target.join("Movie Name (1999)");
+        let organized: Vec<_> = fs::read_dir(&movie_dir).unwrap().collect();
+        assert_eq!(
+            organized.len(),
+            1,
+            "Only the main movie should be organized"
+        );
+        assert!(movie_dir.join("Movie Name (1999).mkv").exists());
     }
 
     #[test]
-    fn test_organize_copy() {
+    fn test_organize_move_with_trash() {
         let temp_dir = TempDir::new().unwrap();
         let source = temp_dir.path().join("source");
         let target = temp_dir.path().join("target");
 
-        create_test_files(&source, &test_files());
+        create_test_files(&source, &test_episode_files());
 
         let show = create_test_show();
+        let options = OrganizeOptions {
+            trash_retention_days: Some(trash::DEFAULT_RETENTION_DAYS),
+            ..auto_confirm()
+        };
 
-        let result = organize_tv(Mode::Copy, &source, Some(&target), &show, true);
+        let result = organize_tv(Mode::Move, &source, Some(&target), &show, &options);
+        assert!(result.is_ok(), "organize_tv failed: {:?}", result.err());
 
+        for file in test_episode_files() {
+            assert!(!source.join(&file).exists(), "{:?} should be staged", file);
+        }
+        let staged = WalkDir::new(source.join(trash::TRASH_DIR))
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .count();
+        assert_eq!(staged, test_episode_files().len());
         assert!(
-            result.is_ok(),
-            "organize should succeed: {:?}",
-            result.err()
+            target
+                .join("Show Name (2008)")
+                .join("Season 01")
+                .join("Show Name - S01E01 - One.mkv")
+                .exists()
         );
 
-        for file_name in &test_files() {
-            let original_path = source.join(file_name);
-            assert!(
-                original_path.exists(),
-                "Original file should still exist after copy: {:?}",
-                file_name
-            );
-        }
+        // Staged files are not picked up again
+        let result = organize_tv(Mode::Move, &source, Some(&target), &show, &options);
+        assert!(result.is_ok(), "second run failed: {:?}", result.err());
+    }
 
-        let show_dir = target.join("Show Name (2008)");
-        assert!(show_dir.exists(), "Show directory should exist");
+    #[test]
+    fn test_organize_parallel_devices() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
 
-        let season1_dir = show_dir.join("Season 01");
-        assert!(season1_dir.exists(), "Season 1 directory should exist");
+        create_test_files(&source, &test_episode_files());
 
-        let season2_dir = show_dir.join("Season 02");
-        assert!(season2_dir.exists(), "Season 2 directory should exist");
+        let show = create_test_show();
+        let options = OrganizeOptions {
+            parallel_devices: true,
+            ..auto_confirm()
+        };
 
-        for expected_file in [
-            season1_dir.join("Show Name - S01E01 - One.mkv"),
-            season1_dir.join("Show Name - S01E01 - One.srt"),
-            season1_dir.join("Show Name - S01E02 - Two.mp4"),
-            season2_dir.join("Show Name - S02E01 - Three.avi"),
-        ] {
-            assert!(
-                expected_file.exists(),
-                "Expected file should exist: {:?}",
-                expected_file
-            );
-        }
+        let result = organize_tv(Mode::Link, &source, Some(&target), &show, &options);
+        assert!(result.is_ok(), "organize_tv failed: {:?}", result.err());
+        assert!(
+            target
+                .join("Show Name (2008)")
+                .join("Season 02")
+                .join("Show Name - S02E01 - Three.avi")
+                .exists()
+        );
     }
 
     #[test]
-    fn test_organize_rename_inplace() {
+    fn test_organize_sample() {
         let temp_dir = TempDir::new().unwrap();
         let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
 
-        create_test_files(&source, &test_files());
+        create_test_files(&source, &test_episode_files());
 
         let show = create_test_show();
+        let options = OrganizeOptions {
+            sample: Some(2),
+            ..auto_confirm()
+        };
 
-        let result = organize_tv(Mode::Move, &source, None, &show, true);
+        let result = organize_tv(Mode::Copy, &source, Some(&target), &show, &options);
+        assert!(result.is_ok(), "organize_tv failed: {:?}", result.err());
 
+        let organized = WalkDir::new(&target)
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .count();
+        assert_eq!(organized, 2);
+    }
+
+    #[test]
+    fn test_organize_plan_out_and_apply() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &test_episode_files());
+
+        let plan_path = temp_dir.path().join("plan.json");
+        let options = OrganizeOptions {
+            plan_out: Some(plan_path.clone()),
+            ..auto_confirm()
+        };
+        organize_tv(
+            Mode::Copy,
+            &source,
+            Some(&target),
+            &create_test_show(),
+            &options,
+        )
+        .unwrap();
+        assert!(!target.exists());
+
+        let plan_file = PlanFile::load(&plan_path).unwrap();
+        assert_eq!(plan_file.content, ContentType::Show);
+        assert_eq!(plan_file.tmdb_id, 42);
+        assert_eq!(plan_file.operations.len(), 4);
+
+        execute_operations(&Mode::Copy, plan_file.into_plan().unwrap(), &auto_confirm()).unwrap();
         assert!(
-            result.is_ok(),
-            "organize should succeed: {:?}",
-            result.err()
+            target
+                .join("Show Name (2008)/Season 01/Show Name - S01E02 - Two.mp4")
+                .exists()
         );
+    }
 
-        for file_name in &test_episode_files() {
-            let original_path = source.join(file_name);
-            assert!(
-                !original_path.exists(),
-                "Original video file should be moved/renamed: {:?}",
-                file_name
-            );
-        }
+    #[test]
+    fn test_execute_operations_keep_going() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &[PathBuf::from("b.mkv")]);
 
-        for file_name in &test_other_files() {
-            let original_path = source.join(file_name);
-            assert!(
-                original_path.exists(),
-                "Regular file should remain untouched: {:?}",
-                file_name
-            );
-        }
+        let plan = Plan {
+            source: source.clone(),
+            operations: vec![
+                (source.join("a.mkv"), target.join("A.mkv")),
+                (source.join("b.mkv"), target.join("B.mkv")),
+            ],
+            ..Default::default()
+        };
+        let options = OrganizeOptions {
+            keep_going: true,
+            ..auto_confirm()
+        };
+        let err = execute_operations(&Mode::Link, plan, &options).unwrap_err();
 
-        let parent = temp_dir.path();
-        let show_dir = parent.join("Show Name (2008)");
-        assert!(show_dir.exists(), "Show directory should exist in parent");
+        assert_eq!(err.to_string(), "1 of 2 operation(s) failed");
+        assert!(target.join("B.mkv").exists());
 
-        let season1_dir = show_dir.join("Season 01");
-        let season2_dir = show_dir.join("Season 02");
-        assert!(season1_dir.exists(), "Season 1 directory should exist");
-        assert!(season2_dir.exists(), "Season 2 directory should exist");
+        // The failed operation is left in the journal, with the options of the run
+        let journal = JournalFile::load(&JournalFile::path(&source)).unwrap();
+        assert!(journal.settings.keep_going);
+        assert_eq!(journal.completed.len(), 1);
+        assert_eq!(journal.remaining[0].source, source.join("a.mkv"));
 
-        for expected_file in [
-            season1_dir.join("Show Name - S01E01 - One.mkv"),
-            season1_dir.join("Show Name - S01E01 - One.srt"),
-            season1_dir.join("Show Name - S01E02 - Two.mp4"),
-            season2_dir.join("Show Name - S02E01 - Three.avi"),
-        ] {
-            assert!(
-                expected_file.exists(),
-                "Renamed file should exist: {:?}",
-                expected_file
-            );
-        }
+        create_test_files(&source, &[PathBuf::from("a.mkv")]);
+        resume_run(&source, true).unwrap();
+        assert!(target.join("A.mkv").exists());
+        assert!(!JournalFile::path(&source).exists());
     }
 
-    fn create_test_movie() -> Movie {
-        Movie {
-            id: 550,
-            title: "Movie Name".to_string(),
-            overview: "Movie description".to_string(),
-            release_date: "1999-10-15".to_string(),
-            original_language: "en".to_string(),
-            popularity: 63.869,
-        }
+    #[test]
+    fn test_execute_operations_atomic_rolls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &[PathBuf::from("a.mkv")]);
+
+        let plan = Plan {
+            source: source.clone(),
+            operations: vec![
+                (source.join("a.mkv"), target.join("Show/A.mkv")),
+                (source.join("missing.mkv"), target.join("Show/B.mkv")),
+            ],
+            ..Default::default()
+        };
+        let options = OrganizeOptions {
+            atomic: true,
+            // The missing source would otherwise fail the hard link check up front
+            allow_hardlinked: true,
+            ..auto_confirm()
+        };
+        let err = execute_operations(&Mode::Move, plan, &options).unwrap_err();
+
+        assert!(
+            err.to_string()
+                .starts_with("Rolled back 1 completed operation(s)")
+        );
+        assert!(source.join("a.mkv").exists());
+        assert!(!target.exists());
+        assert!(!JournalFile::path(&source).exists());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_organize_movie_copy() {
+    fn test_execute_operations_post_hook() {
         let temp_dir = TempDir::new().unwrap();
         let source = temp_dir.path().join("source");
         let target = temp_dir.path().join("target");
+        let out = temp_dir.path().join("hook.txt");
+        create_test_files(&source, &[PathBuf::from("a.mkv")]);
+
+        let plan = Plan {
+            source: source.clone(),
+            operations: vec![(source.join("a.mkv"), target.join("A.mkv"))],
+            media: Some(Media {
+                content: ContentType::Movie,
+                tmdb_id: 550,
+                title: "Fight Club".to_string(),
+            }),
+            ..Default::default()
+        };
+        let options = OrganizeOptions {
+            post_hooks: vec![format!(
+                "echo \"$MEDIAR_TITLE $MEDIAR_TARGETS\" > '{}'",
+                out.display()
+            )],
+            ..auto_confirm()
+        };
+        execute_operations(&Mode::Copy, plan, &options).unwrap();
+        assert_eq!(
+            fs::read_to_string(&out).unwrap(),
+            format!("Fight Club {}\n", target.join("A.mkv").display())
+        );
+    }
 
-        let movie_files = vec![Path::new("Movie.Name.1080p.mkv").to_path_buf()];
+    #[test]
+    fn test_resume_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_test_files(&source, &[PathBuf::from("a.mkv"), PathBuf::from("b.mkv")]);
+        create_test_files(&target, &[PathBuf::from("A.mkv")]);
+
+        JournalFile {
+            mode: Mode::Copy,
+            source: source.clone(),
+            settings: ExecutionSettings::default(),
+            completed: vec![plan::PlannedOperation {
+                source: source.join("a.mkv"),
+                target: target.join("A.mkv"),
+            }],
+            remaining: vec![plan::PlannedOperation {
+                source: source.join("b.mkv"),
+                target: target.join("B.mkv"),
+            }],
+            replaced: Vec::new(),
+        }
+        .save()
+        .unwrap();
 
-        create_test_files(&source, &movie_files);
+        resume_run(&source, true).unwrap();
+        assert!(target.join("B.mkv").exists());
+        assert!(!JournalFile::path(&source).exists());
+    }
+
+    #[test]
+    fn test_organize_movie_id_tag() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+
+        create_test_files(&source, &[PathBuf::from("Movie.Name.1999.mkv")]);
 
         let movie = create_test_movie();
+        let options = OrganizeOptions {
+            id_tag: Some(IdTag::Jellyfin),
+            ..auto_confirm()
+        };
 
-        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, true);
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &options);
+        assert!(result.is_ok(), "organize_movie failed: {:?}", result.err());
 
         assert!(
-            result.is_ok(),
-            "organize_movie should succeed: {:?}",
-            result.err()
+            target
+                .join(format!("Movie Name (1999) [tmdbid-{}]", movie.id))
+                .join("Movie Name (1999).mkv")
+                .exists()
         );
+    }
 
-        for file_name in &movie_files {
-            let original_path = source.join(file_name);
-            assert!(
-                original_path.exists(),
-                "Original file should still exist after copy: {:?}",
-                file_name
-            );
-        }
+    #[test]
+    fn test_organize_movie_rules() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        let uhd = temp_dir.path().join("uhd");
 
-        let movie_dir = target.join("Movie Name (1999)");
-        assert!(movie_dir.exists(), "Movie directory should exist");
+        let movie_files = vec![
+            Path::new("Movie.Name.1999.2160p.mkv").to_path_buf(),
+            Path::new("Movie.Name.1999.1080p.mkv").to_path_buf(),
+            Path::new("Movie.Name.1999.avi").to_path_buf(),
+        ];
+
+        create_test_files(&source, &movie_files);
+
+        let movie = create_test_movie();
+        let options = OrganizeOptions {
+            rules: Rules::new(&[
+                RuleConfig {
+                    path: Some("*2160p*".to_string()),
+                    target: Some(uhd.clone()),
+                    edition: Some("4K".to_string()),
+                    ..Default::default()
+                },
+                RuleConfig {
+                    extension: vec!["avi".to_string()],
+                    skip: true,
+                    ..Default::default()
+                },
+            ])
+            .unwrap(),
+            ..auto_confirm()
+        };
+
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &options);
+        assert!(result.is_ok(), "organize_movie failed: {:?}", result.err());
 
-        let expected_file = movie_dir.join("Movie Name (1999).mkv");
         assert!(
-            expected_file.exists(),
-            "Movie file should exist in target: {:?}",
-            expected_file
+            uhd.join("Movie Name (1999)")
+                .join("Movie Name (1999) {edition-4K}.mkv")
+                .exists()
         );
+        let movie_dir = target.join("Movie Name (1999)");
+        let organized: Vec<_> = fs::read_dir(&movie_dir).unwrap().collect();
+        assert_eq!(organized.len(), 1, "The avi should be skipped by rule");
+        assert!(movie_dir.join("Movie Name (1999).mkv").exists());
     }
 
     #[test]
-    fn test_organize_movie_multiple_files_different_extensions() {
+    fn test_organize_movie_extras() {
         let temp_dir = TempDir::new().unwrap();
         let source = temp_dir.path().join("source");
         let target = temp_dir.path().join("target");
 
         let movie_files = vec![
             Path::new("Movie.Name.1999.mkv").to_path_buf(),
-            Path::new("Movie.Name.1999.srt").to_path_buf(),
+            Path::new("Extras").join("Making.Of.mkv").to_path_buf(),
+            Path::new("Featurettes")
+                .join("Visual Effects.mkv")
+                .to_path_buf(),
+            Path::new("Interview-behindthescenes.mp4").to_path_buf(),
         ];
 
         create_test_files(&source, &movie_files);
 
         let movie = create_test_movie();
 
-        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, true);
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &auto_confirm());
 
         assert!(
             result.is_ok(),
-            "organize_movie should succeed with multiple files of different extensions"
+            "organize_movie should route extras: {:?}",
+            result.err()
         );
 
         let movie_dir = target.join("Movie Name (1999)");
-        assert!(movie_dir.exists(), "Movie directory should exist");
-
-        let expected_mkv = movie_dir.join("Movie Name (1999).mkv");
-        assert!(
-            expected_mkv.exists(),
-            "Movie mkv file should exist in target: {:?}",
-            expected_mkv
-        );
-
-        let expected_srt = movie_dir.join("Movie Name (1999).srt");
-        assert!(
-            expected_srt.exists(),
-            "Movie srt file should exist in target: {:?}",
-            expected_srt
-        );
+        for expected_file in [
+            movie_dir.join("Movie Name (1999).mkv"),
+            movie_dir.join("Other").join("Making.Of.mkv"),
+            movie_dir.join("Featurettes").join("Visual Effects.mkv"),
+            movie_dir
+                .join("Behind The Scenes")
+                .join("Interview-behindthescenes.mp4"),
+        ] {
+            assert!(
+                expected_file.exists(),
+                "Expected file should exist: {:?}",
+                expected_file
+            );
+        }
     }
 
-    #[test]
-    fn test_organize_movie_duplicate_extension_fails() {
+    fn organize_movie_with_trailer(trailers: TrailerPolicy) -> (TempDir, PathBuf) {
         let temp_dir = TempDir::new().unwrap();
         let source = temp_dir.path().join("source");
         let target = temp_dir.path().join("target");
 
-        let movie_files = vec![
-            Path::new("Movie.Name.1999.1080p.mkv").to_path_buf(),
-            Path::new("Movie.Name.1999.720p.mkv").to_path_buf(),
-        ];
-
-        create_test_files(&source, &movie_files);
+        create_test_files(
+            &source,
+            &[
+                Path::new("Movie.Name.1999.mkv").to_path_buf(),
+                Path::new("Movie.Name.1999-trailer.mp4").to_path_buf(),
+            ],
+        );
 
         let movie = create_test_movie();
+        let options = OrganizeOptions {
+            trailers,
+            ..auto_confirm()
+        };
 
-        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, true);
+        let result = organize_movie(Mode::Copy, &source, Some(&target), &movie, &options);
 
         assert!(
-            result.is_err(),
-            "organize_movie should fail when multiple files map to same output"
+            result.is_ok(),
+            "organize_movie should handle trailers: {:?}",
+            result.err()
         );
 
-        let error_msg = result.unwrap_err().to_string();
+        let movie_dir = target.join("Movie Name (1999)");
+        (temp_dir, movie_dir)
+    }
+
+    #[test]
+    fn test_organize_movie_trailers_folder() {
+        let (_temp_dir, movie_dir) = organize_movie_with_trailer(TrailerPolicy::Folder);
+        assert!(movie_dir.join("Movie Name (1999).mkv").exists());
         assert!(
-            error_msg.contains("Multiple input files map to the same output"),
-            "Error message should mention duplicate output: {}",
-            error_msg
+            movie_dir
+                .join("Trailers")
+                .join("Movie.Name.1999-trailer.mp4")
+                .exists()
         );
     }
 
+    #[test]
+    fn test_organize_movie_trailers_inline() {
+        let (_temp_dir, movie_dir) = organize_movie_with_trailer(TrailerPolicy::Inline);
+        assert!(movie_dir.join("Movie Name (1999).mkv").exists());
+        assert!(movie_dir.join("Movie Name (1999)-trailer.mp4").exists());
+    }
+
+    #[test]
+    fn test_organize_movie_trailers_skip() {
+        let (_temp_dir, movie_dir) = organize_movie_with_trailer(TrailerPolicy::Skip);
+        assert!(movie_dir.join("Movie Name (1999).mkv").exists());
+        assert_eq!(fs::read_dir(&movie_dir).unwrap().count(), 1);
+    }
+
     #[test]
     fn test_organize_movie_with_autoconfirm() {
         let temp_dir = TempDir::new().unwrap();
@@ -1095,7 +6586,7 @@ mod tests {
 
         let movie = create_test_movie();
 
-        let result = organize_movie(Mode::Move, &source, Some(&target), &movie, true);
+        let result = organize_movie(Mode::Move, &source, Some(&target), &movie, &auto_confirm());
 
         assert!(
             result.is_ok(),
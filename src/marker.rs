@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{naming::TitleStyle, tmdb::EpisodeOrder};
+
+/// Written in a source directory to remember what it was matched to
+pub const MARKER_FILE: &str = ".mediar.toml";
+
+/// The match and naming options chosen for a source directory, reused on later runs
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Marker {
+    pub tv_id: Option<i32>,
+    pub movie_id: Option<i32>,
+    pub order: Option<EpisodeOrder>,
+    pub title_style: Option<TitleStyle>,
+    pub season_offset: Option<i32>,
+    pub episode_offset: Option<i32>,
+}
+
+impl Marker {
+    /// Where the marker for a source directory is kept
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(MARKER_FILE)
+    }
+
+    /// The marker in a source directory, if it has one
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(dir);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&text)
+            .map(Some)
+            .with_context(|| format!("Invalid marker file {}", path.display()))
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<PathBuf> {
+        let path = Self::path(dir);
+        fs::write(&path, toml::to_string(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_marker_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(Marker::load(temp_dir.path()).unwrap(), None);
+
+        let marker = Marker {
+            tv_id: Some(1396),
+            order: Some(EpisodeOrder::Dvd),
+            episode_offset: Some(-1),
+            ..Default::default()
+        };
+        let path = marker.save(temp_dir.path()).unwrap();
+        assert_eq!(path, temp_dir.path().join(MARKER_FILE));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "tv_id = 1396\norder = \"dvd\"\nepisode_offset = -1\n"
+        );
+        assert_eq!(Marker::load(temp_dir.path()).unwrap(), Some(marker));
+    }
+
+    #[test]
+    fn test_marker_invalid() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(Marker::path(temp_dir.path()), "tv_id = \"Breaking Bad\"\n").unwrap();
+        assert!(Marker::load(temp_dir.path()).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use strsim::normalized_levenshtein;
+
+use crate::video::ContentType;
+
+/// Default minimum confidence for `--auto` to pick a result without prompting
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.85;
+
+/// Episode title similarity needed to use a match without asking
+pub const EPISODE_TITLE_CONFIDENCE: f64 = 0.8;
+
+/// Episode title similarity below which a match is not even offered
+const MIN_EPISODE_TITLE_CONFIDENCE: f64 = 0.5;
+
+/// Results scoring within this margin of the best match make the choice ambiguous
+const AMBIGUITY_MARGIN: f64 = 0.05;
+
+/// Parse a minimum confidence such as `0.9`, which must lie between 0.0 and 1.0
+pub fn parse_confidence(value: &str) -> Result<f64> {
+    let confidence: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Expected a number between 0.0 and 1.0, got {}", value))?;
+    if !(0.0..=1.0).contains(&confidence) {
+        return Err(anyhow!(
+            "Confidence must be between 0.0 and 1.0, got {}",
+            value
+        ));
+    }
+    Ok(confidence)
+}
+
+/// Lowercase a title and reduce punctuation to single spaces
+fn normalize(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Confidence (0.0 to 1.0) that a search result is the title parsed from a filename
+pub fn confidence(
+    title: &str,
+    year: Option<i32>,
+    name: &str,
+    result_year: Option<i32>,
+    popularity: f64,
+) -> f64 {
+    let similarity = normalized_levenshtein(&normalize(title), &normalize(name));
+
+    // Popular results are more likely to be the intended one, but only nudge the score
+    let popularity = popularity.max(0.0);
+    let score = 0.9 * similarity + 0.1 * popularity / (popularity + 10.0);
+
+    match (year, result_year) {
+        (Some(year), Some(result_year)) if (year - result_year).abs() > 1 => score * 0.5,
+        (Some(year), Some(result_year)) if year != result_year => score * 0.9,
+        _ => score,
+    }
+}
+
+/// Pick the best-scoring result if it clears the threshold and is not ambiguous
+pub fn best_match<T>(
+    results: &[T],
+    min_confidence: f64,
+    score: impl Fn(&T) -> f64,
+) -> Option<(&T, f64)> {
+    let mut scored: Vec<_> = results
+        .iter()
+        .map(|result| (result, score(result)))
+        .collect();
+    scored.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+    let (best, best_score) = *scored.first()?;
+    if best_score < min_confidence {
+        return None;
+    }
+    if let Some((_, runner_up)) = scored.get(1)
+        && best_score - runner_up < AMBIGUITY_MARGIN
+    {
+        return None;
+    }
+
+    Some((best, best_score))
+}
+
+/// Similarity (0.0 to 1.0) of a filename's title words, without the show name, to an episode title
+pub fn episode_title_confidence(file_title: &str, show: &str, episode_title: &str) -> f64 {
+    let file_title = normalize(file_title);
+    let show = normalize(show);
+    let remainder = file_title
+        .strip_prefix(&show)
+        .map(str::trim_start)
+        .filter(|rest| !rest.is_empty())
+        .unwrap_or(&file_title);
+    normalized_levenshtein(remainder, &normalize(episode_title))
+}
+
+/// The episode whose title best matches a filename's title, with its confidence
+pub fn match_episode_title<'a, T>(
+    file_title: &str,
+    show: &str,
+    episodes: &'a [T],
+    episode_title: impl Fn(&T) -> &str,
+) -> Option<(&'a T, f64)> {
+    best_match(episodes, MIN_EPISODE_TITLE_CONFIDENCE, |episode| {
+        episode_title_confidence(file_title, show, episode_title(episode))
+    })
+}
+
+/// Matches chosen interactively during this run, keyed by the title parsed from the files
+#[derive(Debug, Default)]
+pub struct MatchMemory {
+    matches: HashMap<String, (ContentType, i32)>,
+}
+
+impl MatchMemory {
+    pub fn remember(&mut self, parsed_title: &str, content_type: ContentType, id: i32) {
+        self.matches
+            .insert(normalize(parsed_title), (content_type, id));
+    }
+
+    /// The content type and TMDB ID chosen earlier for the same parsed title
+    pub fn recall(&self, parsed_title: &str) -> Option<(ContentType, i32)> {
+        self.matches.get(&normalize(parsed_title)).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("Star Trek: The Next Generation"),
+            "star trek the next generation"
+        );
+        assert_eq!(normalize("  Mr. Robot "), "mr robot");
+    }
+
+    #[test]
+    fn test_confidence_exact_match() {
+        assert!(confidence("Breaking Bad", None, "Breaking Bad", Some(2008), 100.0) > 0.95);
+        assert!(confidence("breaking bad", None, "Breaking Bad", Some(2008), 0.0) >= 0.9);
+    }
+
+    #[test]
+    fn test_confidence_year_mismatch() {
+        let matching = confidence("The Office", Some(2005), "The Office", Some(2005), 50.0);
+        let mismatched = confidence("The Office", Some(2005), "The Office", Some(2001), 50.0);
+        assert!(matching >= DEFAULT_MIN_CONFIDENCE);
+        assert!(mismatched < DEFAULT_MIN_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_confidence_different_title() {
+        assert!(confidence("Office", None, "The Office", None, 50.0) < DEFAULT_MIN_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_parse_confidence() {
+        assert_eq!(parse_confidence("0.9").unwrap(), 0.9);
+        assert_eq!(parse_confidence("1").unwrap(), 1.0);
+        assert!(parse_confidence("5").is_err());
+        assert!(parse_confidence("-0.1").is_err());
+        assert!(parse_confidence("high").is_err());
+    }
+
+    #[test]
+    fn test_best_match() {
+        let results = [("Breaking Bad", 100.0), ("Breaking Badly", 1.0)];
+        let (best, _) = best_match(&results, DEFAULT_MIN_CONFIDENCE, |(name, pop)| {
+            confidence("Breaking Bad", None, name, None, *pop)
+        })
+        .unwrap();
+        assert_eq!(best.0, "Breaking Bad");
+    }
+
+    #[test]
+    fn test_best_match_below_threshold() {
+        let results = [("Something Else", 100.0)];
+        assert!(
+            best_match(&results, DEFAULT_MIN_CONFIDENCE, |(name, pop)| {
+                confidence("Breaking Bad", None, name, None, *pop)
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_best_match_ambiguous() {
+        let results = [("The Office", 50.0), ("The Office", 45.0)];
+        assert!(
+            best_match(&results, DEFAULT_MIN_CONFIDENCE, |(name, pop)| {
+                confidence("The Office", None, name, None, *pop)
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_episode_title_confidence() {
+        let score = episode_title_confidence(
+            "Friends The One With the Embryos",
+            "Friends",
+            "The One with the Embryos",
+        );
+        assert!(score > 0.99);
+        assert!(
+            episode_title_confidence(
+                "Friends The One With Embryos",
+                "Friends",
+                "The One with the Embryos"
+            ) >= EPISODE_TITLE_CONFIDENCE
+        );
+        assert!(
+            episode_title_confidence("Friends The One With the Embryos", "Friends", "Pilot")
+                < MIN_EPISODE_TITLE_CONFIDENCE
+        );
+    }
+
+    #[test]
+    fn test_match_episode_title() {
+        let episodes = [
+            "The One with the Embryos",
+            "The One with the Jellyfish",
+            "The One Where Ross Got High",
+        ];
+        let (best, _) = match_episode_title(
+            "Friends The One With the Embryos",
+            "Friends",
+            &episodes,
+            |title| title,
+        )
+        .unwrap();
+        assert_eq!(*best, "The One with the Embryos");
+        assert!(
+            match_episode_title("Friends Pilot", "Friends", &episodes, |title| title).is_none()
+        );
+    }
+
+    #[test]
+    fn test_match_memory() {
+        let mut memory = MatchMemory::default();
+        assert_eq!(memory.recall("The Office"), None);
+
+        memory.remember("The.Office", ContentType::Show, 2316);
+        assert_eq!(memory.recall("the office"), Some((ContentType::Show, 2316)));
+        assert_eq!(memory.recall("Office"), None);
+    }
+
+    #[test]
+    fn test_best_match_empty() {
+        let results: [(&str, f64); 0] = [];
+        assert!(best_match(&results, 0.0, |_| 1.0).is_none());
+    }
+}
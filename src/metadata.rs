@@ -0,0 +1,214 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+use crate::tmdb::{Movie, Show, TvSeason, TvSeasonEpisode};
+
+/// Describes a show that is not on TMDB, placed in the source directory
+pub const SHOW_METADATA_FILE: &str = "show.yaml";
+
+/// Describes a movie that is not on TMDB, placed in the source directory
+pub const MOVIE_METADATA_FILE: &str = "movie.yaml";
+
+/// A show described by hand in `show.yaml`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ShowMetadata {
+    pub name: String,
+    pub year: i32,
+    #[serde(default)]
+    pub overview: String,
+    #[serde(default)]
+    pub seasons: Vec<SeasonMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeasonMetadata {
+    pub season: i32,
+    #[serde(default)]
+    pub episodes: Vec<EpisodeMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EpisodeMetadata {
+    pub episode: i32,
+    pub title: String,
+    pub air_date: Option<String>,
+}
+
+/// A movie described by hand in `movie.yaml`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MovieMetadata {
+    pub title: String,
+    pub year: i32,
+    #[serde(default)]
+    pub overview: String,
+}
+
+impl ShowMetadata {
+    /// The show as if fetched from TMDB, with no TMDB ID (0)
+    pub fn into_show(self) -> Show {
+        let seasons: Vec<TvSeason> = self
+            .seasons
+            .into_iter()
+            .map(|season| TvSeason {
+                id: 0,
+                season_number: season.season,
+                name: format!("Season {}", season.season),
+                overview: String::new(),
+                air_date: None,
+                episodes: season
+                    .episodes
+                    .into_iter()
+                    .map(|episode| TvSeasonEpisode {
+                        id: 0,
+                        season_number: season.season,
+                        episode_number: episode.episode,
+                        name: episode.title,
+                        overview: String::new(),
+                        air_date: episode.air_date,
+                    })
+                    .collect(),
+            })
+            .collect();
+        Show {
+            id: 0,
+            name: self.name,
+            original_name: None,
+            overview: self.overview,
+            year: self.year,
+            first_air_date: self.year.to_string(),
+            number_of_episodes: seasons
+                .iter()
+                .map(|season| season.episodes.len() as i32)
+                .sum(),
+            number_of_seasons: seasons.len() as i32,
+            seasons,
+        }
+    }
+}
+
+impl MovieMetadata {
+    /// The movie as if fetched from TMDB, with no TMDB ID (0)
+    pub fn into_movie(self) -> Movie {
+        Movie {
+            id: 0,
+            title: self.title,
+            original_title: None,
+            overview: self.overview,
+            release_date: self.year.to_string(),
+            original_language: String::new(),
+            popularity: 0.0,
+            imdb_id: None,
+            genres: Vec::new(),
+            poster_path: None,
+        }
+    }
+}
+
+/// Content described by a local metadata file
+#[derive(Debug, PartialEq)]
+pub enum LocalMetadata {
+    Show(Show),
+    Movie(Movie),
+}
+
+fn load<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Option<T>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let yaml = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read metadata from {}", path.display()))?;
+    serde_yaml::from_str(&yaml)
+        .map(Some)
+        .with_context(|| format!("Invalid metadata file {}", path.display()))
+}
+
+/// Local metadata in a source directory, if it has a `show.yaml` or `movie.yaml`
+pub fn load_local(dir: &Path) -> Result<Option<LocalMetadata>> {
+    let show = load::<ShowMetadata>(&dir.join(SHOW_METADATA_FILE))?;
+    let movie = load::<MovieMetadata>(&dir.join(MOVIE_METADATA_FILE))?;
+    match (show, movie) {
+        (Some(_), Some(_)) => Err(anyhow!(
+            "{} has both {} and {}; keep only one",
+            dir.display(),
+            SHOW_METADATA_FILE,
+            MOVIE_METADATA_FILE
+        )),
+        (Some(show), None) => Ok(Some(LocalMetadata::Show(show.into_show()))),
+        (None, Some(movie)) => Ok(Some(LocalMetadata::Movie(movie.into_movie()))),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_show() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(SHOW_METADATA_FILE),
+            r#"
+name: Family Holidays
+year: 2019
+seasons:
+  - season: 1
+    episodes:
+      - episode: 1
+        title: Lake Garda
+        air_date: 2019-08-02
+      - episode: 2
+        title: Christmas
+"#,
+        )
+        .unwrap();
+
+        let Some(LocalMetadata::Show(show)) = load_local(temp_dir.path()).unwrap() else {
+            panic!("expected a show");
+        };
+        assert_eq!(show.id, 0);
+        assert_eq!(show.year, 2019);
+        assert_eq!(show.number_of_episodes, 2);
+        assert_eq!(show.episodes()["S01E02"].name, "Christmas");
+    }
+
+    #[test]
+    fn test_load_movie() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(MOVIE_METADATA_FILE),
+            "title: Our Wedding\nyear: 2021\n",
+        )
+        .unwrap();
+
+        let Some(LocalMetadata::Movie(movie)) = load_local(temp_dir.path()).unwrap() else {
+            panic!("expected a movie");
+        };
+        assert_eq!(movie.title, "Our Wedding");
+        assert_eq!(movie.release_date, "2021");
+    }
+
+    #[test]
+    fn test_load_local_none_or_both() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(load_local(temp_dir.path()).unwrap(), None);
+
+        fs::write(
+            temp_dir.path().join(SHOW_METADATA_FILE),
+            "name: A\nyear: 2000\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join(MOVIE_METADATA_FILE),
+            "title: B\nyear: 2000\n",
+        )
+        .unwrap();
+        assert!(load_local(temp_dir.path()).is_err());
+    }
+}
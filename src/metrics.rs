@@ -0,0 +1,173 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+};
+
+use crate::plan::Mode;
+
+const MODES: [Mode; 5] = [
+    Mode::Move,
+    Mode::Copy,
+    Mode::Link,
+    Mode::Reflink,
+    Mode::Symlink,
+];
+
+static FILES_ORGANIZED: [AtomicU64; MODES.len()] = [const { AtomicU64::new(0) }; MODES.len()];
+static OPERATIONS_FAILED: AtomicU64 = AtomicU64::new(0);
+static BYTES_COPIED: AtomicU64 = AtomicU64::new(0);
+static TMDB_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static TMDB_ERRORS: AtomicU64 = AtomicU64::new(0);
+static QUEUE_DEPTH: AtomicI64 = AtomicI64::new(0);
+
+/// Count a file put into place
+pub fn file_organized(mode: Mode) {
+    let index = MODES.iter().position(|m| *m == mode).unwrap_or(0);
+    FILES_ORGANIZED[index].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count an operation that failed
+pub fn operation_failed() {
+    OPERATIONS_FAILED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count bytes written by a copy
+pub fn bytes_copied(bytes: u64) {
+    BYTES_COPIED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// Count a TMDB request and whether it failed
+pub fn tmdb_request(failed: bool) {
+    TMDB_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    if failed {
+        TMDB_ERRORS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts a request as queued or running until dropped
+pub struct Queued(());
+
+impl Queued {
+    pub fn start() -> Self {
+        QUEUE_DEPTH.fetch_add(1, Ordering::Relaxed);
+        Self(())
+    }
+}
+
+impl Drop for Queued {
+    fn drop(&mut self) {
+        QUEUE_DEPTH.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, String)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+/// All metrics in the Prometheus text format
+pub fn render() -> String {
+    let counter =
+        |value: &AtomicU64| vec![(String::new(), value.load(Ordering::Relaxed).to_string())];
+    let files = MODES
+        .iter()
+        .zip(&FILES_ORGANIZED)
+        .map(|(mode, count)| {
+            (
+                format!(
+                    "{{mode=\"{}\"}}",
+                    format!("{:?}", mode).to_ascii_lowercase()
+                ),
+                count.load(Ordering::Relaxed).to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    metric(
+        &mut out,
+        "mediar_files_organized_total",
+        "counter",
+        "Files put into place, by mode",
+        &files,
+    );
+    metric(
+        &mut out,
+        "mediar_operations_failed_total",
+        "counter",
+        "Operations that failed",
+        &counter(&OPERATIONS_FAILED),
+    );
+    metric(
+        &mut out,
+        "mediar_bytes_copied_total",
+        "counter",
+        "Bytes written by copies",
+        &counter(&BYTES_COPIED),
+    );
+    metric(
+        &mut out,
+        "mediar_tmdb_requests_total",
+        "counter",
+        "Requests sent to TMDB",
+        &counter(&TMDB_REQUESTS),
+    );
+    metric(
+        &mut out,
+        "mediar_tmdb_errors_total",
+        "counter",
+        "TMDB requests that failed or returned an error status",
+        &counter(&TMDB_ERRORS),
+    );
+    metric(
+        &mut out,
+        "mediar_queue_depth",
+        "gauge",
+        "Organize requests waiting or running",
+        &[(
+            String::new(),
+            QUEUE_DEPTH.load(Ordering::Relaxed).to_string(),
+        )],
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(rendered: &str, name: &str) -> u64 {
+        rendered
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .unwrap()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_render() {
+        let before = render();
+        file_organized(Mode::Reflink);
+        bytes_copied(1024);
+        tmdb_request(true);
+        let queued = Queued::start();
+        let after = render();
+        drop(queued);
+
+        let name = "mediar_files_organized_total{mode=\"reflink\"}";
+        assert!(sample(&after, name) > sample(&before, name));
+        assert!(
+            sample(&after, "mediar_bytes_copied_total")
+                >= sample(&before, "mediar_bytes_copied_total") + 1024
+        );
+        assert!(
+            sample(&after, "mediar_tmdb_errors_total")
+                > sample(&before, "mediar_tmdb_errors_total")
+        );
+        assert!(after.contains("# TYPE mediar_queue_depth gauge\n"));
+    }
+}
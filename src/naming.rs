@@ -0,0 +1,329 @@
+use clap::ValueEnum;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+use walkdir::WalkDir;
+
+use crate::video::parse_extension;
+
+/// Provider ID tag appended to show and movie folder names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdTag {
+    /// Jellyfin style: `Fight Club (1999) [tmdbid-550]`
+    Jellyfin,
+    /// Plex style: `Fight Club (1999) {tmdb-550}`
+    Plex,
+}
+
+impl IdTag {
+    /// The tag for a TMDB ID, including the leading space
+    pub fn format(&self, id: i32) -> String {
+        match self {
+            IdTag::Jellyfin => format!(" [tmdbid-{}]", id),
+            IdTag::Plex => format!(" {{tmdb-{}}}", id),
+        }
+    }
+}
+
+/// Which TMDB title names a show or movie
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TitleStyle {
+    /// The title in the metadata language, e.g. `Spirited Away`
+    #[default]
+    Localized,
+    /// The title in the original language, e.g. `千と千尋の神隠し`
+    Original,
+    /// Both, e.g. `Spirited Away - 千と千尋の神隠し`
+    Both,
+}
+
+impl TitleStyle {
+    /// The title to use, optionally transliterated to Latin script (`Брат` to `Brat`)
+    pub fn title(&self, localized: &str, original: Option<&str>, transliterate: bool) -> String {
+        let original = original.filter(|original| !original.is_empty() && *original != localized);
+        let title = match (self, original) {
+            (TitleStyle::Original, Some(original)) => original.to_string(),
+            (TitleStyle::Both, Some(original)) => format!("{} - {}", localized, original),
+            _ => localized.to_string(),
+        };
+        if transliterate {
+            deunicode::deunicode(&title)
+        } else {
+            title
+        }
+    }
+}
+
+/// Folder name for a show or movie, e.g. `Fight Club (1999) [tmdbid-550]`
+pub fn title_folder(name: &str, year: i32, id: i32, id_tag: Option<IdTag>) -> String {
+    // Titles from local metadata have no TMDB ID to tag
+    let tag = id_tag
+        .filter(|_| id > 0)
+        .map(|tag| tag.format(id))
+        .unwrap_or_default();
+    format!("{} ({}){}", name, year, tag)
+}
+
+/// Leading articles moved to the end by [`move_article`]
+const ARTICLES: &[&str] = &["The", "A", "An"];
+
+/// Move a leading article to the end for sorting, e.g. `The Wire` to `Wire, The`
+pub fn move_article(title: &str) -> String {
+    for article in ARTICLES {
+        if let Some((first, rest)) = title.split_once(' ')
+            && first.eq_ignore_ascii_case(article)
+            && !rest.is_empty()
+        {
+            return format!("{}, {}", rest, first);
+        }
+    }
+    title.to_string()
+}
+
+/// First-letter bucket for a title folder, e.g. `W` for `Wire, The (2002)` or `#` for `24 (2001)`
+pub fn letter_folder(folder: &str) -> String {
+    match folder.chars().next() {
+        Some(c) if c.is_alphabetic() => c.to_uppercase().collect(),
+        _ => "#".to_string(),
+    }
+}
+
+/// Whether a library folder is a first-letter bucket made by [`letter_folder`]
+pub fn is_letter_folder(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c == '#' || (c.is_alphabetic() && !c.is_lowercase()))
+        && chars.next().is_none()
+}
+
+/// How episode file names are laid out, e.g. `Show - S01E01 - Title` or `Show.S01E01`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EpisodeStyle {
+    /// Separator between the show name, episode code and title
+    pub separator: String,
+    /// Whether the episode title is part of the name
+    pub include_title: bool,
+    /// Zero padding of the season number
+    pub season_padding: usize,
+    /// Zero padding of the episode number
+    pub episode_padding: usize,
+}
+
+impl Default for EpisodeStyle {
+    fn default() -> Self {
+        Self {
+            separator: " - ".to_string(),
+            include_title: true,
+            season_padding: 2,
+            episode_padding: 2,
+        }
+    }
+}
+
+impl EpisodeStyle {
+    /// Format an episode file name (without sanitizing) in this style
+    pub fn format(&self, show: &str, season: i32, episode: i32, title: &str, ext: &str) -> String {
+        // Dotted and underscored styles use the separator between words too
+        let words = |text: &str| match self.separator.as_str() {
+            "." | "_" => text
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(&self.separator),
+            _ => text.to_string(),
+        };
+
+        let code = format!(
+            "S{:0sw$}E{:0ew$}",
+            season,
+            episode,
+            sw = self.season_padding,
+            ew = self.episode_padding
+        );
+
+        let mut name = format!("{}{}{}", words(show), self.separator, code);
+        if self.include_title {
+            name.push_str(&self.separator);
+            name.push_str(&words(title));
+        }
+        format!("{}.{}", name, ext)
+    }
+
+    /// Detect the style of a single episode file name
+    pub fn detect(file_name: &str) -> Option<Self> {
+        let stem = Path::new(file_name).file_stem()?.to_str()?;
+        let re = Regex::new(
+            r"(?i)^.+?(?P<sep> - |\.|_| )S(?P<season>\d+)E(?P<episode>\d+)(?P<title>(?: - |\.|_| ).+)?$",
+        )
+        .ok()?;
+        let captures = re.captures(stem)?;
+
+        Some(Self {
+            separator: captures["sep"].to_string(),
+            include_title: captures.name("title").is_some(),
+            season_padding: captures["season"].len(),
+            episode_padding: captures["episode"].len(),
+        })
+    }
+
+    /// Detect the most common style among the episodes already in a show directory
+    pub fn detect_in(show_dir: &Path) -> Option<Self> {
+        let mut counts: HashMap<Self, usize> = HashMap::new();
+        for entry in WalkDir::new(show_dir).max_depth(2).into_iter().flatten() {
+            if parse_extension(entry.path()).is_none() {
+                continue;
+            }
+            if let Some(style) = entry.file_name().to_str().and_then(Self::detect) {
+                *counts.entry(style).or_default() += 1;
+            }
+        }
+
+        counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(style, _)| style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_title_style() {
+        let original = Some("Le Fabuleux Destin d'Amélie Poulain");
+        assert_eq!(
+            TitleStyle::Localized.title("Amélie", original, false),
+            "Amélie"
+        );
+        assert_eq!(
+            TitleStyle::Original.title("Amélie", original, false),
+            "Le Fabuleux Destin d'Amélie Poulain"
+        );
+        assert_eq!(
+            TitleStyle::Both.title("Amélie", original, true),
+            "Amelie - Le Fabuleux Destin d'Amelie Poulain"
+        );
+        assert_eq!(TitleStyle::Both.title("Heat", Some("Heat"), false), "Heat");
+        assert_eq!(TitleStyle::Original.title("Heat", None, false), "Heat");
+        assert_eq!(
+            TitleStyle::Original.title("Brother", Some("Брат"), true),
+            "Brat"
+        );
+    }
+
+    #[test]
+    fn test_move_article() {
+        assert_eq!(move_article("The Wire"), "Wire, The");
+        assert_eq!(move_article("A Quiet Place"), "Quiet Place, A");
+        assert_eq!(move_article("an Education"), "Education, an");
+        assert_eq!(move_article("Theodore Rex"), "Theodore Rex");
+        assert_eq!(move_article("The"), "The");
+    }
+
+    #[test]
+    fn test_letter_folder() {
+        assert_eq!(letter_folder("The Wire (2002)"), "T");
+        assert_eq!(letter_folder("Wire, The (2002)"), "W");
+        assert_eq!(letter_folder("élite (2018)"), "É");
+        assert_eq!(letter_folder("24 (2001)"), "#");
+        assert!(is_letter_folder("W"));
+        assert!(is_letter_folder("#"));
+        assert!(!is_letter_folder("w"));
+        assert!(!is_letter_folder("Up (2009)"));
+    }
+
+    #[test]
+    fn test_title_folder() {
+        assert_eq!(
+            title_folder("Fight Club", 1999, 550, None),
+            "Fight Club (1999)"
+        );
+        assert_eq!(
+            title_folder("Fight Club", 1999, 550, Some(IdTag::Jellyfin)),
+            "Fight Club (1999) [tmdbid-550]"
+        );
+        assert_eq!(
+            title_folder("Fight Club", 1999, 550, Some(IdTag::Plex)),
+            "Fight Club (1999) {tmdb-550}"
+        );
+        assert_eq!(
+            title_folder("Home Videos", 2019, 0, Some(IdTag::Plex)),
+            "Home Videos (2019)"
+        );
+    }
+
+    #[test]
+    fn test_format_default() {
+        assert_eq!(
+            EpisodeStyle::default().format("Show Name", 1, 2, "The Title", "mkv"),
+            "Show Name - S01E02 - The Title.mkv"
+        );
+    }
+
+    #[test]
+    fn test_format_dotted_without_title() {
+        let style = EpisodeStyle {
+            separator: ".".to_string(),
+            include_title: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            style.format("Show Name", 1, 2, "The Title", "mkv"),
+            "Show.Name.S01E02.mkv"
+        );
+    }
+
+    #[test]
+    fn test_detect_default() {
+        assert_eq!(
+            EpisodeStyle::detect("Show Name - S01E02 - The Title.mkv"),
+            Some(EpisodeStyle::default())
+        );
+    }
+
+    #[test]
+    fn test_detect_dotted() {
+        assert_eq!(
+            EpisodeStyle::detect("Show.Name.S1E002.mkv"),
+            Some(EpisodeStyle {
+                separator: ".".to_string(),
+                include_title: false,
+                season_padding: 1,
+                episode_padding: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_detect_not_an_episode() {
+        assert_eq!(EpisodeStyle::detect("Movie Name (1999).mkv"), None);
+    }
+
+    #[test]
+    fn test_detect_in_show_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let season = temp_dir.path().join("Season 01");
+        fs::create_dir_all(&season).unwrap();
+        for name in [
+            "Show.S01E01.mkv",
+            "Show.S01E02.mkv",
+            "Show - S01E03 - C.mkv",
+        ] {
+            fs::File::create(season.join(name)).unwrap();
+        }
+
+        let style = EpisodeStyle::detect_in(temp_dir.path()).unwrap();
+        assert_eq!(style.separator, ".");
+        assert!(!style.include_title);
+    }
+
+    #[test]
+    fn test_detect_in_missing_directory() {
+        assert_eq!(EpisodeStyle::detect_in(Path::new("/nonexistent")), None);
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{
+    plan::Media,
+    video::{ContentType, is_sidecar, parse_episode},
+};
+
+/// What each organize in this run did, summarized in a notification when the run ends
+static TALLY: Mutex<Vec<Organized>> = Mutex::new(Vec::new());
+
+/// How a run ended, to choose which notifications are sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Success,
+    Partial,
+    Error,
+}
+
+/// Where a notification is sent
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "service", rename_all = "lowercase")]
+pub enum Service {
+    Discord { webhook_url: String },
+    Telegram { bot_token: String, chat_id: String },
+    Pushover { token: String, user: String },
+}
+
+/// A `[[notify]]` entry in the config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct NotifyConfig {
+    #[serde(flatten)]
+    pub service: Service,
+    /// The outcomes to notify about; all of them by default
+    #[serde(default = "all_severities")]
+    pub on: Vec<Severity>,
+}
+
+fn all_severities() -> Vec<Severity> {
+    vec![Severity::Success, Severity::Partial, Severity::Error]
+}
+
+/// Files organized for one show, movie or source, and how many operations failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Organized {
+    pub media: Option<Media>,
+    pub source: PathBuf,
+    pub targets: Vec<PathBuf>,
+    pub failed: usize,
+}
+
+/// Add an organize to this run's summary
+pub fn record(organized: Organized) {
+    TALLY.lock().unwrap().push(organized);
+}
+
+/// Everything recorded in this run, clearing the record
+pub fn take_recorded() -> Vec<Organized> {
+    std::mem::take(&mut TALLY.lock().unwrap())
+}
+
+/// `S02` or `S01-S03` for the seasons of the episodes among `targets`
+fn seasons(targets: &[PathBuf]) -> String {
+    let seasons: BTreeSet<i32> = targets
+        .iter()
+        .filter_map(|target| parse_episode(Path::new(target.file_name()?)).ok())
+        .map(|(season, _)| season)
+        .collect();
+    match (seasons.first(), seasons.last()) {
+        (Some(first), Some(last)) if first == last => format!(" S{:02}", first),
+        (Some(first), Some(last)) => format!(" S{:02}-S{:02}", first, last),
+        _ => String::new(),
+    }
+}
+
+impl Organized {
+    /// Videos organized, leaving out subtitles and audio tracks
+    fn videos(&self) -> Vec<PathBuf> {
+        self.targets
+            .iter()
+            .filter(|target| target.extension().is_some() && !is_sidecar(target))
+            .cloned()
+            .collect()
+    }
+
+    /// E.g. `12 episode(s) of Severance S02` or `Heat`
+    fn describe(&self) -> String {
+        let videos = self.videos();
+        match &self.media {
+            Some(media) if media.content == ContentType::Show => format!(
+                "{} episode(s) of {}{}",
+                videos.len(),
+                media.title,
+                seasons(&videos)
+            ),
+            Some(media) => media.title.clone(),
+            None => format!(
+                "{} file(s) from {}",
+                self.targets.len(),
+                self.source.display()
+            ),
+        }
+    }
+}
+
+/// The message for a run's outcome, or `None` when there is nothing to report
+pub fn message(organized: &[Organized], error: Option<&str>) -> Option<(Severity, String)> {
+    if let Some(error) = error {
+        return Some((Severity::Error, format!("mediar failed: {}", error)));
+    }
+    let done: Vec<String> = organized
+        .iter()
+        .filter(|organized| !organized.targets.is_empty())
+        .map(Organized::describe)
+        .collect();
+    let failed: usize = organized.iter().map(|organized| organized.failed).sum();
+    let mut text = match done.is_empty() {
+        true if failed == 0 => return None,
+        true => String::from("Organized nothing"),
+        false => format!("Organized {}", done.join("; ")),
+    };
+    if failed > 0 {
+        text.push_str(&format!("; {} operation(s) failed", failed));
+        return Some((Severity::Partial, text));
+    }
+    Some((Severity::Success, text))
+}
+
+impl Service {
+    /// The webhook request that posts `text`
+    pub fn request(&self, client: &reqwest::Client, text: &str) -> reqwest::RequestBuilder {
+        match self {
+            Service::Discord { webhook_url } => client
+                .post(webhook_url)
+                .json(&serde_json::json!({ "content": text })),
+            Service::Telegram { bot_token, chat_id } => client
+                .post(format!(
+                    "https://api.telegram.org/bot{}/sendMessage",
+                    bot_token
+                ))
+                .json(&serde_json::json!({ "chat_id": chat_id, "text": text })),
+            Service::Pushover { token, user } => client
+                .post("https://api.pushover.net/1/messages.json")
+                .json(&serde_json::json!({ "token": token, "user": user, "message": text })),
+        }
+    }
+}
+
+/// Send `text` to every configured service that wants this severity
+pub async fn send(configs: &[NotifyConfig], severity: Severity, text: &str) -> Result<()> {
+    let client = reqwest::Client::new();
+    for config in configs
+        .iter()
+        .filter(|config| config.on.contains(&severity))
+    {
+        let response = config.service.request(&client, text).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Notification failed with status {}",
+                response.status()
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn organized(media: Option<Media>, targets: &[&str], failed: usize) -> Organized {
+        Organized {
+            media,
+            source: PathBuf::from("/downloads/release"),
+            targets: targets.iter().map(PathBuf::from).collect(),
+            failed,
+        }
+    }
+
+    fn show(title: &str) -> Option<Media> {
+        Some(Media {
+            content: ContentType::Show,
+            tmdb_id: 1,
+            title: title.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_message() {
+        let severance = organized(
+            show("Severance"),
+            &[
+                "/tv/Severance/Season 02/Severance - S02E01 - Hello.mkv",
+                "/tv/Severance/Season 02/Severance - S02E01 - Hello.en.srt",
+                "/tv/Severance/Season 02/Severance - S02E02 - Goodbye.mkv",
+            ],
+            0,
+        );
+        assert_eq!(
+            message(std::slice::from_ref(&severance), None),
+            Some((
+                Severity::Success,
+                "Organized 2 episode(s) of Severance S02".to_string()
+            ))
+        );
+
+        let heat = organized(
+            Some(Media {
+                content: ContentType::Movie,
+                tmdb_id: 949,
+                title: "Heat".to_string(),
+            }),
+            &["/movies/Heat (1995)/Heat (1995).mkv"],
+            1,
+        );
+        assert_eq!(
+            message(&[severance, heat], None),
+            Some((
+                Severity::Partial,
+                "Organized 2 episode(s) of Severance S02; Heat; 1 operation(s) failed".to_string()
+            ))
+        );
+
+        assert_eq!(message(&[organized(None, &[], 0)], None), None);
+        assert_eq!(
+            message(&[], Some("disk full")),
+            Some((Severity::Error, "mediar failed: disk full".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_seasons() {
+        let targets = |names: &[&str]| names.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(
+            seasons(&targets(&["Show - S01E01.mkv", "Show - S03E02.mkv"])),
+            " S01-S03"
+        );
+        assert_eq!(seasons(&targets(&["Movie (1999).mkv"])), "");
+    }
+
+    #[test]
+    fn test_parse_config_and_request() {
+        let config: NotifyConfig = toml::from_str(
+            r#"
+service = "telegram"
+bot_token = "123:abc"
+chat_id = "42"
+on = ["partial", "error"]
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.on, vec![Severity::Partial, Severity::Error]);
+
+        let request = config
+            .service
+            .request(&reqwest::Client::new(), "hi")
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "https://api.telegram.org/bot123:abc/sendMessage"
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({ "chat_id": "42", "text": "hi" }));
+
+        let config: NotifyConfig =
+            toml::from_str("service = \"discord\"\nwebhook_url = \"https://discord/hook\"\n")
+                .unwrap();
+        assert_eq!(config.on, all_severities());
+    }
+}
@@ -0,0 +1,145 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::{
+    io::Write,
+    path::Path,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use crate::plan::Mode;
+
+static EVENTS: AtomicBool = AtomicBool::new(false);
+
+/// Events collected instead of printed, while `mediar serve` handles a request
+static CAPTURED: Mutex<Option<Vec<serde_json::Value>>> = Mutex::new(None);
+
+/// What is written to stdout
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// One JSON event per line for each planned, skipped, executed or failed operation;
+    /// everything else goes to stderr
+    Ndjson,
+}
+
+/// Set the output format for this run
+pub fn configure(format: OutputFormat) {
+    EVENTS.store(format == OutputFormat::Ndjson, Ordering::Relaxed);
+}
+
+/// Whether stdout carries events, leaving human-readable output to stderr
+pub fn events() -> bool {
+    EVENTS.load(Ordering::Relaxed)
+}
+
+/// Print a line of human-readable output, which moves to stderr while stdout carries events
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::events() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// Progress of a run, for other tools to follow
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum Event<'a> {
+    Planned {
+        mode: Mode,
+        source: &'a Path,
+        target: &'a Path,
+    },
+    Skipped {
+        source: &'a Path,
+        reason: String,
+    },
+    Executed {
+        mode: Mode,
+        source: &'a Path,
+        target: &'a Path,
+    },
+    Failed {
+        source: &'a Path,
+        error: String,
+    },
+}
+
+/// Collect events from now on instead of printing them
+pub fn start_capture() {
+    *CAPTURED.lock().unwrap() = Some(Vec::new());
+}
+
+/// Stop collecting events and return the ones collected
+pub fn take_captured() -> Vec<serde_json::Value> {
+    CAPTURED.lock().unwrap().take().unwrap_or_default()
+}
+
+impl Event<'_> {
+    /// Write the event to stdout as one line of JSON when events are enabled, or collect it
+    /// while capturing
+    pub fn emit(&self) {
+        if let Some(captured) = CAPTURED.lock().unwrap().as_mut() {
+            captured.extend(serde_json::to_value(self).ok());
+            return;
+        }
+        if !events() {
+            return;
+        }
+        let Ok(line) = serde_json::to_string(self) else {
+            return;
+        };
+        // Lock so events from parallel workers never interleave
+        let mut stdout = std::io::stdout().lock();
+        let _ = writeln!(stdout, "{}", line);
+        let _ = stdout.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_json() {
+        let event = Event::Executed {
+            mode: Mode::Link,
+            source: Path::new("/downloads/Heat.1995.mkv"),
+            target: Path::new("/library/Heat (1995)/Heat (1995).mkv"),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"executed","mode":"link","source":"/downloads/Heat.1995.mkv","target":"/library/Heat (1995)/Heat (1995).mkv"}"#
+        );
+
+        let event = Event::Skipped {
+            source: Path::new("/downloads/sample.mkv"),
+            reason: "sample".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"skipped","source":"/downloads/sample.mkv","reason":"sample"}"#
+        );
+    }
+
+    #[test]
+    fn test_capture() {
+        start_capture();
+        Event::Failed {
+            source: Path::new("/downloads/a.mkv"),
+            error: "disk full".to_string(),
+        }
+        .emit();
+        let captured = take_captured();
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0]["event"], "failed");
+        assert!(take_captured().is_empty());
+    }
+}
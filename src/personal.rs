@@ -0,0 +1,126 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Local, NaiveDate};
+use regex::Regex;
+use sanitize_filename::sanitize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Where `mediar personal` puts a file unless `--date-template` or `date_template` says otherwise
+pub const DEFAULT_TEMPLATE: &str = "{year}/{year}-{month}-{folder}/{name}";
+
+const FIELDS: [&str; 5] = ["year", "month", "day", "folder", "name"];
+
+/// A date in the file name, such as `2023-06-14`, `20230614` or `VID_20230614_181203`
+pub fn filename_date(path: &Path) -> Option<NaiveDate> {
+    let re = Regex::new(r"(?:^|\D)((?:19|20)\d{2})[-_.]?(\d{2})[-_.]?(\d{2})(?:\D|$)").ok()?;
+    let stem = path.file_stem()?.to_str()?;
+    re.captures_iter(stem).find_map(|captures| {
+        NaiveDate::from_ymd_opt(
+            captures[1].parse().ok()?,
+            captures[2].parse().ok()?,
+            captures[3].parse().ok()?,
+        )
+    })
+}
+
+/// The recording date a camera embedded in the file, read with `ffprobe` if it is installed
+pub fn embedded_date(path: &Path) -> Option<NaiveDate> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-show_entries", "format_tags=creation_time"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    NaiveDate::parse_from_str(stdout.trim().get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn modified_date(path: &Path) -> Option<NaiveDate> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(DateTime::<Local>::from(modified).date_naive())
+}
+
+/// When a file was recorded: from its name, then its metadata, then its modification time
+pub fn date_of(path: &Path) -> Option<NaiveDate> {
+    filename_date(path)
+        .or_else(|| embedded_date(path))
+        .or_else(|| modified_date(path))
+}
+
+/// Fail early on a template with a field other than `{year}`, `{month}`, `{day}`, `{folder}`
+/// and `{name}`
+pub fn check_template(template: &str) -> Result<()> {
+    let re = Regex::new(r"\{([^}]*)\}")?;
+    match re
+        .captures_iter(template)
+        .find(|captures| !FIELDS.contains(&&captures[1]))
+    {
+        Some(captures) => Err(anyhow!(
+            "Unknown field {} in date template; use {{year}}, {{month}}, {{day}}, {{folder}} or {{name}}",
+            &captures[0]
+        )),
+        None => Ok(()),
+    }
+}
+
+/// The path relative to the target for a file recorded on `date`
+///
+/// `folder` is the folder the file was found in and `name` its file name. Each `/`-separated
+/// part of the result is made safe for the filesystem.
+pub fn render(template: &str, date: NaiveDate, folder: &str, name: &str) -> PathBuf {
+    let rendered = template
+        .replace("{year}", &format!("{:04}", date.year()))
+        .replace("{month}", &format!("{:02}", date.month()))
+        .replace("{day}", &format!("{:02}", date.day()))
+        .replace("{folder}", folder)
+        .replace("{name}", name);
+    rendered
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .map(sanitize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filename_date() {
+        let date = |name: &str| filename_date(Path::new(name));
+        let june_14 = NaiveDate::from_ymd_opt(2023, 6, 14);
+        assert_eq!(date("2023-06-14 Beach.mp4"), june_14);
+        assert_eq!(date("VID_20230614_181203.mp4"), june_14);
+        assert_eq!(date("PXL_20230614.mov"), june_14);
+        assert_eq!(date("clip 2023.06.14.mkv"), june_14);
+        assert_eq!(date("20231399.mp4"), None);
+        assert_eq!(date("GOPR0042.mp4"), None);
+    }
+
+    #[test]
+    fn test_render() {
+        let date = NaiveDate::from_ymd_opt(2023, 6, 4).unwrap();
+        assert_eq!(
+            render(DEFAULT_TEMPLATE, date, "italy", "GOPR0042.mp4"),
+            PathBuf::from("2023/2023-06-italy/GOPR0042.mp4")
+        );
+        assert_eq!(
+            render("{year}/{month}/{day} {name}", date, "a: b", "clip.mp4"),
+            PathBuf::from("2023/06/04 clip.mp4")
+        );
+        assert_eq!(
+            render("{folder}/{name}", date, "a: b", "clip.mp4"),
+            PathBuf::from("a b/clip.mp4")
+        );
+    }
+
+    #[test]
+    fn test_check_template() {
+        assert!(check_template(DEFAULT_TEMPLATE).is_ok());
+        assert!(check_template("{year}/{event}/{name}").is_err());
+    }
+}
@@ -0,0 +1,931 @@
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use rand::{Rng, seq::index};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    checksum::ChecksumFormat,
+    scan::{PathFilter, is_partial_download},
+    transfer::ReflinkFallback,
+    trash::TRASH_DIR,
+    video::{ContentType, DISC, disc_path, is_sample, parse_extension},
+};
+
+/// How files are put into place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Move,
+    Copy,
+    Link,
+    /// Copy-on-write clone
+    Reflink,
+    /// Symbolic link to the source
+    Symlink,
+}
+
+/// What `link` does when the source and target are on different filesystems
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkFallback {
+    /// Refuse to start
+    #[default]
+    Fail,
+    /// Copy the files instead
+    Copy,
+    /// Create symbolic links instead
+    Symlink,
+}
+
+/// Why a source file is left out of a plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Not a video or subtitle file
+    UnsupportedExtension,
+    /// A release sample clip
+    Sample,
+    /// Could not be matched to an episode or title
+    NoMatch(String),
+    /// The target file is already there
+    Exists(PathBuf),
+    /// Left out on purpose, by a filter, rule or option
+    Ignored(&'static str),
+    /// Organized by an earlier run, to the given target
+    Processed(PathBuf),
+    /// Already at its target, by name or as a hard link
+    UpToDate,
+    /// Still being downloaded or written
+    InProgress,
+    /// One half of a VobSub `.sub`/`.idx` pair, missing the file with this extension
+    Unpaired(&'static str),
+    /// A hidden file or folder, such as macOS's `._` AppleDouble files
+    Hidden,
+}
+
+impl SkipReason {
+    /// Whether the skip is routine enough to leave out of the per-file output
+    pub fn is_quiet(&self) -> bool {
+        matches!(self, SkipReason::UnsupportedExtension | SkipReason::Hidden)
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::UnsupportedExtension => write!(f, "unsupported file type"),
+            SkipReason::Sample => write!(f, "sample clip"),
+            SkipReason::NoMatch(reason) => write!(f, "{}", reason),
+            SkipReason::Exists(path) => write!(f, "{} already exists", path.to_string_lossy()),
+            SkipReason::Ignored(reason) => write!(f, "{}", reason),
+            SkipReason::UpToDate => write!(f, "up to date"),
+            SkipReason::InProgress => write!(f, "still downloading"),
+            SkipReason::Hidden => write!(f, "hidden file"),
+            SkipReason::Unpaired(missing) => {
+                write!(f, "VobSub subtitle without its .{} file", missing)
+            }
+            SkipReason::Processed(path) => {
+                write!(f, "already organized to {}", path.to_string_lossy())
+            }
+        }
+    }
+}
+
+/// Where a source file should go
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    To(PathBuf),
+    Skip(SkipReason),
+}
+
+/// The operations to perform for a source directory, and the files left out
+#[derive(Debug, Default)]
+pub struct Plan {
+    /// The source directory that was walked
+    pub source: PathBuf,
+    pub operations: Vec<(PathBuf, PathBuf)>,
+    pub skipped: Vec<(PathBuf, SkipReason)>,
+    /// Existing targets that operations overwrite with a better file
+    pub replaced: HashSet<PathBuf>,
+    /// Targets named with a placeholder title because TMDB does not list their episode yet
+    pub placeholders: HashSet<PathBuf>,
+    /// The show or movie the files were matched to, when known
+    pub media: Option<Media>,
+}
+
+/// The show or movie a plan organizes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Media {
+    pub content: ContentType,
+    pub tmdb_id: i32,
+    pub title: String,
+}
+
+impl Plan {
+    /// Walk the source directory and map every file with `destination`
+    pub fn collect<F>(source: &Path, filter: &PathFilter, mut destination: F) -> Result<Self>
+    where
+        F: FnMut(&Path, &str) -> Result<Destination>,
+    {
+        let mut plan = Self {
+            source: source.to_path_buf(),
+            ..Default::default()
+        };
+        let mut seen_outputs: HashSet<PathBuf> = HashSet::new();
+
+        let mut walker = filter
+            .walk(source, usize::MAX)
+            .sort_by_file_name()
+            .into_iter();
+        while let Some(entry) = walker.next() {
+            let entry = entry?;
+            let old = entry.path().to_path_buf();
+            let relative = old.strip_prefix(source).unwrap_or(&old).to_path_buf();
+
+            // Staged sources from earlier moves are never organized again
+            if entry.depth() > 0 && entry.file_type().is_dir() && entry.file_name() == TRASH_DIR {
+                walker.skip_current_dir();
+                continue;
+            }
+
+            if entry.depth() > 0 && filter.is_hidden(entry.file_name()) {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                plan.skip(old, SkipReason::Hidden);
+                continue;
+            }
+
+            if entry.depth() > 0 && filter.is_excluded(&relative) {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                plan.skip(old, SkipReason::Ignored("excluded by pattern"));
+                continue;
+            }
+
+            if entry.file_type().is_dir() {
+                continue;
+            }
+
+            if is_partial_download(&old) || filter.is_too_recent(&old) {
+                plan.skip(old, SkipReason::InProgress);
+                continue;
+            }
+
+            let ext = match parse_extension(&old) {
+                Some(ext) => ext,
+                None if disc_path(&relative).is_some() => DISC.to_string(),
+                None => {
+                    plan.skip(old, SkipReason::UnsupportedExtension);
+                    continue;
+                }
+            };
+
+            if !filter.is_included(&relative) {
+                plan.skip(old, SkipReason::Ignored("not included by pattern"));
+                continue;
+            }
+
+            if is_sample(&relative) {
+                plan.skip(old, SkipReason::Sample);
+                continue;
+            }
+
+            let new = match destination(&old, &ext)? {
+                Destination::To(new) => new,
+                Destination::Skip(reason) => {
+                    plan.skip(old, reason);
+                    continue;
+                }
+            };
+
+            if old == new || is_same_file(&old, &new) {
+                plan.skip(old, SkipReason::UpToDate);
+                continue;
+            }
+
+            if new.exists() {
+                plan.skip(old, SkipReason::Exists(new));
+                continue;
+            }
+
+            if !seen_outputs.insert(new.clone()) {
+                return Err(anyhow!(
+                    "Multiple input files map to the same output: {} \
+                     (use --version-suffix quality to keep several versions of a movie)",
+                    new.display()
+                ));
+            }
+            plan.operations.push((old, new));
+        }
+
+        plan.bond_vobsub();
+        Ok(plan)
+    }
+
+    /// Keep VobSub `.sub`/`.idx` pairs together, since players only find them with identical
+    /// names: each `.idx` goes wherever its `.sub` goes, and a half without the other is
+    /// skipped
+    fn bond_vobsub(&mut self) {
+        let partner = |path: &Path| -> Option<(PathBuf, &'static str)> {
+            let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+            let other = match ext.as_str() {
+                "sub" => "idx",
+                "idx" => "sub",
+                _ => return None,
+            };
+            let found = [other.to_string(), other.to_ascii_uppercase()]
+                .into_iter()
+                .map(|ext| path.with_extension(ext))
+                .find(|candidate| candidate.exists())
+                .unwrap_or_else(|| path.with_extension(other));
+            Some((found, other))
+        };
+        let targets: HashMap<PathBuf, PathBuf> = self.operations.iter().cloned().collect();
+
+        let mut operations = Vec::new();
+        for (old, new) in std::mem::take(&mut self.operations) {
+            let Some((other, other_ext)) = partner(&old) else {
+                operations.push((old, new));
+                continue;
+            };
+            if !other.exists() {
+                self.skip(old, SkipReason::Unpaired(other_ext));
+                continue;
+            }
+            let new = match targets.get(&other) {
+                Some(sub_target) if other_ext == "sub" => {
+                    sub_target.with_extension(new.extension().unwrap_or_default())
+                }
+                _ => new,
+            };
+            operations.push((old, new));
+        }
+        self.operations = operations;
+    }
+
+    /// Add the plan of another source, so both run as one
+    ///
+    /// The source becomes the deepest folder the two sources share, and the media is only
+    /// kept when both plans matched the same show or movie.
+    pub fn merge(&mut self, other: Plan) -> Result<()> {
+        let targets: HashSet<&PathBuf> = self.operations.iter().map(|(_, new)| new).collect();
+        if let Some((_, new)) = other
+            .operations
+            .iter()
+            .find(|(_, new)| targets.contains(new))
+        {
+            return Err(anyhow!(
+                "Multiple input files map to the same output: {}",
+                new.display()
+            ));
+        }
+        self.source = self
+            .source
+            .ancestors()
+            .find(|ancestor| other.source.starts_with(ancestor))
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        if self.media != other.media {
+            self.media = None;
+        }
+        self.operations.extend(other.operations);
+        self.skipped.extend(other.skipped);
+        self.replaced.extend(other.replaced);
+        self.placeholders.extend(other.placeholders);
+        Ok(())
+    }
+
+    /// Keep a random sample of `count` operations, in their original order
+    pub fn sample(&mut self, count: usize, rng: &mut impl Rng) {
+        if count >= self.operations.len() {
+            return;
+        }
+
+        let mut keep = index::sample(rng, self.operations.len(), count).into_vec();
+        keep.sort_unstable();
+        let operations = std::mem::take(&mut self.operations);
+        self.operations = keep
+            .into_iter()
+            .map(|index| operations[index].clone())
+            .collect();
+    }
+
+    /// Wait `settle` and move operations whose source changed size meanwhile into the skipped
+    /// list, since they are still being written
+    pub fn skip_growing(&mut self, settle: Duration) {
+        let size = |path: &Path| fs::metadata(path).map(|meta| meta.len()).ok();
+        let before: Vec<_> = self.operations.iter().map(|(old, _)| size(old)).collect();
+        thread::sleep(settle);
+        let mut operations = Vec::new();
+        for ((old, new), before) in std::mem::take(&mut self.operations).into_iter().zip(before) {
+            if size(&old) == before {
+                operations.push((old, new));
+            } else {
+                self.skipped.push((old, SkipReason::InProgress));
+            }
+        }
+        self.operations = operations;
+    }
+
+    /// Order operations by destination device, then source directory, so that each disk
+    /// is read and written in long sequential runs
+    pub fn order_by_device(&mut self) {
+        self.order_by(device_of);
+    }
+
+    fn order_by(&mut self, device: impl Fn(&Path) -> u64) {
+        self.operations
+            .sort_by_cached_key(|(old, new)| (device(new), old.parent().map(Path::to_path_buf)));
+    }
+
+    /// The replaced targets of planned operations, in path order, to save with them
+    pub fn replaced_targets(&self) -> Vec<PathBuf> {
+        let mut replaced: Vec<PathBuf> = self
+            .operations
+            .iter()
+            .map(|(_, new)| new)
+            .filter(|new| self.replaced.contains(*new))
+            .cloned()
+            .collect();
+        replaced.sort();
+        replaced
+    }
+
+    fn skip(&mut self, path: PathBuf, reason: SkipReason) {
+        tracing::debug!(path = %path.display(), reason = %reason, "skipped file");
+        self.skipped.push((path, reason));
+    }
+}
+
+/// A single planned operation in a plan file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedOperation {
+    pub source: PathBuf,
+    pub target: PathBuf,
+}
+
+/// Options that change how saved operations are executed, kept with plan and journal files
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionSettings {
+    /// Move sources even if they are hard-linked elsewhere
+    pub allow_hardlinked: bool,
+    /// Run operations on different destination devices in parallel
+    pub parallel_devices: bool,
+    /// For moves, stage sources in `.mediar-trash/` for this many days instead of moving them
+    pub trash_retention_days: Option<u32>,
+    /// Record failed operations and continue instead of stopping at the first one
+    pub keep_going: bool,
+    /// Roll back the run's completed operations if one fails
+    pub atomic: bool,
+    pub reflink_fallback: ReflinkFallback,
+    pub link_fallback: LinkFallback,
+    /// Maximum bytes per second for each copy
+    pub rate_limit: Option<u64>,
+    /// Database that completed operations are recorded in
+    pub state_db: Option<PathBuf>,
+    pub post_hooks: Vec<String>,
+    pub file_hooks: Vec<String>,
+    /// Record checksums of completed targets in a manifest in each target folder
+    pub write_checksums: Option<ChecksumFormat>,
+}
+
+/// A plan saved with `--plan-out`, to review, edit and run later with `mediar apply`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanFile {
+    pub mode: Mode,
+    /// The source directory that was walked
+    pub source: PathBuf,
+    pub content: ContentType,
+    /// TMDB ID of the show or movie the files were matched to
+    pub tmdb_id: i32,
+    #[serde(flatten)]
+    pub settings: ExecutionSettings,
+    pub operations: Vec<PlannedOperation>,
+    /// Existing targets that operations replace with a better file (`--upgrade replace`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replaced: Vec<PathBuf>,
+}
+
+impl PlanFile {
+    pub fn new(
+        mode: Mode,
+        content: ContentType,
+        tmdb_id: i32,
+        plan: &Plan,
+        settings: ExecutionSettings,
+    ) -> Self {
+        Self {
+            mode,
+            source: plan.source.clone(),
+            content,
+            tmdb_id,
+            settings,
+            operations: plan
+                .operations
+                .iter()
+                .map(|(old, new)| PlannedOperation {
+                    source: old.clone(),
+                    target: new.clone(),
+                })
+                .collect(),
+            replaced: plan.replaced_targets(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write plan to {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read plan from {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid plan file {}", path.display()))
+    }
+
+    /// Rebuild the plan, re-checking sources and targets since the file was written
+    pub fn into_plan(self) -> Result<Plan> {
+        Plan::from_operations(self.source, self.operations, &self.replaced)
+    }
+}
+
+impl Plan {
+    /// A plan for saved operations, skipping sources that are gone and targets that exist
+    ///
+    /// Targets in `replaced` were planned to be replaced, so they are expected to exist.
+    pub fn from_operations(
+        source: PathBuf,
+        operations: Vec<PlannedOperation>,
+        replaced: &[PathBuf],
+    ) -> Result<Self> {
+        let mut plan = Plan {
+            source,
+            ..Default::default()
+        };
+        let mut seen_outputs: HashSet<PathBuf> = HashSet::new();
+
+        for PlannedOperation { source, target } in operations {
+            if !source.exists() {
+                plan.skip(source, SkipReason::Ignored("source no longer exists"));
+                continue;
+            }
+            if target.exists() {
+                if !replaced.contains(&target) {
+                    plan.skip(source, SkipReason::Exists(target));
+                    continue;
+                }
+                plan.replaced.insert(target.clone());
+            }
+            if !seen_outputs.insert(target.clone()) {
+                return Err(anyhow!(
+                    "Multiple input files map to the same output: {}",
+                    target.display()
+                ));
+            }
+            plan.operations.push((source, target));
+        }
+        Ok(plan)
+    }
+}
+
+/// Whether two paths are hard links to the same file
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (std::fs::metadata(a), std::fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        false
+    }
+}
+
+/// Device holding a path, or its nearest existing ancestor for paths not created yet
+pub fn device_of(path: &Path) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        // A relative path's last ancestor is "", which has no metadata
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        path.ancestors()
+            .find_map(|ancestor| std::fs::metadata(ancestor).ok())
+            .map(|metadata| metadata.dev())
+            .unwrap_or(0)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        0
+    }
+}
+
+/// Split operations into batches that share a destination device, keeping their order
+pub fn group_by_device(operations: Vec<(PathBuf, PathBuf)>) -> Vec<Vec<(PathBuf, PathBuf)>> {
+    group_by(operations, device_of)
+}
+
+fn group_by(
+    operations: Vec<(PathBuf, PathBuf)>,
+    device: impl Fn(&Path) -> u64,
+) -> Vec<Vec<(PathBuf, PathBuf)>> {
+    let mut groups: Vec<(u64, Vec<(PathBuf, PathBuf)>)> = Vec::new();
+    for (old, new) in operations {
+        let key = device(&new);
+        match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+            Some((_, group)) => group.push((old, new)),
+            None => groups.push((key, vec![(old, new)])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_files(base: &Path, files: &[&str]) {
+        for file in files {
+            let path = base.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+    }
+
+    #[test]
+    fn test_plan_file_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_files(&source, &["a.mkv", "b.mkv", "c.mkv"]);
+        create_files(&target, &["B.mkv"]);
+
+        let plan = Plan {
+            source: source.clone(),
+            operations: vec![
+                (source.join("a.mkv"), target.join("A.mkv")),
+                (source.join("b.mkv"), target.join("B.mkv")),
+                (source.join("gone.mkv"), target.join("Gone.mkv")),
+            ],
+            ..Default::default()
+        };
+        let path = temp_dir.path().join("plan.json");
+        let settings = ExecutionSettings {
+            rate_limit: Some(1024),
+            post_hooks: vec!["true".to_string()],
+            ..Default::default()
+        };
+        PlanFile::new(Mode::Copy, ContentType::Movie, 550, &plan, settings.clone())
+            .save(&path)
+            .unwrap();
+
+        let loaded = PlanFile::load(&path).unwrap();
+        assert_eq!(loaded.mode, Mode::Copy);
+        assert_eq!(loaded.tmdb_id, 550);
+        assert_eq!(loaded.settings, settings);
+
+        let plan = loaded.into_plan().unwrap();
+        assert_eq!(plan.source, source);
+        assert_eq!(
+            plan.operations,
+            vec![(source.join("a.mkv"), target.join("A.mkv"))]
+        );
+        assert_eq!(
+            plan.skipped,
+            vec![
+                (
+                    source.join("b.mkv"),
+                    SkipReason::Exists(target.join("B.mkv"))
+                ),
+                (
+                    source.join("gone.mkv"),
+                    SkipReason::Ignored("source no longer exists")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_skip_reasons() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_files(
+            &source,
+            &[
+                "a.mkv",
+                "b.mkv",
+                "c.mkv",
+                "d.mkv.part",
+                "readme.txt",
+                "Sample/a-sample.mkv",
+                "Extras/x.mkv",
+                "._a.mkv",
+            ],
+        );
+        create_files(&target, &["b.mkv"]);
+
+        let filter = PathFilter::new(&[], &["Extras/".to_string()]).unwrap();
+        let plan = Plan::collect(&source, &filter, |old, _| {
+            let name = old.file_name().unwrap();
+            Ok(if name == "c.mkv" {
+                Destination::Skip(SkipReason::NoMatch("no episode number".to_string()))
+            } else {
+                Destination::To(target.join(name))
+            })
+        })
+        .unwrap();
+
+        assert_eq!(
+            plan.operations,
+            vec![(source.join("a.mkv"), target.join("a.mkv"))]
+        );
+        assert_eq!(
+            plan.skipped,
+            vec![
+                (source.join("._a.mkv"), SkipReason::Hidden),
+                (
+                    source.join("Extras"),
+                    SkipReason::Ignored("excluded by pattern")
+                ),
+                (
+                    source.join("Sample").join("a-sample.mkv"),
+                    SkipReason::Sample
+                ),
+                (
+                    source.join("b.mkv"),
+                    SkipReason::Exists(target.join("b.mkv"))
+                ),
+                (
+                    source.join("c.mkv"),
+                    SkipReason::NoMatch("no episode number".to_string())
+                ),
+                (source.join("d.mkv.part"), SkipReason::InProgress),
+                (source.join("readme.txt"), SkipReason::UnsupportedExtension),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_up_to_date() {
+        let temp_dir = TempDir::new().unwrap();
+        let library = temp_dir.path().join("library");
+        create_files(&library, &["Movie (1999)/Movie (1999).mkv", "new.mkv"]);
+        let linked = temp_dir.path().join("Linked (2000).mkv");
+        fs::hard_link(library.join("new.mkv"), &linked).unwrap();
+
+        let plan = Plan::collect(&library, &PathFilter::default(), |old, _| {
+            Ok(Destination::To(if old.ends_with("new.mkv") {
+                linked.clone()
+            } else {
+                old.to_path_buf()
+            }))
+        })
+        .unwrap();
+
+        assert!(plan.operations.is_empty());
+        assert_eq!(
+            plan.skipped,
+            vec![
+                (
+                    library.join("Movie (1999)").join("Movie (1999).mkv"),
+                    SkipReason::UpToDate
+                ),
+                (library.join("new.mkv"), SkipReason::UpToDate),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_duplicate_output_fails() {
+        let temp_dir = TempDir::new().unwrap();
+        create_files(temp_dir.path(), &["a.mkv", "b.mkv"]);
+
+        let result = Plan::collect(temp_dir.path(), &PathFilter::default(), |_, _| {
+            Ok(Destination::To(PathBuf::from("/target/same.mkv")))
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_collect_vobsub_pairs() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let target = temp_dir.path().join("target");
+        create_files(&source, &["a.idx", "a.sub", "b.sub"]);
+
+        // Name the halves differently to check the .idx follows its .sub
+        let plan = Plan::collect(&source, &PathFilter::default(), |old, ext| {
+            let stem = old.file_stem().unwrap().to_string_lossy();
+            Ok(Destination::To(target.join(match ext {
+                "idx" => format!("{} (idx).idx", stem),
+                _ => format!("{}.{}", stem, ext),
+            })))
+        })
+        .unwrap();
+
+        assert_eq!(
+            plan.operations,
+            vec![
+                (source.join("a.idx"), target.join("a.idx")),
+                (source.join("a.sub"), target.join("a.sub")),
+            ]
+        );
+        assert_eq!(
+            plan.skipped,
+            vec![(source.join("b.sub"), SkipReason::Unpaired("idx"))]
+        );
+    }
+
+    #[test]
+    fn test_skip_growing() {
+        let temp_dir = TempDir::new().unwrap();
+        create_files(temp_dir.path(), &["done.mkv", "growing.mkv"]);
+        let growing = temp_dir.path().join("growing.mkv");
+        let mut plan = Plan::default();
+        for name in ["done.mkv", "growing.mkv"] {
+            plan.operations
+                .push((temp_dir.path().join(name), PathBuf::from(name)));
+        }
+
+        let writer = {
+            let growing = growing.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                fs::write(&growing, "more data than before").unwrap();
+            })
+        };
+        plan.skip_growing(Duration::from_millis(200));
+        writer.join().unwrap();
+
+        assert_eq!(
+            plan.operations,
+            vec![(temp_dir.path().join("done.mkv"), PathBuf::from("done.mkv"))]
+        );
+        assert_eq!(plan.skipped, vec![(growing, SkipReason::InProgress)]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let media = |tmdb_id| {
+            Some(Media {
+                content: ContentType::Show,
+                tmdb_id,
+                title: "Show".to_string(),
+            })
+        };
+        let mut plan = Plan {
+            source: PathBuf::from("/downloads/Show.S01"),
+            operations: vec![operation("/downloads/Show.S01/e1.mkv", "/tv/S01E01.mkv")],
+            media: media(1),
+            ..Default::default()
+        };
+        let other = || Plan {
+            source: PathBuf::from("/downloads/Show.S02"),
+            operations: vec![operation("/downloads/Show.S02/e1.mkv", "/tv/S02E01.mkv")],
+            media: media(1),
+            ..Default::default()
+        };
+
+        plan.merge(other()).unwrap();
+        assert_eq!(plan.source, PathBuf::from("/downloads"));
+        assert_eq!(plan.operations.len(), 2);
+        assert_eq!(plan.media, media(1));
+        assert!(plan.merge(other()).is_err());
+
+        plan.merge(Plan {
+            source: PathBuf::from("/downloads/Other"),
+            media: media(2),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(plan.media, None);
+    }
+
+    #[test]
+    fn test_sample() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let mut plan = Plan {
+            operations: (0..10)
+                .map(|i| (PathBuf::from(i.to_string()), PathBuf::from("target")))
+                .collect(),
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+
+        plan.sample(20, &mut rng);
+        assert_eq!(plan.operations.len(), 10);
+
+        plan.sample(3, &mut rng);
+        assert_eq!(plan.operations.len(), 3);
+        let sampled: Vec<usize> = plan
+            .operations
+            .iter()
+            .map(|(old, _)| old.to_string_lossy().parse().unwrap())
+            .collect();
+        assert!(
+            sampled.is_sorted(),
+            "sample keeps plan order: {:?}",
+            sampled
+        );
+    }
+
+    /// Pretend `/a` and `/b` are separate disks
+    fn fake_device(path: &Path) -> u64 {
+        u64::from(path.starts_with("/b"))
+    }
+
+    fn operation(old: &str, new: &str) -> (PathBuf, PathBuf) {
+        (PathBuf::from(old), PathBuf::from(new))
+    }
+
+    #[test]
+    fn test_order_by_device() {
+        let mut plan = Plan {
+            operations: vec![
+                operation("/src/x/1.mkv", "/b/1.mkv"),
+                operation("/src/y/2.mkv", "/a/2.mkv"),
+                operation("/src/x/3.mkv", "/a/3.mkv"),
+                operation("/src/y/4.mkv", "/b/4.mkv"),
+            ],
+            ..Default::default()
+        };
+        plan.order_by(fake_device);
+        assert_eq!(
+            plan.operations,
+            vec![
+                operation("/src/x/3.mkv", "/a/3.mkv"),
+                operation("/src/y/2.mkv", "/a/2.mkv"),
+                operation("/src/x/1.mkv", "/b/1.mkv"),
+                operation("/src/y/4.mkv", "/b/4.mkv"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_device() {
+        let groups = group_by(
+            vec![
+                operation("1", "/a/1"),
+                operation("2", "/b/2"),
+                operation("3", "/a/3"),
+            ],
+            fake_device,
+        );
+        assert_eq!(
+            groups,
+            vec![
+                vec![operation("1", "/a/1"), operation("3", "/a/3")],
+                vec![operation("2", "/b/2")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_device_of_missing_path_uses_ancestor() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(
+            device_of(&temp_dir.path().join("not").join("yet.mkv")),
+            device_of(temp_dir.path())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_device_of_missing_relative_path() {
+        let missing = Path::new("not-created-yet").join("Show (2020)");
+        assert_ne!(device_of(&missing), 0);
+        assert_eq!(device_of(&missing), device_of(Path::new(".")));
+    }
+
+    #[test]
+    fn test_skip_reason_display() {
+        assert_eq!(SkipReason::Sample.to_string(), "sample clip");
+        assert_eq!(
+            SkipReason::Exists(PathBuf::from("a.mkv")).to_string(),
+            "a.mkv already exists"
+        );
+        assert_eq!(
+            SkipReason::Processed(PathBuf::from("A.mkv")).to_string(),
+            "already organized to A.mkv"
+        );
+        assert!(SkipReason::UnsupportedExtension.is_quiet());
+        assert!(!SkipReason::Sample.is_quiet());
+    }
+}
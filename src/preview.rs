@@ -0,0 +1,151 @@
+use clap::ValueEnum;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// How planned operations are shown before confirming
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Preview {
+    /// Every source and its destination
+    #[default]
+    List,
+    /// The resulting target layout as a tree with file counts
+    Tree,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn insert(&mut self, components: &[String]) {
+        if let Some((first, rest)) = components.split_first() {
+            self.children.entry(first.clone()).or_default().insert(rest);
+        }
+    }
+
+    fn is_file(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn file_count(&self) -> usize {
+        if self.is_file() {
+            1
+        } else {
+            self.children.values().map(Node::file_count).sum()
+        }
+    }
+
+    fn render(&self, prefix: &str, lines: &mut Vec<String>) {
+        let count = self.children.len();
+        for (index, (name, child)) in self.children.iter().enumerate() {
+            let last = index + 1 == count;
+            let branch = if last { "└── " } else { "├── " };
+            if child.is_file() {
+                lines.push(format!("{}{}{}", prefix, branch, name));
+            } else {
+                lines.push(format!(
+                    "{}{}{}/ ({})",
+                    prefix,
+                    branch,
+                    name,
+                    files(child.file_count())
+                ));
+                let indent = if last { "    " } else { "│   " };
+                child.render(&format!("{}{}", prefix, indent), lines);
+            }
+        }
+    }
+}
+
+fn files(count: usize) -> String {
+    if count == 1 {
+        "1 file".to_string()
+    } else {
+        format!("{} files", count)
+    }
+}
+
+/// The deepest directory containing every path
+fn common_root(paths: &[PathBuf]) -> PathBuf {
+    let mut root = paths
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    for path in paths {
+        while !path.starts_with(&root) {
+            if !root.pop() {
+                break;
+            }
+        }
+    }
+    root
+}
+
+/// Render destination paths as a directory tree below their common root
+pub fn render_tree(paths: &[PathBuf]) -> Vec<String> {
+    let root = common_root(paths);
+    let mut tree = Node::default();
+    for path in paths {
+        let relative = path.strip_prefix(&root).unwrap_or(path);
+        let components: Vec<String> = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        tree.insert(&components);
+    }
+
+    let mut lines = vec![format!(
+        "{}/ ({})",
+        root.display(),
+        files(tree.file_count())
+    )];
+    tree.render("", &mut lines);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_root() {
+        let paths = [
+            PathBuf::from("/lib/Show (2008)/Season 01/a.mkv"),
+            PathBuf::from("/lib/Show (2008)/Season 02/b.mkv"),
+        ];
+        assert_eq!(common_root(&paths), Path::new("/lib/Show (2008)"));
+        assert_eq!(
+            common_root(&paths[..1]),
+            Path::new("/lib/Show (2008)/Season 01")
+        );
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let paths = [
+            PathBuf::from("/lib/Show (2008)/Season 01/Show - S01E01 - One.mkv"),
+            PathBuf::from("/lib/Show (2008)/Season 01/Show - S01E02 - Two.mkv"),
+            PathBuf::from("/lib/Show (2008)/Season 02/Show - S02E01 - Three.mkv"),
+            PathBuf::from("/lib/Other (2010)/Season 01/Other - S01E01 - Pilot.mkv"),
+        ];
+        assert_eq!(
+            render_tree(&paths),
+            [
+                "/lib/ (4 files)",
+                "├── Other (2010)/ (1 file)",
+                "│   └── Season 01/ (1 file)",
+                "│       └── Other - S01E01 - Pilot.mkv",
+                "└── Show (2008)/ (3 files)",
+                "    ├── Season 01/ (2 files)",
+                "    │   ├── Show - S01E01 - One.mkv",
+                "    │   └── Show - S01E02 - Two.mkv",
+                "    └── Season 02/ (1 file)",
+                "        └── Show - S02E01 - Three.mkv",
+            ]
+        );
+    }
+}
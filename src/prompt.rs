@@ -0,0 +1,53 @@
+use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+static NO_INPUT: AtomicBool = AtomicBool::new(false);
+
+/// Set how prompts behave for this run, from `--yes`/`MEDIAR_ASSUME_YES` and `--no-input`
+pub fn configure(assume_yes: bool, no_input: bool) {
+    ASSUME_YES.store(assume_yes, Ordering::Relaxed);
+    NO_INPUT.store(no_input, Ordering::Relaxed);
+}
+
+/// Whether every confirmation is answered yes
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
+
+/// Whether prompts may be shown at all
+pub fn interactive() -> bool {
+    !assume_yes() && !NO_INPUT.load(Ordering::Relaxed)
+}
+
+/// Fail with a hint instead of prompting when no one can answer
+pub fn require_input(question: &str, hint: &str) -> Result<()> {
+    if NO_INPUT.load(Ordering::Relaxed) {
+        return Err(anyhow!(
+            "Cannot ask \"{}\" without input; {}",
+            question.trim_end_matches(':'),
+            hint
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_require_input_message() {
+        configure(false, true);
+        let err = require_input("Select TV Show:", "pass --tv-id").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot ask \"Select TV Show\" without input; pass --tv-id"
+        );
+        assert!(!interactive());
+
+        configure(false, false);
+        assert!(require_input("Select TV Show:", "pass --tv-id").is_ok());
+        assert!(interactive());
+    }
+}
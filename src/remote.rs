@@ -0,0 +1,231 @@
+use anyhow::{Context, Result, anyhow};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{marker::MARKER_FILE, metrics, transfer, video::parse_extension};
+
+const SCHEME: &str = "ssh://";
+
+/// A source directory on another machine, given as `ssh://[user@]host/path`
+///
+/// The remote tree is mirrored locally as placeholder files so it can be planned like a
+/// local source; only the files that end up in the plan are downloaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSource {
+    /// Host as `ssh` and `sftp` accept it, including any user and `~/.ssh/config` alias
+    pub host: String,
+    /// Absolute path of the source directory on the host
+    pub path: String,
+    /// Local directory holding the placeholders
+    pub mirror: PathBuf,
+}
+
+/// Quote a string for the remote shell that runs `ssh` commands
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quote a path for an `sftp` batch command
+fn sftp_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+/// Parse `find -printf '%s %P\n'` output into file sizes and paths relative to the source
+fn parse_listing(listing: &str) -> Result<Vec<(u64, PathBuf)>> {
+    listing
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (size, path) = line
+                .split_once(' ')
+                .with_context(|| format!("Unexpected listing line: {}", line))?;
+            Ok((size.parse()?, PathBuf::from(path)))
+        })
+        .collect()
+}
+
+impl RemoteSource {
+    /// Parse an `ssh://` source, or `None` for a local path
+    pub fn parse(source: &str) -> Result<Option<Self>> {
+        let Some(rest) = source.strip_prefix(SCHEME) else {
+            return Ok(None);
+        };
+        let (host, path) = rest
+            .split_once('/')
+            .filter(|(host, path)| !host.is_empty() && !path.is_empty())
+            .with_context(|| format!("Expected ssh://host/path, got {}", source))?;
+        // ssh and sftp would take a host such as `-oProxyCommand=...` for an option
+        if host.starts_with('-') {
+            return Err(anyhow!("Invalid host {} in {}", host, source));
+        }
+        let path = format!("/{}", path.trim_end_matches('/'));
+
+        let digest = Sha256::digest(format!("{}:{}", host, path).as_bytes());
+        let key: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        let name = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_else(|| host.into());
+        let mirror = dirs::cache_dir()
+            .context("Failed to determine the cache directory")?
+            .join("mediar")
+            .join("remote")
+            .join(key)
+            // Keep the remote folder's name; matching searches TMDB by it
+            .join(name);
+        Ok(Some(Self {
+            host: host.to_string(),
+            path,
+            mirror,
+        }))
+    }
+
+    /// List the remote media files and mirror them as placeholders, returning how many there are
+    ///
+    /// The saved match (`.mediar.toml`) survives from earlier runs; other stale placeholders go.
+    pub fn mirror(&self) -> Result<usize> {
+        let output = Command::new("ssh")
+            .arg("--")
+            .arg(&self.host)
+            .arg(format!(
+                "find {} -type f -printf '%s %P\\n'",
+                shell_quote(&self.path)
+            ))
+            .output()
+            .context("Failed to run ssh; is it installed?")?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Listing {} on {} failed: {}",
+                self.path,
+                self.host,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let listing = parse_listing(&String::from_utf8_lossy(&output.stdout))?;
+
+        if self.mirror.exists() {
+            for entry in fs::read_dir(&self.mirror)? {
+                let entry = entry?;
+                if entry.file_name() == MARKER_FILE {
+                    continue;
+                }
+                if entry.file_type()?.is_dir() {
+                    fs::remove_dir_all(entry.path())?;
+                } else {
+                    fs::remove_file(entry.path())?;
+                }
+            }
+        }
+        fs::create_dir_all(&self.mirror)?;
+
+        let mut count = 0;
+        for (size, relative) in listing {
+            if parse_extension(&relative).is_none() {
+                continue;
+            }
+            let placeholder = self.mirror.join(&relative);
+            if let Some(parent) = placeholder.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Sparse, so sizes look right to the planner without using any space
+            fs::File::create(&placeholder)?.set_len(size)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// The remote path of a placeholder
+    pub fn remote_path(&self, placeholder: &Path) -> Result<String> {
+        let relative = placeholder
+            .strip_prefix(&self.mirror)
+            .with_context(|| format!("{} is not in the remote mirror", placeholder.display()))?;
+        Ok(format!("{}/{}", self.path, relative.to_string_lossy()))
+    }
+
+    /// Download the file behind a placeholder to `new`, through a partial file `sftp` resumes
+    pub fn pull(&self, placeholder: &Path, new: &Path, rate_limit: Option<u64>) -> Result<()> {
+        let remote = self.remote_path(placeholder)?;
+        let partial = transfer::partial_path(new);
+
+        let mut sftp = Command::new("sftp");
+        sftp.arg("-q").arg("-b").arg("-");
+        if let Some(limit) = rate_limit {
+            // sftp limits in Kbit/s
+            sftp.arg("-l").arg((limit * 8 / 1000).max(1).to_string());
+        }
+        let mut child = sftp
+            .arg("--")
+            .arg(&self.host)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run sftp; is it installed?")?;
+        writeln!(
+            child.stdin.take().context("Failed to open sftp's input")?,
+            "reget {} {}",
+            sftp_quote(&remote),
+            sftp_quote(&partial.to_string_lossy())
+        )?;
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Downloading {} from {} failed: {}",
+                remote,
+                self.host,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        fs::rename(&partial, new)
+            .with_context(|| format!("Failed to rename {} into place", partial.display()))?;
+        metrics::bytes_copied(fs::metadata(new)?.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(RemoteSource::parse("/downloads/Heat").unwrap(), None);
+        let remote = RemoteSource::parse("ssh://me@seedbox/downloads/Show.S01/")
+            .unwrap()
+            .unwrap();
+        assert_eq!(remote.host, "me@seedbox");
+        assert_eq!(remote.path, "/downloads/Show.S01");
+        assert!(remote.mirror.ends_with("Show.S01"));
+        assert_eq!(
+            remote
+                .remote_path(&remote.mirror.join("Season 1").join("e01.mkv"))
+                .unwrap(),
+            "/downloads/Show.S01/Season 1/e01.mkv"
+        );
+        assert!(RemoteSource::parse("ssh://seedbox").is_err());
+        assert!(RemoteSource::parse("ssh://-oProxyCommand=touch%20x/downloads").is_err());
+    }
+
+    #[test]
+    fn test_parse_listing() {
+        assert_eq!(
+            parse_listing("1024 Show.S01E01.mkv\n12 Sub dir/a b.srt\n").unwrap(),
+            vec![
+                (1024, PathBuf::from("Show.S01E01.mkv")),
+                (12, PathBuf::from("Sub dir/a b.srt")),
+            ]
+        );
+        assert!(parse_listing("garbage").is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(shell_quote("/it's here"), r"'/it'\''s here'");
+        assert_eq!(sftp_quote(r#"/a "b"\c"#), r#""/a \"b\"\\c""#);
+    }
+}
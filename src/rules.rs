@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use globset::{Glob, GlobMatcher};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    config::RuleConfig,
+    video::{ContentType, Resolution},
+};
+
+/// What is known about a source file when rules are evaluated
+pub struct RuleInput<'a> {
+    pub content: ContentType,
+    /// TMDB show or movie title
+    pub title: &'a str,
+    /// Source path relative to the source directory
+    pub relative: &'a Path,
+    /// Only detected when a rule has a `resolution` condition
+    pub resolution: Option<Resolution>,
+}
+
+/// Actions of the rule that matched a file
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RuleAction {
+    pub target: Option<PathBuf>,
+    pub edition: Option<String>,
+    pub skip: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    content: Option<ContentType>,
+    title: Option<Regex>,
+    path: Option<GlobMatcher>,
+    extensions: Vec<String>,
+    resolution: Option<Resolution>,
+    action: RuleAction,
+}
+
+impl Rule {
+    fn new(config: &RuleConfig) -> Result<Self> {
+        Ok(Self {
+            content: config.content,
+            title: config
+                .title
+                .as_deref()
+                .map(|title| {
+                    Regex::new(&format!("(?i){}", title))
+                        .with_context(|| format!("Invalid title regex: {}", title))
+                })
+                .transpose()?,
+            path: config
+                .path
+                .as_deref()
+                .map(|path| {
+                    Glob::new(path)
+                        .map(|glob| glob.compile_matcher())
+                        .with_context(|| format!("Invalid path glob: {}", path))
+                })
+                .transpose()?,
+            extensions: config
+                .extension
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+            resolution: config.resolution,
+            action: RuleAction {
+                target: config.target.clone(),
+                edition: config.edition.clone(),
+                skip: config.skip,
+            },
+        })
+    }
+
+    fn matches(&self, input: &RuleInput) -> bool {
+        let extension = input
+            .relative
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        self.content.is_none_or(|content| content == input.content)
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|re| re.is_match(input.title))
+            && self.path.as_ref().is_none_or(|glob| {
+                glob.is_match(input.relative)
+                    || input
+                        .relative
+                        .file_name()
+                        .is_some_and(|name| glob.is_match(name))
+            })
+            && (self.extensions.is_empty()
+                || extension.is_some_and(|ext| self.extensions.contains(&ext)))
+            && self
+                .resolution
+                .is_none_or(|resolution| input.resolution == Some(resolution))
+    }
+}
+
+/// Ordered rules from the config, evaluated per file while planning operations
+#[derive(Debug, Default, Clone)]
+pub struct Rules {
+    rules: Vec<Rule>,
+}
+
+impl Rules {
+    pub fn new(configs: &[RuleConfig]) -> Result<Self> {
+        let rules = configs
+            .iter()
+            .enumerate()
+            .map(|(index, config)| {
+                Rule::new(config).with_context(|| format!("In rule {}", index + 1))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Whether any rule needs the resolution of a file, which may mean probing it
+    pub fn uses_resolution(&self) -> bool {
+        self.rules.iter().any(|rule| rule.resolution.is_some())
+    }
+
+    /// Actions of the first rule matching the input, if any
+    pub fn evaluate(&self, input: &RuleInput) -> Option<&RuleAction> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(input))
+            .map(|rule| &rule.action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input<'a>(content: ContentType, title: &'a str, relative: &'a str) -> RuleInput<'a> {
+        RuleInput {
+            content,
+            title,
+            relative: Path::new(relative),
+            resolution: None,
+        }
+    }
+
+    #[test]
+    fn test_empty_rules_match_nothing() {
+        let rules = Rules::default();
+        assert!(
+            rules
+                .evaluate(&input(ContentType::Show, "Show", "a.mkv"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = Rules::new(&[
+            RuleConfig {
+                content: Some(ContentType::Show),
+                path: Some("*2160p*".to_string()),
+                target: Some(PathBuf::from("/mnt/anime4k")),
+                ..Default::default()
+            },
+            RuleConfig {
+                content: Some(ContentType::Show),
+                target: Some(PathBuf::from("/mnt/shows")),
+                ..Default::default()
+            },
+        ])
+        .unwrap();
+
+        let action = rules
+            .evaluate(&input(
+                ContentType::Show,
+                "One Piece",
+                "One.Piece.S01E01.2160p.mkv",
+            ))
+            .unwrap();
+        assert_eq!(action.target.as_deref(), Some(Path::new("/mnt/anime4k")));
+
+        let action = rules
+            .evaluate(&input(
+                ContentType::Show,
+                "One Piece",
+                "One.Piece.S01E01.1080p.mkv",
+            ))
+            .unwrap();
+        assert_eq!(action.target.as_deref(), Some(Path::new("/mnt/shows")));
+
+        assert!(
+            rules
+                .evaluate(&input(ContentType::Movie, "Movie", "Movie.2160p.mkv"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_title_and_extension_conditions() {
+        let rules = Rules::new(&[RuleConfig {
+            title: Some("^star trek".to_string()),
+            extension: vec!["avi".to_string()],
+            skip: true,
+            ..Default::default()
+        }])
+        .unwrap();
+
+        assert!(
+            rules
+                .evaluate(&input(
+                    ContentType::Movie,
+                    "Star Trek: Generations",
+                    "st.AVI"
+                ))
+                .is_some_and(|action| action.skip)
+        );
+        assert!(
+            rules
+                .evaluate(&input(
+                    ContentType::Movie,
+                    "Star Trek: Generations",
+                    "st.mkv"
+                ))
+                .is_none()
+        );
+        assert!(
+            rules
+                .evaluate(&input(ContentType::Movie, "Generations", "st.avi"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_invalid_rule() {
+        let result = Rules::new(&[RuleConfig {
+            title: Some("(unclosed".to_string()),
+            ..Default::default()
+        }]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_resolution_condition() {
+        let rules = Rules::new(&[RuleConfig {
+            resolution: Some(Resolution::Uhd),
+            target: Some(PathBuf::from("/media/movies-4k")),
+            ..Default::default()
+        }])
+        .unwrap();
+        assert!(rules.uses_resolution());
+        assert!(!Rules::default().uses_resolution());
+
+        let mut uhd = input(ContentType::Movie, "Movie", "Movie.2019.2160p.mkv");
+        uhd.resolution = Some(Resolution::Uhd);
+        assert_eq!(
+            rules.evaluate(&uhd).unwrap().target.as_deref(),
+            Some(Path::new("/media/movies-4k"))
+        );
+        assert!(
+            rules
+                .evaluate(&input(ContentType::Movie, "Movie", "Movie.2019.mkv"))
+                .is_none()
+        );
+    }
+}
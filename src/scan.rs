@@ -0,0 +1,358 @@
+use anyhow::{Context, Result, anyhow};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::{
+    ffi::OsStr,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+use walkdir::WalkDir;
+
+use crate::archive::EXTRACT_DIR;
+
+/// File extensions download clients give files they are still writing
+const IN_PROGRESS_EXTENSIONS: [&str; 5] = ["part", "!qb", "!ut", "crdownload", "mediar-partial"];
+
+/// Expand source arguments with glob patterns, such as `downloads/Show*`, into the matching
+/// paths, in order
+///
+/// Arguments without a pattern, including `ssh://` sources, are kept as given. A path matched
+/// more than once is only listed the first time.
+pub fn expand_sources(sources: &[String]) -> Result<Vec<String>> {
+    let mut expanded: Vec<String> = Vec::new();
+    for source in sources {
+        let matches = if source.starts_with("ssh://") || !source.contains(['*', '?', '[']) {
+            vec![source.clone()]
+        } else {
+            glob::glob(source)
+                .with_context(|| format!("Invalid glob: {}", source))?
+                .map(|path| Ok(path?.to_string_lossy().into_owned()))
+                .collect::<Result<Vec<_>>>()?
+        };
+        if matches.is_empty() {
+            return Err(anyhow!("No sources match {}", source));
+        }
+        for path in matches {
+            if !expanded.contains(&path) {
+                expanded.push(path);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Parse a duration such as `10m`, `90s`, `1h30m` or `2d`; a bare number is seconds
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let invalid = || {
+        anyhow!(
+            "Invalid duration {:?}, expected e.g. 10m or 1h30m",
+            duration
+        )
+    };
+    let trimmed = duration.trim();
+    if trimmed.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(seconds) = trimmed.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
+    }
+    let mut seconds = 0;
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .filter(|&split| split > 0)
+            .ok_or_else(invalid)?;
+        let (number, tail) = rest.split_at(split);
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let multiplier = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 60 * 60,
+            "d" => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        seconds += number.parse::<u64>()? * multiplier;
+        rest = tail;
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Include/exclude glob patterns applied while walking a source directory
+///
+/// Patterns without a `/` match against file and directory names anywhere in the tree
+/// (e.g. `*.trailer.mkv`); patterns with a `/` match against the path relative to the
+/// source (e.g. `Extras/` or `Season 01/*.srt`).
+#[derive(Debug, Default, Clone)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Files modified more recently than this are treated as still downloading
+    min_age: Option<Duration>,
+    /// How many folders deep to look below the source
+    max_depth: Option<usize>,
+    /// Descend into symlinked folders
+    follow_symlinks: bool,
+    /// Look at hidden files and folders too
+    hidden: bool,
+}
+
+/// Whether a file is still being downloaded, judging by its name
+///
+/// Covers partial files such as `.part` and `.!qB`, and files next to an aria2 `.aria2`
+/// control file.
+pub fn is_partial_download(path: &Path) -> bool {
+    let partial_extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            IN_PROGRESS_EXTENSIONS
+                .iter()
+                .any(|partial| partial.eq_ignore_ascii_case(ext))
+        });
+    let mut control = path.as_os_str().to_owned();
+    control.push(".aria2");
+    partial_extension || Path::new(&control).exists()
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_end_matches('/');
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid glob: {}", pattern))?);
+    }
+    Ok(builder.build()?)
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(build_globset(include)?)
+            },
+            exclude: build_globset(exclude)?,
+            min_age: None,
+            max_depth: None,
+            follow_symlinks: false,
+            hidden: false,
+        })
+    }
+
+    /// Treat files modified within `min_age` as still downloading
+    pub fn with_min_age(self, min_age: Option<Duration>) -> Self {
+        Self { min_age, ..self }
+    }
+
+    /// Limit how deep walks go and whether they follow symlinks
+    pub fn with_traversal(self, max_depth: Option<usize>, follow_symlinks: bool) -> Self {
+        Self {
+            max_depth,
+            follow_symlinks,
+            ..self
+        }
+    }
+
+    /// Include hidden files and folders, whose names start with `.`
+    pub fn with_hidden(self, hidden: bool) -> Self {
+        Self { hidden, ..self }
+    }
+
+    /// Whether a file or folder below the source is left alone for being hidden
+    ///
+    /// AppleDouble files (`._Movie.mkv`) only hold macOS metadata, so they are skipped even
+    /// with hidden files included. Archives extracted for the run are never skipped.
+    pub fn is_hidden(&self, name: &OsStr) -> bool {
+        let name = name.to_string_lossy();
+        if name == EXTRACT_DIR {
+            return false;
+        }
+        name.starts_with("._") || (!self.hidden && name.starts_with('.'))
+    }
+
+    /// A walk of `source` as configured, going `default_depth` levels deep unless a maximum
+    /// depth was given
+    pub fn walk(&self, source: &Path, default_depth: usize) -> WalkDir {
+        WalkDir::new(source)
+            .max_depth(self.max_depth.unwrap_or(default_depth))
+            .follow_links(self.follow_symlinks)
+    }
+
+    /// Whether a file was modified too recently to be complete
+    pub fn is_too_recent(&self, path: &Path) -> bool {
+        let Some(min_age) = self.min_age else {
+            return false;
+        };
+        fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < min_age)
+    }
+
+    fn matches(set: &GlobSet, relative: &Path) -> bool {
+        set.is_match(relative)
+            || relative
+                .file_name()
+                .is_some_and(|name| set.is_match(Path::new(name)))
+    }
+
+    /// Whether a file or directory (relative to the source) is excluded
+    pub fn is_excluded(&self, relative: &Path) -> bool {
+        Self::matches(&self.exclude, relative)
+    }
+
+    /// Whether a file (relative to the source) passes the include patterns
+    pub fn is_included(&self, relative: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| Self::matches(include, relative))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_empty_filter_allows_everything() {
+        let filter = PathFilter::default();
+        assert!(!filter.is_excluded(Path::new("Extras/clip.mkv")));
+        assert!(filter.is_included(Path::new("Extras/clip.mkv")));
+    }
+
+    #[test]
+    fn test_exclude_directory() {
+        let filter = PathFilter::new(&[], &patterns(&["Extras/"])).unwrap();
+        assert!(filter.is_excluded(Path::new("Extras")));
+        assert!(filter.is_excluded(Path::new("Movie/Extras")));
+        assert!(!filter.is_excluded(Path::new("Movie.mkv")));
+    }
+
+    #[test]
+    fn test_exclude_file_name_pattern() {
+        let filter = PathFilter::new(&[], &patterns(&["*.trailer.mkv"])).unwrap();
+        assert!(filter.is_excluded(Path::new("Movie.trailer.mkv")));
+        assert!(filter.is_excluded(Path::new("sub/Movie.trailer.mkv")));
+        assert!(!filter.is_excluded(Path::new("Movie.mkv")));
+    }
+
+    #[test]
+    fn test_include_pattern() {
+        let filter = PathFilter::new(&patterns(&["*.mkv"]), &[]).unwrap();
+        assert!(filter.is_included(Path::new("Season 01/Show.S01E01.mkv")));
+        assert!(!filter.is_included(Path::new("Season 01/Show.S01E01.srt")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_traversal() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let nested = temp_dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("deep.mkv"), "video").unwrap();
+        let outside = tempfile::TempDir::new().unwrap();
+        fs::write(outside.path().join("linked.mkv"), "video").unwrap();
+        std::os::unix::fs::symlink(outside.path(), temp_dir.path().join("link")).unwrap();
+
+        let files = |filter: &PathFilter| {
+            filter
+                .walk(temp_dir.path(), usize::MAX)
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_type().is_file())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(files(&PathFilter::default()), ["deep.mkv"]);
+        assert!(files(&PathFilter::default().with_traversal(Some(2), false)).is_empty());
+        let mut followed = files(&PathFilter::default().with_traversal(None, true));
+        followed.sort();
+        assert_eq!(followed, ["deep.mkv", "linked.mkv"]);
+    }
+
+    #[test]
+    fn test_is_hidden() {
+        let hidden = |filter: &PathFilter, name: &str| filter.is_hidden(OsStr::new(name));
+        let filter = PathFilter::default();
+        assert!(hidden(&filter, ".hidden.mkv"));
+        assert!(hidden(&filter, "._Movie.mkv"));
+        assert!(hidden(&filter, ".AppleDouble"));
+        assert!(!hidden(&filter, "Movie.mkv"));
+        assert!(!hidden(&filter, EXTRACT_DIR));
+
+        let filter = filter.with_hidden(true);
+        assert!(!hidden(&filter, ".hidden.mkv"));
+        assert!(hidden(&filter, "._Movie.mkv"));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("10m").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::from_secs(172_800));
+        assert_eq!(parse_duration("45").unwrap(), Duration::from_secs(45));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("10 minutes").is_err());
+        assert!(parse_duration("m").is_err());
+    }
+
+    #[test]
+    fn test_expand_sources() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for name in ["Show.S02", "Show.S01", "Other"] {
+            fs::create_dir(temp_dir.path().join(name)).unwrap();
+        }
+        let dir = temp_dir.path().to_string_lossy();
+
+        assert_eq!(
+            expand_sources(&[
+                format!("{}/Show*", dir),
+                format!("{}/Other", dir),
+                format!("{}/Show.S01", dir)
+            ])
+            .unwrap(),
+            vec![
+                format!("{}/Show.S01", dir),
+                format!("{}/Show.S02", dir),
+                format!("{}/Other", dir)
+            ]
+        );
+        assert!(expand_sources(&[format!("{}/Movie*", dir)]).is_err());
+        assert_eq!(
+            expand_sources(&patterns(&["ssh://seedbox/downloads/[x]"])).unwrap(),
+            patterns(&["ssh://seedbox/downloads/[x]"])
+        );
+    }
+
+    #[test]
+    fn test_in_progress() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let video = temp_dir.path().join("Show.S01E01.mkv");
+        fs::write(&video, "video").unwrap();
+
+        assert!(is_partial_download(Path::new("Show.S01E01.mkv.part")));
+        assert!(is_partial_download(Path::new("Show.S01E01.mkv.!qB")));
+        assert!(!is_partial_download(&video));
+        fs::write(temp_dir.path().join("Show.S01E01.mkv.aria2"), "").unwrap();
+        assert!(is_partial_download(&video));
+
+        let filter = PathFilter::default();
+        assert!(!filter.is_too_recent(&video));
+        let filter = filter.with_min_age(Some(Duration::from_secs(600)));
+        assert!(filter.is_too_recent(&video));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(PathFilter::new(&[], &patterns(&["[unclosed"])).is_err());
+    }
+}
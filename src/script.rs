@@ -0,0 +1,194 @@
+use anyhow::{Context, Result, anyhow};
+use rhai::{AST, Dynamic, Engine, Map, Scope};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::video::ContentType;
+
+/// Metadata passed to a naming script along with the computed destination
+#[derive(Debug, Clone)]
+pub struct ScriptInput {
+    pub content: ContentType,
+    /// TMDB show or movie title
+    pub title: String,
+    pub year: i32,
+    /// TMDB ID
+    pub id: i32,
+    pub season: Option<i32>,
+    pub episode: Option<i32>,
+    pub episode_title: Option<String>,
+    pub edition: Option<String>,
+    /// Source path relative to the source directory
+    pub source: PathBuf,
+}
+
+impl ScriptInput {
+    fn to_map(&self) -> Map {
+        let mut map = Map::new();
+        let content = match self.content {
+            ContentType::Show => "show",
+            ContentType::Movie => "movie",
+        };
+        map.insert("content".into(), content.into());
+        map.insert("title".into(), self.title.clone().into());
+        map.insert("year".into(), Dynamic::from_int(self.year.into()));
+        map.insert("id".into(), Dynamic::from_int(self.id.into()));
+        let optional_int =
+            |value: Option<i32>| value.map_or(Dynamic::UNIT, |v| Dynamic::from_int(v.into()));
+        map.insert("season".into(), optional_int(self.season));
+        map.insert("episode".into(), optional_int(self.episode));
+        let optional_str =
+            |value: &Option<String>| value.clone().map_or(Dynamic::UNIT, Dynamic::from);
+        map.insert("episode_title".into(), optional_str(&self.episode_title));
+        map.insert("edition".into(), optional_str(&self.edition));
+        map.insert(
+            "source".into(),
+            self.source.to_string_lossy().into_owned().into(),
+        );
+        map
+    }
+}
+
+/// A Rhai script defining `fn rename(path, media)` to rewrite destination paths
+///
+/// `rename` returns the new destination as a string, or `()` to keep the computed one.
+pub struct NamingScript {
+    name: String,
+    engine: Engine,
+    ast: AST,
+}
+
+impl fmt::Debug for NamingScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamingScript")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+// The engine has no state beyond its defaults, so a clone gets a fresh one
+impl Clone for NamingScript {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            engine: Engine::new(),
+            ast: self.ast.clone(),
+        }
+    }
+}
+
+impl NamingScript {
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read naming script {}", path.display()))?;
+        Self::compile(&path.to_string_lossy(), &source)
+    }
+
+    pub fn compile(name: &str, source: &str) -> Result<Self> {
+        let engine = Engine::new();
+        let ast = engine
+            .compile(source)
+            .map_err(|err| anyhow!("Failed to compile naming script {}: {}", name, err))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "rename" && f.params.len() == 2)
+        {
+            return Err(anyhow!(
+                "Naming script {} must define fn rename(path, media)",
+                name
+            ));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    /// Run the script on a computed destination
+    pub fn apply(&self, destination: &Path, input: &ScriptInput) -> Result<PathBuf> {
+        let result: Dynamic = self
+            .engine
+            .call_fn(
+                &mut Scope::new(),
+                &self.ast,
+                "rename",
+                (destination.to_string_lossy().into_owned(), input.to_map()),
+            )
+            .map_err(|err| anyhow!("Naming script {} failed: {}", self.name, err))?;
+
+        if result.is_unit() {
+            return Ok(destination.to_path_buf());
+        }
+        let path = result.into_string().map_err(|type_name| {
+            anyhow!(
+                "Naming script {} returned {} instead of a path",
+                self.name,
+                type_name
+            )
+        })?;
+        Ok(PathBuf::from(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movie_input() -> ScriptInput {
+        ScriptInput {
+            content: ContentType::Movie,
+            title: "Star Wars".to_string(),
+            year: 1977,
+            id: 11,
+            season: None,
+            episode: None,
+            episode_title: None,
+            edition: None,
+            source: PathBuf::from("Star.Wars.1977.mkv"),
+        }
+    }
+
+    #[test]
+    fn test_apply_rewrites_path() {
+        let script = NamingScript::compile(
+            "test",
+            r#"
+            fn rename(path, media) {
+                if media.content == "movie" && media.title.starts_with("Star Wars") {
+                    path.replace("/movies/", "/movies/Star Wars Saga/");
+                    return path;
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let destination = Path::new("/movies/Star Wars (1977)/Star Wars (1977).mkv");
+        assert_eq!(
+            script.apply(destination, &movie_input()).unwrap(),
+            Path::new("/movies/Star Wars Saga/Star Wars (1977)/Star Wars (1977).mkv")
+        );
+
+        let other = ScriptInput {
+            title: "Alien".to_string(),
+            ..movie_input()
+        };
+        let destination = Path::new("/movies/Alien (1979)/Alien (1979).mkv");
+        assert_eq!(script.apply(destination, &other).unwrap(), destination);
+    }
+
+    #[test]
+    fn test_missing_rename_function() {
+        assert!(NamingScript::compile("test", "fn other(x) { x }").is_err());
+    }
+
+    #[test]
+    fn test_invalid_return_type() {
+        let script = NamingScript::compile("test", "fn rename(path, media) { 42 }").unwrap();
+        assert!(script.apply(Path::new("a.mkv"), &movie_input()).is_err());
+    }
+}
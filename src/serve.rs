@@ -0,0 +1,652 @@
+use anyhow::{Context, Result};
+use axum::{
+    Json, Router,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    routing::{get, post},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{metrics, plan::Mode, tmdb};
+
+/// Address `mediar serve` listens on unless `--listen` is given; local only
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8787";
+
+/// How long a queued request waits before each new attempt; the last delay repeats
+const RETRY_DELAYS: [Duration; 4] = [
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(15 * 60),
+    Duration::from_secs(60 * 60),
+];
+
+/// Body of `POST /organize`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrganizeRequest {
+    /// The download to organize
+    pub path: String,
+    /// Library root; the configured targets are used when left out
+    #[serde(default)]
+    pub target: Option<String>,
+    #[serde(default = "default_mode")]
+    pub mode: Mode,
+    #[serde(default)]
+    pub tv_id: Option<i32>,
+    #[serde(default)]
+    pub movie_id: Option<i32>,
+    /// Only plan the run and return the plan
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_mode() -> Mode {
+    Mode::Link
+}
+
+/// Response of `POST /organize`: how the run ended and the events it produced
+#[derive(Debug, PartialEq, Serialize)]
+pub struct RunReport {
+    /// `done`, `nothing_to_do`, `partial_failure`, `cancelled` or `planned` for dry runs
+    pub status: String,
+    /// Planned, skipped, executed and failed operations, as in `--output ndjson`
+    pub events: Vec<serde_json::Value>,
+}
+
+/// Organizes a request; runs one at a time
+pub type Runner = Arc<dyn Fn(OrganizeRequest) -> Result<RunReport> + Send + Sync>;
+
+/// A request that failed while TMDB was unavailable, waiting to be run again
+#[derive(Debug, Clone, Serialize)]
+struct QueuedRequest {
+    #[serde(flatten)]
+    request: OrganizeRequest,
+    /// How many times the request has been run
+    attempts: usize,
+    /// Why the last attempt failed
+    error: String,
+    /// When the next attempt is made, in RFC 3339
+    retry_at: String,
+}
+
+/// Requests waiting for TMDB to come back, each retried on its own backoff schedule
+struct Queue {
+    delays: Vec<Duration>,
+    pending: Mutex<Vec<QueuedRequest>>,
+}
+
+impl Queue {
+    fn new(delays: &[Duration]) -> Self {
+        Self {
+            delays: delays.to_vec(),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The delay before attempt `attempts + 1`
+    fn delay(&self, attempts: usize) -> Duration {
+        self.delays[(attempts - 1).min(self.delays.len() - 1)]
+    }
+
+    /// Add or update the entry for `request` after a failed attempt, returning the delay
+    /// before the next one and whether the request was newly queued
+    ///
+    /// A request posted again while it is queued keeps the attempts and schedule of the
+    /// retries already under way; only its error is updated.
+    fn wait(
+        &self,
+        request: &OrganizeRequest,
+        attempts: usize,
+        error: &anyhow::Error,
+    ) -> (Duration, bool) {
+        let delay = self.delay(attempts);
+        let queued = QueuedRequest {
+            request: request.clone(),
+            attempts,
+            error: format!("{:#}", error),
+            retry_at: (chrono::Local::now() + delay).to_rfc3339(),
+        };
+        let mut pending = self.pending.lock().unwrap();
+        match pending.iter_mut().find(|entry| entry.request == *request) {
+            Some(entry) if attempts > entry.attempts => *entry = queued,
+            Some(entry) => entry.error = queued.error,
+            None => {
+                pending.push(queued);
+                return (delay, true);
+            }
+        }
+        (delay, false)
+    }
+
+    fn remove(&self, request: &OrganizeRequest) {
+        self.pending
+            .lock()
+            .unwrap()
+            .retain(|entry| entry.request != *request);
+    }
+}
+
+/// How many failed runs `GET /status` reports
+const RECENT_ERRORS: usize = 10;
+
+/// A finished run, as reported by `GET /status`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub path: String,
+    /// When the run ended, in RFC 3339
+    pub at: String,
+    /// The run's status, or why it failed
+    pub outcome: String,
+}
+
+/// A request waiting for TMDB, as reported by `GET /status`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub path: String,
+    pub attempts: usize,
+    pub error: String,
+    pub retry_at: String,
+}
+
+/// Body of `GET /status`: the queue and how recent runs went
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub queued: Vec<QueueEntry>,
+    pub last_success: Option<RunRecord>,
+    /// Newest first
+    pub recent_errors: Vec<RunRecord>,
+}
+
+/// The last successful run and the latest failures since the server started
+#[derive(Default)]
+struct History {
+    last_success: Option<RunRecord>,
+    recent_errors: VecDeque<RunRecord>,
+}
+
+impl History {
+    fn record(&mut self, path: &str, result: &Result<RunReport>) {
+        let record = |outcome| RunRecord {
+            path: path.to_string(),
+            at: chrono::Local::now().to_rfc3339(),
+            outcome,
+        };
+        match result {
+            Ok(report) => self.last_success = Some(record(report.status.clone())),
+            Err(err) => {
+                self.recent_errors.push_front(record(format!("{:#}", err)));
+                self.recent_errors.truncate(RECENT_ERRORS);
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    runner: Runner,
+    token: Option<String>,
+    queue: Arc<Queue>,
+    history: Arc<Mutex<History>>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+type Reply<T> = Result<Json<T>, (StatusCode, Json<ErrorBody>)>;
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<ErrorBody>) {
+    (status, Json(ErrorBody { error: message }))
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+async fn render_metrics() -> ([(header::HeaderName, &'static str); 1], String) {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, Json<ErrorBody>)> {
+    if let Some(token) = &state.token {
+        let expected = format!("Bearer {}", token);
+        let given = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        if given != Some(expected.as_str()) {
+            return Err(error(
+                StatusCode::UNAUTHORIZED,
+                "Missing or wrong bearer token".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Run a request, keeping the blocking run off the server's threads, and record how a real run
+/// went
+async fn run(state: &AppState, request: OrganizeRequest) -> Result<RunReport> {
+    let _queued = metrics::Queued::start();
+    let runner = state.runner.clone();
+    let (path, dry_run) = (request.path.clone(), request.dry_run);
+    let result = tokio::task::spawn_blocking(move || runner(request))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+    // A dry run only plans, so it says nothing about how the library is kept
+    if !dry_run {
+        state.history.lock().unwrap().record(&path, &result);
+    }
+    result
+}
+
+/// Run a queued request again after each delay until TMDB answers
+async fn retry(state: AppState, request: OrganizeRequest, mut delay: Duration) {
+    for attempts in 2.. {
+        tokio::time::sleep(delay).await;
+        match run(&state, request.clone()).await {
+            Err(err) if tmdb::is_unavailable(&err) => {
+                delay = state.queue.wait(&request, attempts, &err).0;
+                tracing::warn!(path = %request.path, attempts, error = %format!("{:#}", err), "TMDB still unavailable, keeping the request queued");
+            }
+            result => {
+                state.queue.remove(&request);
+                match result {
+                    Ok(report) => {
+                        tracing::info!(path = %request.path, status = %report.status, "ran queued request")
+                    }
+                    Err(err) => {
+                        tracing::error!(path = %request.path, error = %format!("{:#}", err), "queued request failed")
+                    }
+                }
+                return;
+            }
+        }
+    }
+}
+
+async fn organize(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<OrganizeRequest>,
+) -> Result<(StatusCode, Json<RunReport>), (StatusCode, Json<ErrorBody>)> {
+    authorize(&state, &headers)?;
+    match run(&state, request.clone()).await {
+        Ok(report) => Ok((StatusCode::OK, Json(report))),
+        // A plan is only useful as the answer, so dry runs are not queued
+        Err(err) if tmdb::is_unavailable(&err) && !request.dry_run => {
+            // A request posted again while queued is left to the retries already scheduled
+            if let (delay, true) = state.queue.wait(&request, 1, &err) {
+                tokio::spawn(retry(state.clone(), request, delay));
+            }
+            Ok((
+                StatusCode::ACCEPTED,
+                Json(RunReport {
+                    status: "queued".to_string(),
+                    events: Vec::new(),
+                }),
+            ))
+        }
+        Err(err) if tmdb::is_unavailable(&err) => {
+            Err(error(StatusCode::SERVICE_UNAVAILABLE, format!("{:#}", err)))
+        }
+        Err(err) => Err(error(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("{:#}", err),
+        )),
+    }
+}
+
+/// Requests waiting for TMDB, with their attempts, last error and next attempt
+async fn list_queue(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Reply<Vec<QueuedRequest>> {
+    authorize(&state, &headers)?;
+    Ok(Json(state.queue.pending.lock().unwrap().clone()))
+}
+
+/// The queued requests, the last successful run and the latest failures, for `mediar status`
+async fn status(State(state): State<AppState>, headers: HeaderMap) -> Reply<ServerStatus> {
+    authorize(&state, &headers)?;
+    let queued = state
+        .queue
+        .pending
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|entry| QueueEntry {
+            path: entry.request.path.clone(),
+            attempts: entry.attempts,
+            error: entry.error.clone(),
+            retry_at: entry.retry_at.clone(),
+        })
+        .collect();
+    let history = state.history.lock().unwrap();
+    Ok(Json(ServerStatus {
+        queued,
+        last_success: history.last_success.clone(),
+        recent_errors: history.recent_errors.iter().cloned().collect(),
+    }))
+}
+
+/// The server's routes: `GET /health`, `GET /metrics`, `POST /organize`, `GET /queue` and
+/// `GET /status`
+pub fn router(runner: Runner, token: Option<String>) -> Router {
+    app(runner, token, Queue::new(&RETRY_DELAYS))
+}
+
+fn app(runner: Runner, token: Option<String>, queue: Queue) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/metrics", get(render_metrics))
+        .route("/organize", post(organize))
+        .route("/queue", get(list_queue))
+        .route("/status", get(status))
+        .with_state(AppState {
+            runner,
+            token,
+            queue: Arc::new(queue),
+            history: Arc::default(),
+        })
+}
+
+/// Ask the server at `address` for its status, or `None` if no server answers there
+pub async fn fetch_status(address: &str, token: Option<&str>) -> Result<Option<ServerStatus>> {
+    let base = if address.contains("://") {
+        address.trim_end_matches('/').to_string()
+    } else {
+        format!("http://{}", address)
+    };
+    let mut request = reqwest::Client::new().get(format!("{}/status", base));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(err) if err.is_connect() => return Ok(None),
+        Err(err) => return Err(err).with_context(|| format!("Failed to reach {}", base)),
+    };
+    let response = response
+        .error_for_status()
+        .with_context(|| format!("{} refused the status request", base))?;
+    Ok(Some(response.json().await?))
+}
+
+/// Serve until the process is stopped
+pub async fn serve(listen: SocketAddr, runner: Runner, token: Option<String>) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, router(runner, token)).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmdb::TmdbError;
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    async fn start(token: Option<&str>) -> String {
+        let runner: Runner = Arc::new(|request: OrganizeRequest| {
+            if request.path == "/missing" {
+                return Err(anyhow!("No such directory"));
+            }
+            Ok(RunReport {
+                status: if request.dry_run { "planned" } else { "done" }.to_string(),
+                events: vec![serde_json::json!({ "event": "executed", "source": request.path })],
+            })
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let app = router(runner, token.map(str::to_string));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", address)
+    }
+
+    #[tokio::test]
+    async fn test_organize() {
+        let base = start(None).await;
+        let client = reqwest::Client::new();
+
+        let health = client.get(format!("{}/health", base)).send().await.unwrap();
+        assert_eq!(health.status(), 200);
+        let metrics = client
+            .get(format!("{}/metrics", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(metrics.status(), 200);
+        assert!(
+            metrics
+                .text()
+                .await
+                .unwrap()
+                .contains("mediar_files_organized_total")
+        );
+
+        let response = client
+            .post(format!("{}/organize", base))
+            .json(&serde_json::json!({ "path": "/downloads/Heat", "dry_run": true }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "planned");
+        assert_eq!(body["events"][0]["source"], "/downloads/Heat");
+
+        let response = client
+            .post(format!("{}/organize", base))
+            .json(&serde_json::json!({ "path": "/missing" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 422);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "No such directory");
+    }
+
+    #[tokio::test]
+    async fn test_organize_queued() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let runner: Runner = {
+            let calls = calls.clone();
+            Arc::new(move |request: OrganizeRequest| {
+                // TMDB is down for the first two runs: the queued request and the dry run
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    return Err(anyhow::Error::new(TmdbError::Other {
+                        status: 503,
+                        message: "Service Unavailable".to_string(),
+                    }));
+                }
+                Ok(RunReport {
+                    status: "done".to_string(),
+                    events: vec![serde_json::json!({ "source": request.path })],
+                })
+            })
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = app(runner, None, Queue::new(&[Duration::from_millis(200)]));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("{}/organize", base))
+            .json(&serde_json::json!({ "path": "/downloads/Heat" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 202);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "queued");
+
+        let queued: serde_json::Value = client
+            .get(format!("{}/queue", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(queued[0]["path"], "/downloads/Heat");
+        assert_eq!(queued[0]["attempts"], 1);
+
+        // Dry runs answer with a plan, so they fail instead of waiting
+        let response = client
+            .post(format!("{}/organize", base))
+            .json(&serde_json::json!({ "path": "/downloads/Heat", "dry_run": true }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 503);
+
+        // Retried until TMDB answers, then dropped from the queue
+        let mut queued = serde_json::json!(null);
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            queued = client
+                .get(format!("{}/queue", base))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            if queued == serde_json::json!([]) {
+                break;
+            }
+        }
+        assert_eq!(queued, serde_json::json!([]));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_organize_queued_twice() {
+        let down = Arc::new(AtomicBool::new(true));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let runner: Runner = {
+            let (down, calls) = (down.clone(), calls.clone());
+            Arc::new(move |request: OrganizeRequest| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if down.load(Ordering::SeqCst) {
+                    return Err(anyhow::Error::new(TmdbError::RateLimited {
+                        message: "Too Many Requests".to_string(),
+                    }));
+                }
+                Ok(RunReport {
+                    status: "done".to_string(),
+                    events: vec![serde_json::json!({ "source": request.path })],
+                })
+            })
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let app = app(runner, None, Queue::new(&[Duration::from_millis(300)]));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+        let queue = || async {
+            client
+                .get(format!("{}/queue", base))
+                .send()
+                .await
+                .unwrap()
+                .json::<serde_json::Value>()
+                .await
+                .unwrap()
+        };
+
+        for _ in 0..2 {
+            let response = client
+                .post(format!("{}/organize", base))
+                .json(&serde_json::json!({ "path": "/downloads/Heat" }))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 202);
+        }
+        let queued = queue().await;
+        assert_eq!(queued.as_array().unwrap().len(), 1);
+        assert_eq!(queued[0]["attempts"], 1);
+
+        // Only one retry runs once TMDB answers again
+        down.store(false, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(900)).await;
+        assert_eq!(queue().await, serde_json::json!([]));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_status() {
+        let base = start(Some("secret")).await;
+        let address = base.trim_start_matches("http://");
+        assert!(fetch_status(address, None).await.is_err());
+
+        let status = fetch_status(address, Some("secret"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status, ServerStatus::default());
+
+        let client = reqwest::Client::new();
+        for path in ["/downloads/Heat", "/missing"] {
+            client
+                .post(format!("{}/organize", base))
+                .bearer_auth("secret")
+                .json(&serde_json::json!({ "path": path }))
+                .send()
+                .await
+                .unwrap();
+        }
+        let status = fetch_status(&base, Some("secret")).await.unwrap().unwrap();
+        let last_success = status.last_success.unwrap();
+        assert_eq!(last_success.path, "/downloads/Heat");
+        assert_eq!(last_success.outcome, "done");
+        assert_eq!(status.recent_errors.len(), 1);
+        assert_eq!(status.recent_errors[0].path, "/missing");
+        assert_eq!(status.recent_errors[0].outcome, "No such directory");
+        assert!(status.queued.is_empty());
+
+        // Nothing listens on the port once a listener is dropped
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        assert_eq!(fetch_status(&address, None).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_organize_token() {
+        let base = start(Some("secret")).await;
+        let client = reqwest::Client::new();
+        let request = || {
+            client
+                .post(format!("{}/organize", base))
+                .json(&serde_json::json!({ "path": "/downloads/Heat" }))
+        };
+
+        assert_eq!(request().send().await.unwrap().status(), 401);
+        assert_eq!(
+            request()
+                .bearer_auth("secret")
+                .send()
+                .await
+                .unwrap()
+                .status(),
+            200
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OptionalExtension, params};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use crate::plan::{Plan, SkipReason};
+
+/// Bytes hashed from each end of a file to fingerprint it
+const FINGERPRINT_WINDOW: u64 = 1024 * 1024;
+
+/// Identify a file by its size and the bytes at its start and end
+///
+/// Reading a few megabytes instead of the whole file keeps this cheap for large videos, and
+/// the fingerprint survives renames, moves and re-downloads of the same release.
+pub fn fingerprint(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+    let mut buffer = Vec::new();
+    (&mut file)
+        .take(FINGERPRINT_WINDOW)
+        .read_to_end(&mut buffer)?;
+    if size > FINGERPRINT_WINDOW {
+        file.seek(SeekFrom::Start(
+            size.saturating_sub(FINGERPRINT_WINDOW)
+                .max(FINGERPRINT_WINDOW),
+        ))?;
+        file.read_to_end(&mut buffer)?;
+    }
+    hasher.update(&buffer);
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// SQLite database of files already organized, keyed by fingerprint
+pub struct StateDb {
+    conn: Connection,
+}
+
+impl StateDb {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open state database {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS processed (
+                fingerprint TEXT PRIMARY KEY,
+                source TEXT NOT NULL,
+                target TEXT NOT NULL,
+                organized_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS releases (
+                path TEXT PRIMARY KEY,
+                imported_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS placeholders (
+                target TEXT PRIMARY KEY,
+                recorded_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS verified (
+                dir TEXT PRIMARY KEY,
+                verified_at TEXT NOT NULL
+            )",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Where a file with this fingerprint was organized to, if it was
+    pub fn target_of(&self, fingerprint: &str) -> Result<Option<PathBuf>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT target FROM processed WHERE fingerprint = ?1",
+                params![fingerprint],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(PathBuf::from))
+    }
+
+    /// Remember that `source` was organized to `target`, fingerprinting the target
+    pub fn record(&self, source: &Path, target: &Path) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO processed (fingerprint, source, target, organized_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                fingerprint(target)?,
+                source.to_string_lossy(),
+                target.to_string_lossy(),
+                chrono::Local::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Whether `mediar import` already imported this release folder
+    pub fn is_imported(&self, release: &Path) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM releases WHERE path = ?1",
+                params![release.to_string_lossy()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    /// Remember that a release folder was imported, so later scans pass over it
+    pub fn record_import(&self, release: &Path) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO releases (path, imported_at) VALUES (?1, ?2)",
+            params![release.to_string_lossy(), chrono::Local::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Remember that `target` was named with a placeholder episode title, for `mediar refresh`
+    pub fn record_placeholder(&self, target: &Path) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO placeholders (target, recorded_at) VALUES (?1, ?2)",
+            params![target.to_string_lossy(), chrono::Local::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// The files named with a placeholder episode title, in path order
+    pub fn placeholders(&self) -> Result<Vec<PathBuf>> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT target FROM placeholders ORDER BY target")?;
+        statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|target| Ok(PathBuf::from(target?)))
+            .collect()
+    }
+
+    /// Forget placeholder files below `dir` that were renamed or removed, returning how many
+    pub fn forget_placeholders(&self, dir: &Path) -> Result<usize> {
+        let mut forgotten = 0;
+        for target in self.placeholders()? {
+            if target.starts_with(dir) && !target.exists() {
+                self.conn.execute(
+                    "DELETE FROM placeholders WHERE target = ?1",
+                    params![target.to_string_lossy()],
+                )?;
+                forgotten += 1;
+            }
+        }
+        Ok(forgotten)
+    }
+
+    /// Whether an unfinished `mediar verify` already found this title folder correctly named
+    pub fn is_verified(&self, dir: &Path) -> Result<bool> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT 1 FROM verified WHERE dir = ?1",
+                params![dir.to_string_lossy()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    /// Remember that a title folder is correctly named, so an interrupted verify resumes after it
+    pub fn record_verified(&self, dir: &Path) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO verified (dir, verified_at) VALUES (?1, ?2)",
+            params![dir.to_string_lossy(), chrono::Local::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the verified title folders below `library` once a verify of it finishes
+    pub fn forget_verified(&self, library: &Path) -> Result<()> {
+        let mut statement = self.conn.prepare("SELECT dir FROM verified")?;
+        let dirs = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for dir in dirs {
+            if Path::new(&dir).starts_with(library) {
+                self.conn
+                    .execute("DELETE FROM verified WHERE dir = ?1", params![dir])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move planned operations whose source was organized before into the skipped list
+    pub fn skip_processed(&self, plan: &mut Plan) -> Result<()> {
+        let mut operations = Vec::new();
+        for (old, new) in std::mem::take(&mut plan.operations) {
+            match self.target_of(&fingerprint(&old)?)? {
+                Some(target) => plan.skipped.push((old, SkipReason::Processed(target))),
+                None => operations.push((old, new)),
+            }
+        }
+        plan.operations = operations;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_fingerprint() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("a.mkv");
+        let b = temp_dir.path().join("b.mkv");
+        let c = temp_dir.path().join("c.mkv");
+        let large: Vec<u8> = (0..3 * FINGERPRINT_WINDOW)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut changed_end = large.clone();
+        *changed_end.last_mut().unwrap() ^= 1;
+        fs::write(&a, &large).unwrap();
+        fs::write(&b, &large).unwrap();
+        fs::write(&c, &changed_end).unwrap();
+
+        assert_eq!(fingerprint(&a).unwrap(), fingerprint(&b).unwrap());
+        assert_ne!(fingerprint(&a).unwrap(), fingerprint(&c).unwrap());
+        assert_eq!(fingerprint(&a).unwrap().len(), 64);
+    }
+
+    #[test]
+    fn test_record_import() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = StateDb::open(&temp_dir.path().join("state.db")).unwrap();
+        let release = Path::new("/downloads/Show.S01.1080p");
+
+        assert!(!db.is_imported(release).unwrap());
+        db.record_import(release).unwrap();
+        assert!(db.is_imported(release).unwrap());
+        assert!(!db.is_imported(Path::new("/downloads/Show.S02")).unwrap());
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = StateDb::open(&temp_dir.path().join("state.db")).unwrap();
+        let show_dir = temp_dir.path().join("Show (2008)");
+        let renamed = show_dir.join("Season 01/Show - S01E02 - TBA.mkv");
+        let waiting = show_dir.join("Season 01/Show - S01E03 - TBA.mkv");
+        let other = temp_dir
+            .path()
+            .join("Other (2010)/Season 01/Other - S01E01 - TBA.mkv");
+        fs::create_dir_all(waiting.parent().unwrap()).unwrap();
+        fs::write(&waiting, "").unwrap();
+
+        for target in [&renamed, &waiting, &other] {
+            db.record_placeholder(target).unwrap();
+        }
+        assert_eq!(db.forget_placeholders(&show_dir).unwrap(), 1);
+        assert_eq!(db.placeholders().unwrap(), vec![other, waiting]);
+    }
+
+    #[test]
+    fn test_verified() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = StateDb::open(&temp_dir.path().join("state.db")).unwrap();
+        let heat = Path::new("/media/movies/Heat (1995)");
+        let other = Path::new("/mnt/movies/Alien (1979)");
+
+        assert!(!db.is_verified(heat).unwrap());
+        db.record_verified(heat).unwrap();
+        db.record_verified(other).unwrap();
+        assert!(db.is_verified(heat).unwrap());
+
+        db.forget_verified(Path::new("/media/movies")).unwrap();
+        assert!(!db.is_verified(heat).unwrap());
+        assert!(db.is_verified(other).unwrap());
+    }
+
+    #[test]
+    fn test_skip_processed() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = StateDb::open(&temp_dir.path().join("state").join("state.db")).unwrap();
+        let organized = temp_dir.path().join("Movie (1999).mkv");
+        let reseeded = temp_dir.path().join("Movie.1999.mkv");
+        let new = temp_dir.path().join("New.2020.mkv");
+        fs::write(&organized, "movie").unwrap();
+        fs::write(&reseeded, "movie").unwrap();
+        fs::write(&new, "new").unwrap();
+
+        db.record(Path::new("/downloads/Movie.1999.mkv"), &organized)
+            .unwrap();
+        let mut plan = Plan::default();
+        plan.operations.push((reseeded.clone(), organized.clone()));
+        plan.operations
+            .push((new.clone(), temp_dir.path().join("New (2020).mkv")));
+
+        db.skip_processed(&mut plan).unwrap();
+        assert_eq!(plan.operations.len(), 1);
+        assert_eq!(plan.operations[0].0, new);
+        assert_eq!(
+            plan.skipped,
+            vec![(reseeded, SkipReason::Processed(organized))]
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    thread,
+    time::Duration,
+};
+use walkdir::WalkDir;
+
+use crate::video::parse_extension;
+
+/// An entry from `rclone lsjson`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct RemoteEntry {
+    path: String,
+    size: i64,
+    is_dir: bool,
+}
+
+/// Why a file needs to be sent to the remote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncReason {
+    Missing,
+    Changed,
+}
+
+/// Parse `rclone lsjson` output into file sizes keyed by relative path
+fn parse_listing(json: &str) -> Result<HashMap<PathBuf, u64>> {
+    let entries: Vec<RemoteEntry> = serde_json::from_str(json)?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| (PathBuf::from(entry.path), entry.size.max(0) as u64))
+        .collect())
+}
+
+/// List the files on an rclone remote such as `gdrive:Media`
+pub fn list_remote(remote: &str) -> Result<HashMap<PathBuf, u64>> {
+    let output = Command::new("rclone")
+        .args(["lsjson", "--recursive", "--files-only", remote])
+        .output()
+        .context("Failed to run rclone; is it installed?")?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "rclone lsjson {} failed: {}",
+            remote,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    parse_listing(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Media files in the local library with their sizes, relative to the library root
+pub fn list_local(library: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(library).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() || parse_extension(entry.path()).is_none() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(library)?.to_path_buf();
+        files.push((relative, entry.metadata()?.len()));
+    }
+    Ok(files)
+}
+
+/// Local files that are missing from the remote or differ in size
+pub fn plan(
+    local: &[(PathBuf, u64)],
+    remote: &HashMap<PathBuf, u64>,
+) -> Vec<(PathBuf, SyncReason)> {
+    local
+        .iter()
+        .filter_map(|(path, size)| match remote.get(path) {
+            None => Some((path.clone(), SyncReason::Missing)),
+            Some(remote_size) if remote_size != size => Some((path.clone(), SyncReason::Changed)),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+/// Remote location of a library file, e.g. `gdrive:Media/Show/Season 01/...`
+pub fn remote_path(remote: &str, relative: &Path) -> String {
+    let relative = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    if remote.ends_with(':') || remote.ends_with('/') {
+        format!("{}{}", remote, relative)
+    } else {
+        format!("{}/{}", remote, relative)
+    }
+}
+
+/// How long to wait before each new attempt at sending a file; one attempt more than delays
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+    Duration::from_secs(120),
+];
+
+/// An rclone filter matching exactly one path below the root, with glob characters escaped
+fn exact_filter(relative: &Path) -> String {
+    let mut filter = String::new();
+    for component in relative.components() {
+        filter.push('/');
+        for c in component.as_os_str().to_string_lossy().chars() {
+            if matches!(c, '*' | '?' | '[' | ']' | '{' | '}' | '\\') {
+                filter.push('\\');
+            }
+            filter.push(c);
+        }
+    }
+    filter
+}
+
+/// Run rclone, failing with its error output
+fn rclone(args: &[&std::ffi::OsStr], what: &str) -> Result<()> {
+    let output = Command::new("rclone")
+        .args(args)
+        .output()
+        .context("Failed to run rclone; is it installed?")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{} failed: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Run `attempt` until it succeeds, waiting `delays` between tries
+fn with_retries(delays: &[Duration], mut attempt: impl FnMut() -> Result<()>) -> Result<()> {
+    let mut delays = delays.iter();
+    loop {
+        match attempt() {
+            Ok(()) => return Ok(()),
+            Err(err) => match delays.next() {
+                Some(delay) => {
+                    tracing::warn!(delay = ?delay, error = %format!("{:#}", err), "transfer failed, retrying");
+                    thread::sleep(*delay);
+                }
+                None => return Err(err),
+            },
+        }
+    }
+}
+
+/// Copy a single library file to the remote and check it arrived intact
+///
+/// A dropped transfer is tried again with a new `rclone copyto`. After each transfer,
+/// `rclone check` compares the remote file to the local one by hash, or by size where the
+/// remote has no hashes, and a mismatch sends the file again.
+pub fn copy_to_remote(library: &Path, remote: &str, relative: &Path) -> Result<()> {
+    let destination = remote_path(remote, relative);
+    let source = library.join(relative);
+    let filter = exact_filter(relative);
+    with_retries(&RETRY_DELAYS, || {
+        rclone(
+            &["copyto".as_ref(), source.as_os_str(), destination.as_ref()],
+            &format!("rclone copyto {}", destination),
+        )?;
+        rclone(
+            &[
+                "check".as_ref(),
+                "--one-way".as_ref(),
+                "--include".as_ref(),
+                filter.as_ref(),
+                library.as_os_str(),
+                remote.as_ref(),
+            ],
+            &format!("Checking {}", destination),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_listing() {
+        let listing = parse_listing(
+            r#"[
+                {"Path":"Show","Name":"Show","Size":-1,"ModTime":"2024-01-01T00:00:00Z","IsDir":true},
+                {"Path":"Show/Season 01/Show - S01E01.mkv","Name":"Show - S01E01.mkv","Size":42,"ModTime":"2024-01-01T00:00:00Z","IsDir":false}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(listing.len(), 1);
+        assert_eq!(
+            listing.get(Path::new("Show/Season 01/Show - S01E01.mkv")),
+            Some(&42)
+        );
+    }
+
+    #[test]
+    fn test_plan() {
+        let local = vec![
+            (PathBuf::from("a.mkv"), 1),
+            (PathBuf::from("b.mkv"), 2),
+            (PathBuf::from("c.mkv"), 3),
+        ];
+        let remote = HashMap::from([(PathBuf::from("a.mkv"), 1), (PathBuf::from("b.mkv"), 5)]);
+        assert_eq!(
+            plan(&local, &remote),
+            vec![
+                (PathBuf::from("b.mkv"), SyncReason::Changed),
+                (PathBuf::from("c.mkv"), SyncReason::Missing),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_remote_path() {
+        let relative = Path::new("Show").join("Season 01").join("e.mkv");
+        assert_eq!(
+            remote_path("gdrive:", &relative),
+            "gdrive:Show/Season 01/e.mkv"
+        );
+        assert_eq!(
+            remote_path("gdrive:Media", &relative),
+            "gdrive:Media/Show/Season 01/e.mkv"
+        );
+    }
+
+    #[test]
+    fn test_exact_filter() {
+        assert_eq!(
+            exact_filter(&Path::new("Heat (1995) [tmdbid-949]").join("Heat (1995).mkv")),
+            "/Heat (1995) \\[tmdbid-949\\]/Heat (1995).mkv"
+        );
+    }
+
+    #[test]
+    fn test_with_retries() {
+        let delays = [Duration::ZERO; 2];
+        let mut attempts = 0;
+        with_retries(&delays, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(anyhow!("connection reset"))
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap();
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let err = with_retries(&delays, || {
+            attempts += 1;
+            Err(anyhow!("connection reset"))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "connection reset");
+        assert_eq!(attempts, 3);
+    }
+
+    #[test]
+    fn test_list_local_media_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let season = temp_dir.path().join("Show").join("Season 01");
+        fs::create_dir_all(&season).unwrap();
+        fs::write(season.join("Show - S01E01.mkv"), "video").unwrap();
+        fs::write(season.join("notes.txt"), "notes").unwrap();
+
+        let files = list_local(temp_dir.path()).unwrap();
+        assert_eq!(
+            files,
+            vec![(
+                Path::new("Show/Season 01/Show - S01E01.mkv").to_path_buf(),
+                5
+            )]
+        );
+    }
+}
@@ -1,20 +1,83 @@
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
 use futures::future::try_join_all;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::video::episode_id;
+use crate::{auth, config::TmdbConfig, metrics, scan, video::episode_id};
 
+/// TMDB's API, unless `base_url` or `MEDIAR_TMDB_URL` points elsewhere
 const BASE_URL: &str = "https://api.themoviedb.org/3";
 
+/// Where posters are served from, at a width that suits a terminal preview
+const POSTER_BASE_URL: &str = "https://image.tmdb.org/t/p/w185";
+
+/// Search results fetched unless a limit is given, one page from TMDB
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// Backoff schedule for retrying transient failures such as TMDB maintenance windows
+const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(4),
+    Duration::from_secs(16),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Genre {
+    pub id: i32,
+    pub name: String,
+}
+
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Tv {
     pub id: i32,
     pub name: String,
+    /// Name in the show's original language
+    #[serde(default)]
+    pub original_name: Option<String>,
     pub overview: String,
     pub first_air_date: String,
     pub number_of_episodes: i32,
     pub number_of_seasons: i32,
+    /// The seasons TMDB lists, which may skip numbers or include announced ones
+    #[serde(default)]
+    pub seasons: Vec<TvSeasonSummary>,
+    #[serde(default)]
+    pub genres: Vec<Genre>,
+    #[serde(default)]
+    pub poster_path: Option<String>,
+}
+
+/// A season as listed in a series, without its episodes
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct TvSeasonSummary {
+    pub season_number: i32,
+    #[serde(default)]
+    pub episode_count: i32,
+    pub air_date: Option<String>,
+}
+
+impl Tv {
+    /// The regular seasons worth fetching: those listed with episodes, leaving out specials
+    ///
+    /// Without a season list, seasons are assumed to be numbered 1 to `number_of_seasons`.
+    pub fn season_numbers(&self) -> Vec<i32> {
+        if self.seasons.is_empty() {
+            return (1..=self.number_of_seasons).collect();
+        }
+        self.seasons
+            .iter()
+            .filter(|season| season.season_number >= 1 && season.episode_count > 0)
+            .map(|season| season.season_number)
+            .collect()
+    }
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
@@ -24,6 +87,7 @@ pub struct TvSeasonEpisode {
     pub episode_number: i32,
     pub name: String,
     pub overview: String,
+    pub air_date: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
@@ -32,6 +96,7 @@ pub struct TvSeason {
     pub season_number: i32,
     pub name: String,
     pub overview: String,
+    pub air_date: Option<String>,
     pub episodes: Vec<TvSeasonEpisode>,
 }
 
@@ -39,6 +104,8 @@ pub struct TvSeason {
 pub struct Show {
     pub id: i32,
     pub name: String,
+    /// Name in the show's original language
+    pub original_name: Option<String>,
     pub overview: String,
     pub year: i32,
     pub first_air_date: String,
@@ -57,22 +124,40 @@ pub struct TvSearchResult {
     pub popularity: Option<f64>,
 }
 
+/// One page of search results, or several pages combined by [`TmdbClient::search_tv`]
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
-pub struct TvSearchResponse {
+pub struct SearchResponse<T> {
     pub page: i32,
-    pub results: Vec<TvSearchResult>,
+    pub results: Vec<T>,
     pub total_pages: i32,
     pub total_results: i32,
 }
 
+pub type TvSearchResponse = SearchResponse<TvSearchResult>;
+
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
 pub struct Movie {
     pub id: i32,
     pub title: String,
+    /// Title in the movie's original language
+    #[serde(default)]
+    pub original_title: Option<String>,
     pub overview: String,
     pub release_date: String,
     pub original_language: String,
     pub popularity: f64,
+    pub imdb_id: Option<String>,
+    #[serde(default)]
+    pub genres: Vec<Genre>,
+    #[serde(default)]
+    pub poster_path: Option<String>,
+}
+
+/// IDs of a show on other databases
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct ExternalIds {
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<i32>,
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
@@ -85,27 +170,267 @@ pub struct MovieSearchResult {
     pub popularity: Option<f64>,
 }
 
+pub type MovieSearchResponse = SearchResponse<MovieSearchResult>;
+
+/// A result of `/search/multi`, which also finds people
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "media_type", rename_all = "lowercase")]
+pub enum MultiSearchResult {
+    Tv(TvSearchResult),
+    Movie(MovieSearchResult),
+    #[serde(other)]
+    Other,
+}
+
+impl SearchResponse<MultiSearchResult> {
+    /// Separate shows from movies, counting only the results fetched
+    pub fn split(self) -> (TvSearchResponse, MovieSearchResponse) {
+        let mut tv = Vec::new();
+        let mut movies = Vec::new();
+        for result in self.results {
+            match result {
+                MultiSearchResult::Tv(result) => tv.push(result),
+                MultiSearchResult::Movie(result) => movies.push(result),
+                MultiSearchResult::Other => {}
+            }
+        }
+        (
+            SearchResponse {
+                page: self.page,
+                total_pages: self.total_pages,
+                total_results: tv.len() as i32,
+                results: tv,
+            },
+            SearchResponse {
+                page: self.page,
+                total_pages: self.total_pages,
+                total_results: movies.len() as i32,
+                results: movies,
+            },
+        )
+    }
+}
+
+impl MultiSearchResult {
+    pub fn id(&self) -> i32 {
+        match self {
+            MultiSearchResult::Tv(result) => result.id,
+            MultiSearchResult::Movie(result) => result.id,
+            MultiSearchResult::Other => 0,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            MultiSearchResult::Tv(result) => &result.name,
+            MultiSearchResult::Movie(result) => &result.title,
+            MultiSearchResult::Other => "",
+        }
+    }
+
+    pub fn year(&self) -> Option<i32> {
+        match self {
+            MultiSearchResult::Tv(result) => result.year(),
+            MultiSearchResult::Movie(result) => result.year(),
+            MultiSearchResult::Other => None,
+        }
+    }
+
+    pub fn popularity(&self) -> f64 {
+        match self {
+            MultiSearchResult::Tv(result) => result.popularity,
+            MultiSearchResult::Movie(result) => result.popularity,
+            MultiSearchResult::Other => None,
+        }
+        .unwrap_or(0.0)
+    }
+}
+
+/// Results of looking up an external (e.g. IMDb) ID
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
-pub struct MovieSearchResponse {
-    pub page: i32,
-    pub results: Vec<MovieSearchResult>,
-    pub total_pages: i32,
-    pub total_results: i32,
+pub struct FindResponse {
+    pub movie_results: Vec<MovieSearchResult>,
+    pub tv_results: Vec<TvSearchResult>,
+}
+
+/// Which numbering to organize a show's episodes by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpisodeOrder {
+    /// Seasons and episodes as originally aired
+    #[default]
+    Aired,
+    /// The order of the DVD or Blu-ray release
+    Dvd,
+    /// Episodes numbered consecutively across the whole show
+    Absolute,
+}
+
+impl EpisodeOrder {
+    /// TMDB's episode group type for this order, or `None` for the regular seasons
+    fn group_type(self) -> Option<i32> {
+        match self {
+            EpisodeOrder::Aired => None,
+            EpisodeOrder::Absolute => Some(2),
+            EpisodeOrder::Dvd => Some(3),
+        }
+    }
+}
+
+/// An alternative ordering of a show's episodes, as listed by `/tv/{id}/episode_groups`
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct EpisodeGroup {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub group_type: i32,
+    pub episode_count: i32,
+    pub group_count: i32,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct EpisodeGroupsResponse {
+    pub results: Vec<EpisodeGroup>,
+}
+
+/// An episode group with its seasons (TMDB calls them groups) and episodes
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct EpisodeGroupDetails {
+    pub id: String,
+    pub name: String,
+    pub groups: Vec<EpisodeGroupSeason>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct EpisodeGroupSeason {
+    pub id: String,
+    pub name: String,
+    pub order: i32,
+    pub episodes: Vec<EpisodeGroupEpisode>,
+}
+
+/// An episode with its aired numbering and its zero-based position in the group
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct EpisodeGroupEpisode {
+    #[serde(flatten)]
+    pub episode: TvSeasonEpisode,
+    pub order: i32,
+}
+
+/// The body of a TMDB error response
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    status_message: String,
 }
 
-trait ResponseExt {
-    async fn decode<T: for<'de> Deserialize<'de>>(self) -> Result<T>;
+/// A request TMDB answered with an error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TmdbError {
+    /// No show, movie or season with the requested ID (404)
+    NotFound { request: String, message: String },
+    /// The token or API key is invalid, expired or lacks access (401)
+    Unauthorized { message: String },
+    /// Too many requests, still after retrying (429)
+    RateLimited { message: String },
+    /// Any other error status
+    Other { status: u16, message: String },
 }
 
-impl ResponseExt for reqwest::Response {
-    async fn decode<T: for<'de> Deserialize<'de>>(self) -> Result<T> {
-        let url = self.url().to_string();
-        let text = self.text().await?;
-        serde_json::from_str(&text)
-            .map_err(|e| anyhow::anyhow!("Failed to deserialize response from {url}: {e}:\n{text}"))
+impl TmdbError {
+    /// Classify an error response to `request`, such as `/tv/1396`, by its status and body
+    fn from_response(status: StatusCode, request: &str, body: &str) -> Self {
+        let message = serde_json::from_str::<ErrorResponse>(body)
+            .map(|error| error.status_message.trim_end_matches('.').to_string())
+            .unwrap_or_else(|_| {
+                status
+                    .canonical_reason()
+                    .unwrap_or("no details")
+                    .to_string()
+            });
+        match status {
+            StatusCode::NOT_FOUND => Self::NotFound {
+                request: request.to_string(),
+                message,
+            },
+            StatusCode::UNAUTHORIZED => Self::Unauthorized { message },
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited { message },
+            _ => Self::Other {
+                status: status.as_u16(),
+                message,
+            },
+        }
     }
 }
 
+impl fmt::Display for TmdbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound { request, message } => write!(
+                f,
+                "TMDB has nothing at {} ({}); check that the ID is right",
+                request, message
+            ),
+            Self::Unauthorized { message } => write!(
+                f,
+                "TMDB rejected the credentials ({}); run `mediar auth login` with a valid token or API key",
+                message
+            ),
+            Self::RateLimited { message } => write!(
+                f,
+                "TMDB is limiting requests ({}); wait a minute and try again",
+                message
+            ),
+            Self::Other { status, message } => {
+                write!(f, "TMDB answered with status {} ({})", status, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TmdbError {}
+
+fn decode<T: DeserializeOwned>(url: &str, text: &str) -> Result<T> {
+    serde_json::from_str(text)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize response from {url}: {e}:\n{text}"))
+}
+
+/// Year component of a TMDB date such as `2008-01-20`
+pub fn year_of(date: &str) -> Option<i32> {
+    date.split('-').next()?.parse().ok()
+}
+
+impl TvSearchResult {
+    pub fn year(&self) -> Option<i32> {
+        self.first_air_date.as_deref().and_then(year_of)
+    }
+}
+
+impl MovieSearchResult {
+    pub fn year(&self) -> Option<i32> {
+        self.release_date.as_deref().and_then(year_of)
+    }
+}
+
+/// Whether a run failed because TMDB could not be reached or kept failing, even after retrying,
+/// so running it again later may succeed
+pub fn is_unavailable(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_connect() || err.is_timeout();
+        }
+        match cause.downcast_ref::<TmdbError>() {
+            Some(TmdbError::RateLimited { .. }) => true,
+            Some(TmdbError::Other { status, .. }) => *status >= 500,
+            _ => false,
+        }
+    })
+}
+
+/// Whether a response status indicates TMDB is temporarily unavailable
+fn is_transient(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
 impl Show {
     pub fn episodes(&self) -> HashMap<String, &TvSeasonEpisode> {
         self.seasons
@@ -120,39 +445,310 @@ impl Show {
             })
             .collect()
     }
+
+    /// Renumber seasons and episodes by an episode group, e.g. DVD order
+    ///
+    /// Each group becomes a season numbered by its order, and episodes are numbered by their
+    /// position in the group, starting at 1.
+    pub fn reorder(&mut self, details: EpisodeGroupDetails) {
+        self.seasons = details
+            .groups
+            .into_iter()
+            .map(|group| {
+                let mut episodes: Vec<TvSeasonEpisode> = group
+                    .episodes
+                    .into_iter()
+                    .map(|entry| TvSeasonEpisode {
+                        season_number: group.order,
+                        episode_number: entry.order + 1,
+                        ..entry.episode
+                    })
+                    .collect();
+                episodes.sort_by_key(|episode| episode.episode_number);
+                TvSeason {
+                    id: 0,
+                    season_number: group.order,
+                    name: group.name,
+                    overview: String::new(),
+                    air_date: episodes
+                        .first()
+                        .and_then(|episode| episode.air_date.clone()),
+                    episodes,
+                }
+            })
+            .collect();
+        self.number_of_seasons = self.seasons.len() as i32;
+    }
 }
 
+/// The URL of a poster image, e.g. for `/ggFHVNu6YYI5L9pCfOacjizRGt.jpg`
+fn poster_url(poster_path: &str) -> String {
+    format!("{}{}", POSTER_BASE_URL, poster_path)
+}
+
+/// How requests prove access to TMDB
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// A v4 API Read Access Token, sent as a bearer token
+    Token(String),
+    /// A classic v3 API key, sent as the `api_key` query parameter
+    ApiKey(String),
+}
+
+impl Credential {
+    /// Tell a v3 API key (32 hex digits) from a v4 token, which is much longer
+    pub fn detect(value: String) -> Self {
+        if value.len() == 32 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+            Self::ApiKey(value)
+        } else {
+            Self::Token(value)
+        }
+    }
+}
+
+/// Where the response to a request is cached, named after a hash of its URL without the API key
+fn cache_path(dir: &Path, url: &reqwest::Url) -> PathBuf {
+    let digest = Sha256::digest(log_url(url).as_bytes());
+    let name: String = digest[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    dir.join(format!("{}.json", name))
+}
+
+/// A request URL as logged, without the API key
+fn log_url(url: &reqwest::Url) -> String {
+    let mut url = url.clone();
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| name != "api_key")
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+    url.to_string()
+}
+
+#[derive(Clone)]
 pub struct TmdbClient {
     client: reqwest::Client,
-    token: String,
+    credential: Credential,
+    base_url: String,
+    language: Option<String>,
+    /// Directory every successful response is saved to, for `--offline` runs
+    cache: Option<PathBuf>,
+    /// Serve responses from `cache` only, never reaching TMDB
+    offline: bool,
+    /// The only seasons [`TmdbClient::show`] fetches, when not all of them are needed
+    seasons: Option<BTreeSet<i32>>,
 }
 
 impl TmdbClient {
-    pub fn new() -> Result<Self> {
+    /// A client using the token from `mediar auth login` or `TMDB_API_TOKEN`, or else the
+    /// v3 key in `TMDB_API_KEY`
+    ///
+    /// An `offline` client answers only from responses cached by earlier runs, and needs no
+    /// credentials.
+    pub fn new(settings: &TmdbConfig, offline: bool) -> Result<Self> {
+        let credential = auth::token()
+            .map(Credential::detect)
+            .or_else(|| std::env::var("TMDB_API_KEY").ok().map(Credential::ApiKey));
+        let credential = match credential {
+            Some(credential) => credential,
+            None if offline => Credential::Token(String::new()),
+            None => {
+                return Err(anyhow!(
+                    "No TMDB credentials; run `mediar auth login` or set TMDB_API_TOKEN or TMDB_API_KEY"
+                ));
+            }
+        };
         Ok(Self {
-            client: reqwest::Client::new(),
-            token: std::env::var("TMDB_API_TOKEN")?,
+            offline,
+            ..Self::connect(settings, credential)?
         })
     }
 
+    /// A client with the given credential, reaching TMDB as configured
+    ///
+    /// `MEDIAR_TMDB_URL` and `MEDIAR_TMDB_TIMEOUT` override the config file. Besides the
+    /// configured proxy, the usual `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` variables apply.
+    pub fn connect(settings: &TmdbConfig, credential: Credential) -> Result<Self> {
+        let env = |name| {
+            std::env::var(name)
+                .ok()
+                .filter(|value: &String| !value.is_empty())
+        };
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &settings.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy {}", proxy))?,
+            );
+        }
+        if let Some(timeout) = env("MEDIAR_TMDB_TIMEOUT").or(settings.timeout.clone()) {
+            builder = builder.timeout(scan::parse_duration(&timeout)?);
+        }
+        let base_url = env("MEDIAR_TMDB_URL")
+            .or(settings.base_url.clone())
+            .unwrap_or_else(|| BASE_URL.to_string());
+        Ok(Self {
+            client: builder.build()?,
+            credential,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            language: None,
+            cache: dirs::cache_dir().map(|dir| dir.join("mediar").join("tmdb")),
+            offline: false,
+            seasons: None,
+        })
+    }
+
+    /// Check that TMDB accepts the credential
+    pub async fn validate(&self) -> Result<()> {
+        let response = self.send(self.get("/authentication")).await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(TmdbError::from_response(status, "/authentication", &body).into());
+        }
+        Ok(())
+    }
+
+    /// Request localized metadata (e.g., `de-DE`) instead of the TMDB default
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Keep tests against a local server from writing to the user's cache
+    #[cfg(test)]
+    pub fn without_cache(mut self) -> Self {
+        self.cache = None;
+        self
+    }
+
+    /// Fetch only these seasons of a show, e.g. the ones a source has episodes of
+    pub fn with_seasons(mut self, seasons: Option<BTreeSet<i32>>) -> Self {
+        self.seasons = seasons;
+        self
+    }
+
+    fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(&[("language", self.language.as_deref())]);
+        match &self.credential {
+            Credential::Token(token) => request.bearer_auth(token),
+            Credential::ApiKey(key) => request.query(&[("api_key", key)]),
+        }
+    }
+
+    /// Send a request, retrying with backoff while TMDB is unreachable or overloaded
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut delays = RETRY_DELAYS.iter();
+        loop {
+            let request = request
+                .try_clone()
+                .context("Failed to clone TMDB request")?
+                .build()?;
+            tracing::debug!(url = %log_url(request.url()), "TMDB request");
+            let response = self.client.execute(request).await;
+
+            let transient = match &response {
+                Ok(response) => is_transient(response.status()),
+                Err(err) => err.is_connect() || err.is_timeout(),
+            };
+
+            match delays.next() {
+                Some(delay) if transient => {
+                    tracing::warn!(delay = ?delay, "TMDB unavailable, retrying");
+                    tokio::time::sleep(*delay).await
+                }
+                _ => {
+                    metrics::tmdb_request(
+                        response
+                            .as_ref()
+                            .map_or(true, |response| !response.status().is_success()),
+                    );
+                    return Ok(response?);
+                }
+            }
+        }
+    }
+
+    /// Fetch and decode a response, saving it to the cache or, offline, reading it from there
+    async fn fetch<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        let url = request
+            .try_clone()
+            .context("Failed to clone TMDB request")?
+            .build()?
+            .url()
+            .clone();
+        let cached = self.cache.as_deref().map(|dir| cache_path(dir, &url));
+        if self.offline {
+            let text = cached
+                .and_then(|path| fs::read_to_string(path).ok())
+                .with_context(|| {
+                    format!(
+                        "{} is not cached; run once without --offline to fetch it",
+                        log_url(&url)
+                    )
+                })?;
+            return decode(&log_url(&url), &text);
+        }
+
+        let response = self.send(request).await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            let request = url
+                .as_str()
+                .strip_prefix(&self.base_url)
+                .unwrap_or(url.path());
+            let request = request.split('?').next().unwrap_or(request);
+            return Err(TmdbError::from_response(status, request, &text).into());
+        }
+        let value = decode(&log_url(&url), &text)?;
+        if let Some(path) = cached {
+            let saved = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::write(&path, &text));
+            if let Err(err) = saved {
+                tracing::debug!(path = %path.display(), %err, "Failed to cache TMDB response");
+            }
+        }
+        Ok(value)
+    }
+
     pub async fn show(&self, id: i32) -> Result<Show> {
-        let series = self.series(id).await?;
+        self.show_of(self.series(id).await?).await
+    }
+
+    /// The show for series details already fetched, fetching only its seasons
+    pub async fn show_of(&self, series: Tv) -> Result<Show> {
+        let id = series.id;
         let seasons = try_join_all(
-            (1..=series.number_of_seasons)
-                .map(|season_number| self.season(id, season_number))
+            series
+                .season_numbers()
+                .into_iter()
+                .filter(|season| {
+                    self.seasons
+                        .as_ref()
+                        .is_none_or(|only| only.contains(season))
+                })
+                .map(|season_number| self.listed_season(id, season_number))
                 .collect::<Vec<_>>(),
         )
-        .await?;
-        let year = series
-            .first_air_date
-            .split('-')
-            .next()
-            .and_then(|y| y.parse().ok())
-            .unwrap_or(0);
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+        let year = year_of(&series.first_air_date).unwrap_or(0);
 
         Ok(Show {
             id: series.id,
             name: series.name,
+            original_name: series.original_name,
             overview: series.overview,
             year,
             first_air_date: series.first_air_date,
@@ -162,56 +758,164 @@ impl TmdbClient {
         })
     }
 
-    pub async fn series(&self, id: i32) -> Result<Tv> {
-        self.client
-            .get(format!("{}/tv/{}", BASE_URL, id))
-            .bearer_auth(&self.token)
-            .send()
+    /// Renumber a fetched show by the first TMDB episode group of the given order
+    pub async fn reorder(&self, show: &mut Show, order: EpisodeOrder) -> Result<()> {
+        let Some(group_type) = order.group_type() else {
+            return Ok(());
+        };
+        let group = self
+            .episode_groups(show.id)
             .await?
-            .decode()
+            .into_iter()
+            .find(|group| group.group_type == group_type)
+            .ok_or_else(|| anyhow!("TMDB has no {:?} order for {}", order, show.name))?;
+        show.reorder(self.episode_group(&group.id).await?);
+        Ok(())
+    }
+
+    /// Alternative episode orderings of a show, such as DVD or absolute order
+    pub async fn episode_groups(&self, id: i32) -> Result<Vec<EpisodeGroup>> {
+        let response: EpisodeGroupsResponse = self
+            .fetch(self.get(&format!("/tv/{}/episode_groups", id)))
+            .await?;
+        Ok(response.results)
+    }
+
+    pub async fn episode_group(&self, group_id: &str) -> Result<EpisodeGroupDetails> {
+        self.fetch(self.get(&format!("/tv/episode_group/{}", group_id)))
+            .await
+    }
+
+    pub async fn series(&self, id: i32) -> Result<Tv> {
+        self.fetch(self.get(&format!("/tv/{}", id))).await
+    }
+
+    pub async fn tv_external_ids(&self, id: i32) -> Result<ExternalIds> {
+        self.fetch(self.get(&format!("/tv/{}/external_ids", id)))
             .await
     }
 
+    /// A season the series lists, or `None` if TMDB has no such season after all
+    async fn listed_season(&self, id: i32, season: i32) -> Result<Option<TvSeason>> {
+        match self.season(id, season).await {
+            Ok(season) => Ok(Some(season)),
+            Err(err) if matches!(err.downcast_ref(), Some(TmdbError::NotFound { .. })) => {
+                tracing::warn!(id, season, "Season listed by TMDB not found, skipping");
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
     pub async fn season(&self, id: i32, season: i32) -> Result<TvSeason> {
-        self.client
-            .get(format!("{}/tv/{}/season/{}", BASE_URL, id, season))
-            .bearer_auth(&self.token)
-            .send()
-            .await?
-            .decode()
+        self.fetch(self.get(&format!("/tv/{}/season/{}", id, season)))
             .await
     }
 
-    pub async fn search_tv(&self, query: &str) -> Result<TvSearchResponse> {
-        self.client
-            .get(format!("{}/search/tv", BASE_URL))
-            .bearer_auth(&self.token)
+    /// Search request, narrowed to a release year through `year_param` when one is known
+    fn search(
+        &self,
+        path: &str,
+        query: &str,
+        year_param: &str,
+        year: Option<i32>,
+        page: i32,
+    ) -> reqwest::RequestBuilder {
+        self.get(path)
             .query(&[("query", query)])
-            .send()
-            .await?
-            .decode()
+            .query(&[(year_param, year)])
+            .query(&[("page", (page > 1).then_some(page))])
+    }
+
+    /// Fetch pages of search results until `limit` results or the last page is reached
+    async fn search_pages<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        query: &str,
+        year_param: &str,
+        year: Option<i32>,
+        limit: usize,
+    ) -> Result<SearchResponse<T>> {
+        let mut response: SearchResponse<T> = self
+            .fetch(self.search(path, query, year_param, year, 1))
+            .await?;
+        while response.results.len() < limit && response.page < response.total_pages {
+            let next: SearchResponse<T> = self
+                .fetch(self.search(path, query, year_param, year, response.page + 1))
+                .await?;
+            if next.results.is_empty() {
+                break;
+            }
+            response.page = next.page;
+            response.results.extend(next.results);
+        }
+        response.results.truncate(limit);
+        Ok(response)
+    }
+
+    pub async fn search_tv(
+        &self,
+        query: &str,
+        year: Option<i32>,
+        limit: usize,
+    ) -> Result<TvSearchResponse> {
+        self.search_pages("/search/tv", query, "first_air_date_year", year, limit)
             .await
     }
 
-    pub async fn search_movie(&self, query: &str) -> Result<MovieSearchResponse> {
-        self.client
-            .get(format!("{}/search/movie", BASE_URL))
-            .bearer_auth(&self.token)
-            .query(&[("query", query)])
-            .send()
-            .await?
-            .decode()
+    /// Search shows and movies in one request, ranked together by TMDB; people are left out
+    pub async fn search_multi(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<SearchResponse<MultiSearchResult>> {
+        let mut response: SearchResponse<MultiSearchResult> = self
+            .search_pages("/search/multi", query, "year", None, limit)
+            .await?;
+        response
+            .results
+            .retain(|result| *result != MultiSearchResult::Other);
+        Ok(response)
+    }
+
+    pub async fn search_movie(
+        &self,
+        query: &str,
+        year: Option<i32>,
+        limit: usize,
+    ) -> Result<MovieSearchResponse> {
+        self.search_pages("/search/movie", query, "year", year, limit)
             .await
     }
 
+    /// Look up shows and movies by IMDb ID (e.g., `tt0137523`)
+    pub async fn find_imdb(&self, imdb_id: &str) -> Result<FindResponse> {
+        self.fetch(
+            self.get(&format!("/find/{}", imdb_id))
+                .query(&[("external_source", "imdb_id")]),
+        )
+        .await
+    }
+
     pub async fn movie(&self, id: i32) -> Result<Movie> {
-        self.client
-            .get(format!("{}/movie/{}", BASE_URL, id))
-            .bearer_auth(&self.token)
+        self.fetch(self.get(&format!("/movie/{}", id))).await
+    }
+
+    /// Download a poster image by its `poster_path`
+    ///
+    /// A poster is only a preview, so it is tried once instead of waiting through retries.
+    pub async fn poster(&self, poster_path: &str) -> Result<Vec<u8>> {
+        if self.offline {
+            return Err(anyhow!("Posters are not available offline"));
+        }
+        let response = self
+            .client
+            .get(poster_url(poster_path))
             .send()
             .await?
-            .decode()
-            .await
+            .error_for_status()
+            .context("Failed to download poster")?;
+        Ok(response.bytes().await?.to_vec())
     }
 }
 
@@ -219,6 +923,289 @@ impl TmdbClient {
 mod tests {
     use super::*;
 
+    fn test_client(language: Option<&str>) -> TmdbClient {
+        TmdbClient {
+            client: reqwest::Client::new(),
+            credential: Credential::Token("token".to_string()),
+            base_url: BASE_URL.to_string(),
+            language: None,
+            cache: None,
+            offline: false,
+            seasons: None,
+        }
+        .with_language(language.map(str::to_string))
+    }
+
+    #[test]
+    fn test_request_without_language() {
+        let request = test_client(None).get("/tv/1").build().unwrap();
+        assert_eq!(request.url().as_str(), format!("{}/tv/1", BASE_URL));
+    }
+
+    #[test]
+    fn test_request_with_language() {
+        let request = test_client(Some("de-DE")).get("/tv/1").build().unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            format!("{}/tv/1?language=de-DE", BASE_URL)
+        );
+    }
+
+    #[test]
+    fn test_request_with_api_key() {
+        let key = "0123456789abcdef0123456789abcdef";
+        let client =
+            TmdbClient::connect(&TmdbConfig::default(), Credential::detect(key.to_string()))
+                .unwrap();
+        let request = client.get("/tv/1").build().unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            format!("{}/tv/1?api_key={}", BASE_URL, key)
+        );
+        assert!(request.headers().get("authorization").is_none());
+        assert_eq!(log_url(request.url()), format!("{}/tv/1", BASE_URL));
+
+        let client = test_client(Some("de-DE"));
+        let request = client.get("/tv/1").build().unwrap();
+        assert!(request.headers().get("authorization").is_some());
+        assert_eq!(
+            Credential::detect("eyJhbGciOiJIUzI1NiJ9".to_string()),
+            Credential::Token("eyJhbGciOiJIUzI1NiJ9".to_string())
+        );
+    }
+
+    #[test]
+    fn test_connect_settings() {
+        let settings = TmdbConfig {
+            base_url: Some("http://localhost:8080/3/".to_string()),
+            proxy: Some("http://proxy.example:3128".to_string()),
+            timeout: Some("30s".to_string()),
+        };
+        let client =
+            TmdbClient::connect(&settings, Credential::Token("token".to_string())).unwrap();
+        let request = client.get("/tv/1").build().unwrap();
+        assert_eq!(request.url().as_str(), "http://localhost:8080/3/tv/1");
+
+        let settings = TmdbConfig {
+            timeout: Some("soon".to_string()),
+            ..Default::default()
+        };
+        assert!(TmdbClient::connect(&settings, Credential::Token("token".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_offline_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let client = TmdbClient {
+            cache: Some(temp_dir.path().to_path_buf()),
+            offline: true,
+            ..test_client(None)
+        };
+        let err = client.movie(949).await.unwrap_err();
+        assert!(err.to_string().contains("/movie/949"));
+        assert!(err.to_string().contains("not cached"));
+
+        let url = client.get("/movie/949").build().unwrap().url().clone();
+        fs::write(
+            cache_path(temp_dir.path(), &url),
+            r#"{"id": 949, "title": "Heat", "overview": "", "release_date": "1995-12-15",
+                "original_language": "en", "popularity": 1.0, "imdb_id": "tt0113277"}"#,
+        )
+        .unwrap();
+        assert_eq!(client.movie(949).await.unwrap().title, "Heat");
+        assert!(client.series(949).await.is_err());
+        assert!(client.poster("/heat.jpg").await.is_err());
+    }
+
+    #[test]
+    fn test_error_from_response() {
+        let not_found = TmdbError::from_response(
+            StatusCode::NOT_FOUND,
+            "/tv/99999999",
+            r#"{"success":false,"status_code":34,"status_message":"The resource you requested could not be found."}"#,
+        );
+        assert_eq!(
+            not_found.to_string(),
+            "TMDB has nothing at /tv/99999999 (The resource you requested could not be found); check that the ID is right"
+        );
+        let unauthorized = TmdbError::from_response(
+            StatusCode::UNAUTHORIZED,
+            "/movie/949",
+            r#"{"status_code":7,"status_message":"Invalid API key: You must be granted a valid key."}"#,
+        );
+        assert!(matches!(unauthorized, TmdbError::Unauthorized { .. }));
+        assert!(unauthorized.to_string().contains("mediar auth login"));
+        assert!(matches!(
+            TmdbError::from_response(StatusCode::TOO_MANY_REQUESTS, "/search/tv", ""),
+            TmdbError::RateLimited { .. }
+        ));
+        assert_eq!(
+            TmdbError::from_response(StatusCode::BAD_GATEWAY, "/tv/1", "<html>"),
+            TmdbError::Other {
+                status: 502,
+                message: "Bad Gateway".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_season_numbers() {
+        let series: Tv = serde_json::from_str(
+            r#"{"id": 1, "name": "Show", "overview": "", "first_air_date": "2024-01-01",
+                "number_of_episodes": 12, "number_of_seasons": 3,
+                "seasons": [
+                    {"season_number": 0, "episode_count": 2, "air_date": null},
+                    {"season_number": 1, "episode_count": 10, "air_date": "2024-01-01"},
+                    {"season_number": 3, "episode_count": 2, "air_date": "2025-01-01"},
+                    {"season_number": 4, "episode_count": 0, "air_date": null}
+                ]}"#,
+        )
+        .unwrap();
+        assert_eq!(series.season_numbers(), vec![1, 3]);
+
+        let unlisted = Tv {
+            seasons: Vec::new(),
+            ..series
+        };
+        assert_eq!(unlisted.season_numbers(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_show_with_seasons() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let client = TmdbClient {
+            cache: Some(temp_dir.path().to_path_buf()),
+            offline: true,
+            ..test_client(None)
+        }
+        .with_seasons(Some(BTreeSet::from([2])));
+        let cache = |path: &str, body: &str| {
+            let url = client.get(path).build().unwrap().url().clone();
+            fs::write(cache_path(temp_dir.path(), &url), body).unwrap();
+        };
+        cache(
+            "/tv/1",
+            r#"{"id": 1, "name": "Show", "overview": "", "first_air_date": "2020-01-01",
+                "number_of_episodes": 30, "number_of_seasons": 3}"#,
+        );
+        cache(
+            "/tv/1/season/2",
+            r#"{"id": 12, "season_number": 2, "name": "Season 2", "overview": "",
+                "air_date": null, "episodes": []}"#,
+        );
+
+        // Seasons 1 and 3 are not cached, so fetching them would fail
+        let show = client.show(1).await.unwrap();
+        assert_eq!(show.seasons.len(), 1);
+        assert_eq!(show.seasons[0].season_number, 2);
+        assert_eq!(show.number_of_seasons, 3);
+    }
+
+    #[test]
+    fn test_poster_url() {
+        assert_eq!(
+            poster_url("/ggFHVNu6YYI5L9pCfOacjizRGt.jpg"),
+            "https://image.tmdb.org/t/p/w185/ggFHVNu6YYI5L9pCfOacjizRGt.jpg"
+        );
+    }
+
+    #[test]
+    fn test_search_with_year() {
+        let request = test_client(None)
+            .search(
+                "/search/tv",
+                "The Office",
+                "first_air_date_year",
+                Some(2005),
+                1,
+            )
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            format!(
+                "{}/search/tv?query=The+Office&first_air_date_year=2005",
+                BASE_URL
+            )
+        );
+
+        let request = test_client(None)
+            .search("/search/movie", "Heat", "year", None, 1)
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            format!("{}/search/movie?query=Heat", BASE_URL)
+        );
+
+        let request = test_client(None)
+            .search("/search/movie", "Heat", "year", None, 3)
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            format!("{}/search/movie?query=Heat&page=3", BASE_URL)
+        );
+    }
+
+    #[test]
+    fn test_multi_search_result() {
+        let results: Vec<MultiSearchResult> = serde_json::from_str(
+            r#"[
+                {"media_type": "tv", "id": 1438, "name": "The Wire", "overview": "",
+                 "first_air_date": "2002-06-02", "popularity": 80.5},
+                {"media_type": "person", "id": 17419, "name": "Bryan Cranston"},
+                {"media_type": "movie", "id": 949, "title": "Heat", "overview": "",
+                 "release_date": "1995-12-15"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(results[0].name(), "The Wire");
+        assert_eq!(results[0].year(), Some(2002));
+        assert_eq!(results[1], MultiSearchResult::Other);
+        assert_eq!(results[2].id(), 949);
+        assert_eq!(results[2].popularity(), 0.0);
+
+        let (tv, movies) = SearchResponse {
+            page: 1,
+            results,
+            total_pages: 1,
+            total_results: 3,
+        }
+        .split();
+        assert_eq!(tv.total_results, 1);
+        assert_eq!(movies.results[0].title, "Heat");
+    }
+
+    #[test]
+    fn test_year_of() {
+        assert_eq!(year_of("2008-01-20"), Some(2008));
+        assert_eq!(year_of(""), None);
+    }
+
+    #[test]
+    fn test_is_transient() {
+        assert!(is_transient(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_transient(StatusCode::BAD_GATEWAY));
+        assert!(is_transient(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient(StatusCode::OK));
+        assert!(!is_transient(StatusCode::NOT_FOUND));
+        assert!(!is_transient(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn test_is_unavailable() {
+        let error = |status, body| {
+            anyhow::Error::new(TmdbError::from_response(status, "/tv/1396", body))
+                .context("Failed to get show")
+        };
+        assert!(is_unavailable(&error(StatusCode::SERVICE_UNAVAILABLE, "")));
+        assert!(is_unavailable(&error(StatusCode::TOO_MANY_REQUESTS, "")));
+        assert!(!is_unavailable(&error(StatusCode::NOT_FOUND, "")));
+        assert!(!is_unavailable(&error(StatusCode::UNAUTHORIZED, "")));
+        assert!(!is_unavailable(&anyhow!("No video files found")));
+    }
+
     #[test]
     fn test_episode_id_generation() {
         let episode_id = episode_id(1, 5);
@@ -242,6 +1229,7 @@ mod tests {
         let show = Show {
             id: 1,
             name: "Test Show".to_string(),
+            original_name: None,
             overview: "A test show".to_string(),
             year: 2020,
             first_air_date: "2020-01-01".to_string(),
@@ -252,6 +1240,7 @@ mod tests {
                 season_number: 1,
                 name: "Season 1".to_string(),
                 overview: "First season".to_string(),
+                air_date: None,
                 episodes: vec![
                     TvSeasonEpisode {
                         id: 1,
@@ -259,6 +1248,7 @@ mod tests {
                         episode_number: 1,
                         name: "Pilot".to_string(),
                         overview: "First episode".to_string(),
+                        air_date: None,
                     },
                     TvSeasonEpisode {
                         id: 2,
@@ -266,6 +1256,7 @@ mod tests {
                         episode_number: 2,
                         name: "Second Episode".to_string(),
                         overview: "Second episode".to_string(),
+                        air_date: None,
                     },
                 ],
             }],
@@ -284,6 +1275,7 @@ mod tests {
         let show = Show {
             id: 1,
             name: "Test Show".to_string(),
+            original_name: None,
             overview: "A test show".to_string(),
             year: 2020,
             first_air_date: "2020-01-01".to_string(),
@@ -295,12 +1287,14 @@ mod tests {
                     season_number: 1,
                     name: "Season 1".to_string(),
                     overview: "First season".to_string(),
+                    air_date: None,
                     episodes: vec![TvSeasonEpisode {
                         id: 1,
                         season_number: 1,
                         episode_number: 1,
                         name: "Pilot".to_string(),
                         overview: "First episode".to_string(),
+                        air_date: None,
                     }],
                 },
                 TvSeason {
@@ -308,6 +1302,7 @@ mod tests {
                     season_number: 2,
                     name: "Season 2".to_string(),
                     overview: "Second season".to_string(),
+                    air_date: None,
                     episodes: vec![
                         TvSeasonEpisode {
                             id: 2,
@@ -315,6 +1310,7 @@ mod tests {
                             episode_number: 1,
                             name: "Season 2 Premiere".to_string(),
                             overview: "First episode of season 2".to_string(),
+                            air_date: None,
                         },
                         TvSeasonEpisode {
                             id: 3,
@@ -322,6 +1318,7 @@ mod tests {
                             episode_number: 2,
                             name: "Episode 2".to_string(),
                             overview: "Second episode of season 2".to_string(),
+                            air_date: None,
                         },
                     ],
                 },
@@ -335,11 +1332,51 @@ mod tests {
         assert!(episodes.contains_key("S02E02"));
     }
 
+    #[test]
+    fn test_show_reorder() {
+        let details: EpisodeGroupDetails = serde_json::from_str(
+            r#"{
+                "id": "5b11ba820e0a265847002c6e",
+                "name": "DVD Order",
+                "groups": [{
+                    "id": "5b11ba9b0e0a26584a002c1e",
+                    "name": "Season 1",
+                    "order": 1,
+                    "episodes": [
+                        {"id": 11, "season_number": 1, "episode_number": 2, "name": "Second Aired",
+                         "overview": "", "air_date": "2002-09-27", "order": 0},
+                        {"id": 10, "season_number": 1, "episode_number": 1, "name": "Pilot",
+                         "overview": "", "air_date": "2002-09-20", "order": 1}
+                    ]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let mut show = Show {
+            id: 1437,
+            name: "Firefly".to_string(),
+            original_name: None,
+            overview: String::new(),
+            year: 2002,
+            first_air_date: "2002-09-20".to_string(),
+            number_of_episodes: 2,
+            number_of_seasons: 1,
+            seasons: vec![],
+        };
+
+        show.reorder(details);
+        let episodes = show.episodes();
+        assert_eq!(episodes["S01E01"].name, "Second Aired");
+        assert_eq!(episodes["S01E02"].name, "Pilot");
+        assert_eq!(show.seasons[0].air_date.as_deref(), Some("2002-09-27"));
+    }
+
     #[test]
     fn test_show_episodes_empty() {
         let show = Show {
             id: 1,
             name: "Test Show".to_string(),
+            original_name: None,
             overview: "A test show".to_string(),
             year: 2020,
             first_air_date: "2020-01-01".to_string(),
@@ -0,0 +1,310 @@
+use anyhow::{Context, Result, anyhow};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{interrupt, metrics};
+
+/// Suffix of the partial file written next to a copy's destination until it completes
+pub const PARTIAL_SUFFIX: &str = ".mediar-partial";
+
+/// Bytes copied between flushes, so an interrupted copy loses at most one chunk
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Bytes compared on each side of a checkpoint when verifying a finished copy
+const VERIFY_WINDOW: u64 = 1024 * 1024;
+
+/// Parse a transfer rate such as `50MB/s`, `800K` or `1.5GiB/s` into bytes per second
+///
+/// `K`, `M` and `G` are decimal (1000-based) and `Ki`, `Mi` and `Gi` binary (1024-based).
+pub fn parse_rate(rate: &str) -> Result<u64> {
+    let trimmed = rate.trim();
+    let trimmed = trimmed.strip_suffix("/s").unwrap_or(trimmed);
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow!("Invalid rate {:?}, expected e.g. 50MB/s", rate))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().trim_end_matches('b') {
+        "" => 1,
+        "k" => 1_000,
+        "m" => 1_000_000,
+        "g" => 1_000_000_000,
+        "ki" => 1 << 10,
+        "mi" => 1 << 20,
+        "gi" => 1 << 30,
+        _ => {
+            return Err(anyhow!(
+                "Invalid rate unit in {:?}, expected e.g. 50MB/s",
+                rate
+            ));
+        }
+    };
+    let bytes = (number * multiplier as f64) as u64;
+    if bytes == 0 {
+        return Err(anyhow!("Rate must be greater than zero"));
+    }
+    Ok(bytes)
+}
+
+/// Where a copy is written until it completes, e.g. `Movie (1999).mkv.mediar-partial`
+pub fn partial_path(new: &Path) -> PathBuf {
+    let mut name = new.file_name().unwrap_or_default().to_os_string();
+    name.push(PARTIAL_SUFFIX);
+    new.with_file_name(name)
+}
+
+/// Read up to `len` bytes starting at `offset`
+fn read_window(file: &mut File, offset: u64, len: u64) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    file.seek(SeekFrom::Start(offset))?;
+    file.take(len).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Check a finished copy's size and the bytes around the resume point and at the end
+fn verify(old: &Path, partial: &Path, resumed_at: u64) -> Result<()> {
+    let mut source = File::open(old)?;
+    let mut copy = File::open(partial)?;
+
+    let expected = source.metadata()?.len();
+    let actual = copy.metadata()?.len();
+    if expected != actual {
+        return Err(anyhow!(
+            "Verification failed for {}: expected {} bytes, found {}",
+            partial.display(),
+            expected,
+            actual
+        ));
+    }
+
+    for checkpoint in [resumed_at, expected] {
+        let offset = checkpoint.saturating_sub(VERIFY_WINDOW);
+        if read_window(&mut source, offset, 2 * VERIFY_WINDOW)?
+            != read_window(&mut copy, offset, 2 * VERIFY_WINDOW)?
+        {
+            return Err(anyhow!(
+                "Verification failed for {}: contents differ near byte {}",
+                partial.display(),
+                checkpoint
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Copy a file through a `.mediar-partial` file, resuming from whatever an earlier attempt left behind
+///
+/// With a `rate_limit` in bytes per second, pauses between chunks to stay below it. Returns
+/// the number of bytes that were already present and did not need copying again.
+pub fn resumable_copy(old: &Path, new: &Path, rate_limit: Option<u64>) -> Result<u64> {
+    let partial = partial_path(new);
+    let total = fs::metadata(old)
+        .with_context(|| format!("Failed to read {}", old.display()))?
+        .len();
+
+    let mut resumed_at = fs::metadata(&partial).map(|meta| meta.len()).unwrap_or(0);
+    if resumed_at > total {
+        // Left over from a different source file; start again
+        resumed_at = 0;
+    }
+
+    let mut source = File::open(old)?;
+    source.seek(SeekFrom::Start(resumed_at))?;
+    let mut copy = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&partial)
+        .with_context(|| format!("Failed to open {}", partial.display()))?;
+    copy.set_len(resumed_at)?;
+    copy.seek(SeekFrom::Start(resumed_at))?;
+
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let started = Instant::now();
+    let mut copied = 0;
+    loop {
+        // Stop between chunks on Ctrl-C; the partial file is picked up by the next attempt
+        interrupt::check()?;
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        copy.write_all(&buffer[..read])?;
+        copy.flush()?;
+
+        copied += read as u64;
+        if let Some(limit) = rate_limit {
+            let due = Duration::from_secs_f64(copied as f64 / limit as f64);
+            if let Some(wait) = due.checked_sub(started.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+    }
+    copy.sync_all()?;
+    drop(copy);
+
+    verify(old, &partial, resumed_at)?;
+    fs::rename(&partial, new)
+        .with_context(|| format!("Failed to rename {} into place", partial.display()))?;
+    metrics::bytes_copied(copied);
+    Ok(resumed_at)
+}
+
+/// What to do when the filesystem cannot clone a file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ReflinkFallback {
+    /// Fail the operation
+    #[default]
+    Fail,
+    /// Make a regular copy instead
+    Copy,
+}
+
+/// Clone a file copy-on-write (`FICLONE` on Linux, `clonefile` on macOS), sharing its blocks
+///
+/// Like copies, the clone is made next to the destination and renamed into place.
+pub fn reflink(
+    old: &Path,
+    new: &Path,
+    fallback: ReflinkFallback,
+    rate_limit: Option<u64>,
+) -> Result<()> {
+    let partial = partial_path(new);
+    let copy_instead = fallback == ReflinkFallback::Copy;
+    if copy_instead && partial.exists() {
+        // An earlier fallback copy was interrupted; pick it up where it stopped
+        return resumable_copy(old, new, rate_limit).map(|_| ());
+    }
+
+    let _ = fs::remove_file(&partial);
+    match reflink_copy::reflink(old, &partial) {
+        Ok(()) => fs::rename(&partial, new)
+            .with_context(|| format!("Failed to rename {} into place", partial.display())),
+        Err(_) if copy_instead => {
+            let _ = fs::remove_file(&partial);
+            resumable_copy(old, new, rate_limit).map(|_| ())
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&partial);
+            Err(anyhow!(
+                "Failed to clone {} to {} (does the filesystem support reflinks?): {}",
+                old.display(),
+                new.display(),
+                err
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn contents(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_partial_path() {
+        assert_eq!(
+            partial_path(Path::new("/lib/Movie (1999).mkv")),
+            Path::new("/lib/Movie (1999).mkv.mediar-partial")
+        );
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("50MB/s").unwrap(), 50_000_000);
+        assert_eq!(parse_rate("800k").unwrap(), 800_000);
+        assert_eq!(parse_rate("1.5GiB/s").unwrap(), 1_610_612_736);
+        assert_eq!(parse_rate("4096").unwrap(), 4096);
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("10 parsecs").is_err());
+        assert!(parse_rate("0MB/s").is_err());
+    }
+
+    #[test]
+    fn test_resumable_copy_rate_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = temp_dir.path().join("source.mkv");
+        let new = temp_dir.path().join("target.mkv");
+        fs::write(&old, contents(10_000)).unwrap();
+
+        let started = Instant::now();
+        resumable_copy(&old, &new, Some(50_000)).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(190));
+        assert_eq!(fs::read(&new).unwrap(), contents(10_000));
+    }
+
+    #[test]
+    fn test_resumable_copy_fresh() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = temp_dir.path().join("source.mkv");
+        let new = temp_dir.path().join("target.mkv");
+        fs::write(&old, contents(10_000)).unwrap();
+
+        assert_eq!(resumable_copy(&old, &new, None).unwrap(), 0);
+        assert_eq!(fs::read(&new).unwrap(), contents(10_000));
+        assert!(!partial_path(&new).exists());
+    }
+
+    #[test]
+    fn test_resumable_copy_resumes_partial() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = temp_dir.path().join("source.mkv");
+        let new = temp_dir.path().join("target.mkv");
+        fs::write(&old, contents(10_000)).unwrap();
+        fs::write(partial_path(&new), &contents(10_000)[..4_000]).unwrap();
+
+        assert_eq!(resumable_copy(&old, &new, None).unwrap(), 4_000);
+        assert_eq!(fs::read(&new).unwrap(), contents(10_000));
+    }
+
+    #[test]
+    fn test_resumable_copy_restarts_oversized_partial() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = temp_dir.path().join("source.mkv");
+        let new = temp_dir.path().join("target.mkv");
+        fs::write(&old, contents(1_000)).unwrap();
+        fs::write(partial_path(&new), contents(5_000)).unwrap();
+
+        assert_eq!(resumable_copy(&old, &new, None).unwrap(), 0);
+        assert_eq!(fs::read(&new).unwrap(), contents(1_000));
+    }
+
+    #[test]
+    fn test_resumable_copy_detects_corrupt_partial() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = temp_dir.path().join("source.mkv");
+        let new = temp_dir.path().join("target.mkv");
+        fs::write(&old, contents(10_000)).unwrap();
+        fs::write(partial_path(&new), vec![0xFF; 4_000]).unwrap();
+
+        assert!(resumable_copy(&old, &new, None).is_err());
+        assert!(!new.exists());
+    }
+
+    #[test]
+    fn test_reflink_fallback_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = temp_dir.path().join("source.mkv");
+        let new = temp_dir.path().join("target.mkv");
+        fs::write(&old, contents(10_000)).unwrap();
+
+        // Whether or not the temp filesystem can clone, the fallback produces the file
+        reflink(&old, &new, ReflinkFallback::Copy, None).unwrap();
+        assert_eq!(fs::read(&new).unwrap(), contents(10_000));
+        assert!(!partial_path(&new).exists());
+    }
+}
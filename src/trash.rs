@@ -0,0 +1,182 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::NaiveDate;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::transfer::resumable_copy;
+
+/// Staging folder, inside the source directory, for sources of moved files
+pub const TRASH_DIR: &str = ".mediar-trash";
+
+/// Default number of days staged sources are kept before they are deleted
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+/// The folder a single-file source is in, where its trash is kept instead
+fn file_source_dir(source: &Path) -> &Path {
+    source.parent().unwrap_or(source)
+}
+
+/// Where a source file is staged: `<source>/.mediar-trash/<date>/<relative path>`
+///
+/// A source that is a single file is staged in the trash of the folder it is in.
+pub fn trash_path(source: &Path, old: &Path, date: NaiveDate) -> PathBuf {
+    let base = if old == source {
+        file_source_dir(source)
+    } else {
+        source
+    };
+    let relative = old.strip_prefix(base).unwrap_or(old);
+    base.join(TRASH_DIR)
+        .join(date.format("%Y-%m-%d").to_string())
+        .join(relative)
+}
+
+/// Move a file by placing it at `new` first and then staging the source in the trash
+///
+/// The file is hard-linked where possible and copied otherwise, and only staged once the
+/// new file is verified to have the same size.
+pub fn stage_move(
+    source: &Path,
+    old: &Path,
+    new: &Path,
+    date: NaiveDate,
+    rate_limit: Option<u64>,
+) -> Result<()> {
+    if fs::hard_link(old, new).is_err() {
+        resumable_copy(old, new, rate_limit)
+            .with_context(|| format!("Failed to copy {} to {}", old.display(), new.display()))?;
+    }
+
+    let expected = fs::metadata(old)?.len();
+    let actual = fs::metadata(new)?.len();
+    if expected != actual {
+        return Err(anyhow!(
+            "Verification failed for {}: expected {} bytes, found {}",
+            new.display(),
+            expected,
+            actual
+        ));
+    }
+
+    let trashed = trash_path(source, old, date);
+    fs::create_dir_all(trashed.parent().context("Failed to get parent")?)?;
+    fs::rename(old, &trashed)
+        .with_context(|| format!("Failed to stage {} in the trash", old.display()))?;
+    Ok(())
+}
+
+/// Delete dated trash folders older than the retention window, returning what was removed
+pub fn prune(source: &Path, retention_days: u32, today: NaiveDate) -> Result<Vec<PathBuf>> {
+    // A single-file source is gone once moved, and never a directory
+    let base = if source.is_dir() {
+        source
+    } else {
+        file_source_dir(source)
+    };
+    let trash = base.join(TRASH_DIR);
+    if !trash.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut removed = Vec::new();
+    for entry in fs::read_dir(&trash)? {
+        let entry = entry?;
+        let Some(date) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| NaiveDate::parse_from_str(name, "%Y-%m-%d").ok())
+        else {
+            continue;
+        };
+
+        if (today - date).num_days() > i64::from(retention_days) {
+            fs::remove_dir_all(entry.path())?;
+            removed.push(entry.path());
+        }
+    }
+    removed.sort();
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_trash_path() {
+        assert_eq!(
+            trash_path(
+                Path::new("/downloads"),
+                Path::new("/downloads/Show/e01.mkv"),
+                date("2024-03-05")
+            ),
+            Path::new("/downloads/.mediar-trash/2024-03-05/Show/e01.mkv")
+        );
+    }
+
+    #[test]
+    fn test_stage_move_file_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = temp_dir.path().join("Heat.1995.mkv");
+        let new = temp_dir.path().join("Heat (1995).mkv");
+        fs::write(&old, "video").unwrap();
+
+        stage_move(&old, &old, &new, date("2024-03-05"), None).unwrap();
+
+        let staged = temp_dir
+            .path()
+            .join(TRASH_DIR)
+            .join("2024-03-05")
+            .join("Heat.1995.mkv");
+        assert!(staged.exists());
+        assert_eq!(
+            prune(&old, 0, date("2024-03-07")).unwrap(),
+            vec![temp_dir.path().join(TRASH_DIR).join("2024-03-05")]
+        );
+    }
+
+    #[test]
+    fn test_stage_move() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("source");
+        let old = source.join("Show").join("e01.mkv");
+        let new = temp_dir.path().join("e01.mkv");
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::write(&old, "video").unwrap();
+
+        stage_move(&source, &old, &new, date("2024-03-05"), None).unwrap();
+
+        assert!(!old.exists());
+        assert_eq!(fs::read_to_string(&new).unwrap(), "video");
+        assert!(
+            source
+                .join(TRASH_DIR)
+                .join("2024-03-05")
+                .join("Show")
+                .join("e01.mkv")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_prune() {
+        let temp_dir = TempDir::new().unwrap();
+        let trash = temp_dir.path().join(TRASH_DIR);
+        for name in ["2024-01-01", "2024-02-20", "notes"] {
+            fs::create_dir_all(trash.join(name)).unwrap();
+        }
+
+        let removed = prune(temp_dir.path(), 30, date("2024-03-01")).unwrap();
+
+        assert_eq!(removed, vec![trash.join("2024-01-01")]);
+        assert!(trash.join("2024-02-20").exists());
+        assert!(trash.join("notes").exists());
+    }
+}
@@ -0,0 +1,206 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    plan::{Plan, SkipReason},
+    video::{Resolution, detect_resolution, is_sidecar},
+};
+
+/// What to do with a source whose target already exists and is worse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UpgradePolicy {
+    /// Overwrite the existing file
+    Replace,
+    /// Keep both, naming the new file after its resolution, e.g. `Movie (1999) - 2160p.mkv`
+    Version,
+}
+
+/// What a file is compared on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quality {
+    pub resolution: Option<Resolution>,
+    pub size: u64,
+}
+
+impl Quality {
+    pub fn of(path: &Path) -> Result<Self> {
+        Ok(Self {
+            resolution: detect_resolution(path),
+            size: fs::metadata(path)?.len(),
+        })
+    }
+
+    /// Higher resolution wins; with the same or an unknown resolution, the bigger file does
+    pub fn is_better_than(&self, other: &Quality) -> bool {
+        match (self.resolution, other.resolution) {
+            (Some(new), Some(existing)) if new != existing => new > existing,
+            _ => self.size > other.size,
+        }
+    }
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.resolution {
+            Some(resolution) => write!(f, "{}, {} bytes", resolution, self.size),
+            None => write!(f, "{} bytes", self.size),
+        }
+    }
+}
+
+/// A source that will replace or be added next to an existing file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Upgrade {
+    pub source: PathBuf,
+    pub existing: PathBuf,
+    pub target: PathBuf,
+    pub from: Quality,
+    pub to: Quality,
+}
+
+/// `Movie (1999).mkv` as `Movie (1999) - 2160p.mkv`
+fn versioned_path(target: &Path, resolution: Option<Resolution>, size: u64) -> PathBuf {
+    let label = resolution.map_or_else(|| size.to_string(), |resolution| resolution.to_string());
+    let stem = target.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match target.extension() {
+        Some(ext) => format!("{} - {}.{}", stem, label, ext.to_string_lossy()),
+        None => format!("{} - {}", stem, label),
+    };
+    target.with_file_name(name)
+}
+
+/// Turn skips of existing video targets into operations where the source is better
+///
+/// Replaced targets are recorded in `plan.replaced`, to be set aside just before they are
+/// overwritten and removed once the run is over. Sources that are not better stay skipped.
+pub fn apply(plan: &mut Plan, policy: UpgradePolicy) -> Result<Vec<Upgrade>> {
+    let mut upgrades = Vec::new();
+    let mut skipped = Vec::new();
+    for (old, reason) in std::mem::take(&mut plan.skipped) {
+        let SkipReason::Exists(existing) = &reason else {
+            skipped.push((old, reason));
+            continue;
+        };
+        // Subtitles and audio tracks have no quality to compare
+        if is_sidecar(&old) || !existing.is_file() {
+            skipped.push((old, reason));
+            continue;
+        }
+
+        let from = Quality::of(existing)?;
+        let to = Quality::of(&old)?;
+        if !to.is_better_than(&from) {
+            skipped.push((
+                old,
+                SkipReason::Ignored("the existing file is the same or better quality"),
+            ));
+            continue;
+        }
+
+        let target = match policy {
+            UpgradePolicy::Replace => existing.clone(),
+            UpgradePolicy::Version => versioned_path(existing, to.resolution, to.size),
+        };
+        if policy == UpgradePolicy::Version && target.exists() {
+            skipped.push((old, SkipReason::Exists(target)));
+            continue;
+        }
+        if policy == UpgradePolicy::Replace {
+            plan.replaced.insert(target.clone());
+        }
+        plan.operations.push((old.clone(), target.clone()));
+        upgrades.push(Upgrade {
+            source: old,
+            existing: existing.clone(),
+            target,
+            from,
+            to,
+        });
+    }
+    plan.skipped = skipped;
+    Ok(upgrades)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn quality(resolution: Option<Resolution>, size: u64) -> Quality {
+        Quality { resolution, size }
+    }
+
+    #[test]
+    fn test_is_better_than() {
+        let uhd = quality(Some(Resolution::Uhd), 10);
+        let hd = quality(Some(Resolution::Hd1080), 100);
+        assert!(uhd.is_better_than(&hd));
+        assert!(!hd.is_better_than(&uhd));
+        assert!(quality(None, 200).is_better_than(&hd));
+        assert!(!quality(Some(Resolution::Hd1080), 100).is_better_than(&hd));
+    }
+
+    #[test]
+    fn test_versioned_path() {
+        assert_eq!(
+            versioned_path(
+                Path::new("/lib/Movie (1999)/Movie (1999).mkv"),
+                Some(Resolution::Uhd),
+                1
+            ),
+            Path::new("/lib/Movie (1999)/Movie (1999) - 2160p.mkv")
+        );
+    }
+
+    fn plan_with_existing(temp_dir: &TempDir, source: &str, size: usize) -> Plan {
+        let old = temp_dir.path().join(source);
+        let existing = temp_dir.path().join("Movie (1999) 1080p.mkv");
+        fs::write(&old, vec![0; size]).unwrap();
+        fs::write(&existing, vec![0; 10]).unwrap();
+        let mut plan = Plan::default();
+        plan.skipped.push((old, SkipReason::Exists(existing)));
+        plan
+    }
+
+    #[test]
+    fn test_apply_replace() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut plan = plan_with_existing(&temp_dir, "Movie.1999.2160p.mkv", 5);
+
+        let upgrades = apply(&mut plan, UpgradePolicy::Replace).unwrap();
+        assert_eq!(upgrades.len(), 1);
+        assert!(plan.skipped.is_empty());
+        assert_eq!(plan.operations[0].1, upgrades[0].existing);
+        assert!(plan.replaced.contains(&upgrades[0].existing));
+    }
+
+    #[test]
+    fn test_apply_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut plan = plan_with_existing(&temp_dir, "Movie.1999.2160p.mkv", 5);
+
+        apply(&mut plan, UpgradePolicy::Version).unwrap();
+        assert_eq!(
+            plan.operations[0].1,
+            temp_dir.path().join("Movie (1999) 1080p - 2160p.mkv")
+        );
+        assert!(plan.replaced.is_empty());
+    }
+
+    #[test]
+    fn test_apply_keeps_better_existing() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut plan = plan_with_existing(&temp_dir, "Movie.1999.720p.mkv", 50);
+
+        assert!(apply(&mut plan, UpgradePolicy::Replace).unwrap().is_empty());
+        assert!(plan.operations.is_empty());
+        assert_eq!(
+            plan.skipped[0].1,
+            SkipReason::Ignored("the existing file is the same or better quality")
+        );
+    }
+}
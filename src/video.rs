@@ -1,9 +1,16 @@
 use anyhow::{Context, Result};
 use core::fmt;
 use regex::Regex;
-use std::{collections::HashSet, path::Path};
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ContentType {
     Show,
     Movie,
@@ -24,6 +31,101 @@ impl fmt::Display for ContentType {
     }
 }
 
+/// Kinds of bonus content, named after the Plex/Jellyfin extras folders
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraKind {
+    BehindTheScenes,
+    DeletedScenes,
+    Featurettes,
+    Interviews,
+    Scenes,
+    Shorts,
+    Trailers,
+    Other,
+}
+
+impl ExtraKind {
+    /// Folder name used for this kind of extra inside the title directory
+    pub fn folder(&self) -> &'static str {
+        match self {
+            ExtraKind::BehindTheScenes => "Behind The Scenes",
+            ExtraKind::DeletedScenes => "Deleted Scenes",
+            ExtraKind::Featurettes => "Featurettes",
+            ExtraKind::Interviews => "Interviews",
+            ExtraKind::Scenes => "Scenes",
+            ExtraKind::Shorts => "Shorts",
+            ExtraKind::Trailers => "Trailers",
+            ExtraKind::Other => "Other",
+        }
+    }
+
+    fn from_folder(name: &str) -> Option<Self> {
+        let name = name.to_lowercase().replace([' ', '.', '_', '-'], "");
+        match name.as_str() {
+            "behindthescenes" => Some(ExtraKind::BehindTheScenes),
+            "deletedscenes" => Some(ExtraKind::DeletedScenes),
+            "featurettes" | "featurette" => Some(ExtraKind::Featurettes),
+            "interviews" => Some(ExtraKind::Interviews),
+            "scenes" => Some(ExtraKind::Scenes),
+            "shorts" => Some(ExtraKind::Shorts),
+            "trailers" | "trailer" => Some(ExtraKind::Trailers),
+            "extras" | "extra" | "bonus" | "other" => Some(ExtraKind::Other),
+            _ => None,
+        }
+    }
+
+    fn from_suffix(stem: &str) -> Option<Self> {
+        let (_, suffix) = stem.rsplit_once('-')?;
+        match suffix.to_lowercase().as_str() {
+            "behindthescenes" => Some(ExtraKind::BehindTheScenes),
+            "deleted" => Some(ExtraKind::DeletedScenes),
+            "featurette" => Some(ExtraKind::Featurettes),
+            "interview" => Some(ExtraKind::Interviews),
+            "scene" => Some(ExtraKind::Scenes),
+            "short" => Some(ExtraKind::Shorts),
+            "trailer" => Some(ExtraKind::Trailers),
+            "other" => Some(ExtraKind::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Extension passed for files inside a disc structure, which keep their own names
+pub const DISC: &str = "disc";
+
+/// Folders holding a DVD or Blu-ray disc structure
+const DISC_FOLDERS: [&str; 2] = ["VIDEO_TS", "BDMV"];
+
+/// The part of a path from its disc structure folder on, e.g. `BDMV/STREAM/00000.m2ts`
+///
+/// A disc rip is organized as a unit, keeping this layout inside the movie folder.
+pub fn disc_path(path: &Path) -> Option<PathBuf> {
+    let components: Vec<_> = path.components().collect();
+    let start = components.iter().position(|component| {
+        component.as_os_str().to_str().is_some_and(|name| {
+            DISC_FOLDERS
+                .iter()
+                .any(|disc| disc.eq_ignore_ascii_case(name))
+        })
+    })?;
+    // The folder itself is not a file inside the structure
+    (start + 1 < components.len()).then(|| components[start..].iter().collect())
+}
+
+/// Classify bonus content from a path relative to the source directory
+///
+/// Files inside folders such as `Extras/` or `Featurettes/`, or named with a Plex
+/// suffix such as `Making Of-behindthescenes.mkv`, are extras.
+pub fn parse_extra(path: &Path) -> Option<ExtraKind> {
+    let in_folder = path
+        .parent()?
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .find_map(ExtraKind::from_folder);
+
+    in_folder.or_else(|| ExtraKind::from_suffix(path.file_stem()?.to_str()?))
+}
+
 pub fn episode_id(season: i32, episode: i32) -> String {
     format!("S{:02}E{:02}", season, episode)
 }
@@ -31,8 +133,11 @@ pub fn episode_id(season: i32, episode: i32) -> String {
 /// Extract the title from a filename by removing metadata patterns
 /// Returns the cleaned title as a string
 pub fn parse_title(path: &Path) -> Option<String> {
-    let file_name = path.file_stem().and_then(|name| name.to_str())?;
+    title_from(path.file_stem().and_then(|name| name.to_str())?)
+}
 
+/// Extract the title from a file or folder name without extension
+pub fn title_from(file_name: &str) -> Option<String> {
     // Patterns that indicate the start of metadata (case insensitive)
     let metadata_patterns = [
         r"[Ss]\d+",
@@ -70,6 +175,183 @@ pub fn parse_title(path: &Path) -> Option<String> {
     }
 }
 
+/// Movie editions recognized in filenames, paired with their display names
+const EDITIONS: &[(&str, &str)] = &[
+    (r"directors?'?s?[ ._\-]?cut", "Director's Cut"),
+    (r"extended(?:[ ._\-]?(?:cut|edition))?", "Extended"),
+    (r"theatrical(?:[ ._\-]?(?:cut|edition))?", "Theatrical"),
+    (r"ultimate(?:[ ._\-]?(?:cut|edition))?", "Ultimate"),
+    (r"final[ ._\-]?cut", "Final Cut"),
+    (r"special[ ._\-]?edition", "Special Edition"),
+    (r"collectors?'?s?[ ._\-]?edition", "Collector's Edition"),
+    (r"unrated", "Unrated"),
+    (r"uncut", "Uncut"),
+    (r"remastered", "Remastered"),
+    (r"criterion", "Criterion"),
+    (r"imax", "IMAX"),
+];
+
+/// Detect a movie edition (e.g., Director's Cut) from the filename
+pub fn parse_edition(path: &Path) -> Option<&'static str> {
+    let file_name = path.file_stem()?.to_str()?;
+
+    EDITIONS.iter().find_map(|(pattern, edition)| {
+        let re = Regex::new(&format!(r"(?i)(?:^|[^a-z0-9]){}(?:$|[^a-z0-9])", pattern)).ok()?;
+        re.is_match(file_name).then_some(*edition)
+    })
+}
+
+/// Find a multi-part marker (cd1, part2, disc1, ...) in text, normalized to lowercase
+pub fn part_marker(text: &str) -> Option<String> {
+    let re = Regex::new(
+        r"(?i)(?:^|[^a-z0-9])(cd|dvd|disc|disk|part|pt)[ ._\-]?(\d{1,2})(?:$|[^a-z0-9])",
+    )
+    .ok()?;
+    let captures = re.captures(text)?;
+    let number = captures[2].parse::<u32>().ok()?;
+    Some(format!("{}{}", captures[1].to_lowercase(), number))
+}
+
+/// Detect a multi-part marker (e.g., CD1 or Part2) from the filename
+pub fn parse_part(path: &Path) -> Option<String> {
+    part_marker(path.file_stem()?.to_str()?)
+}
+
+/// Whether a path (relative to the source) is a release sample, e.g. `Sample/x.mkv` or `x-sample.mkv`
+pub fn is_sample(path: &Path) -> bool {
+    let in_sample_folder = path.parent().is_some_and(|parent| {
+        parent.iter().any(|component| {
+            component.eq_ignore_ascii_case("sample") || component.eq_ignore_ascii_case("samples")
+        })
+    });
+    let Some(file_name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+        return in_sample_folder;
+    };
+    let Ok(re) = Regex::new(r"(?i)(?:^|[ ._\-])sample$") else {
+        return in_sample_folder;
+    };
+    in_sample_folder || re.is_match(file_name)
+}
+
+/// A provider ID embedded in a file or folder name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalId {
+    Tmdb(i32),
+    Imdb(String),
+}
+
+/// Find a `{tmdb-550}`, `[tmdbid-550]` or `[imdbid-tt0137523]` tag in a name
+pub fn external_id(text: &str) -> Option<ExternalId> {
+    let re = Regex::new(r"(?i)[\[{](tmdb|imdb)(?:id)?[-=](tt\d+|\d+)[\]}]").ok()?;
+    let captures = re.captures(text)?;
+    let id = &captures[2];
+    match captures[1].to_lowercase().as_str() {
+        "tmdb" => id.parse().ok().map(ExternalId::Tmdb),
+        _ if id.starts_with("tt") => Some(ExternalId::Imdb(id.to_string())),
+        _ => None,
+    }
+}
+
+/// Find a provider ID tag in the file name or the nearest folder that has one
+pub fn parse_external_id(path: &Path) -> Option<ExternalId> {
+    path.iter()
+        .rev()
+        .find_map(|component| external_id(component.to_str()?))
+}
+
+/// Extract a release year (1900-2099) from the filename, preferring the last one
+pub fn parse_year(path: &Path) -> Option<i32> {
+    year_from(path.file_stem()?.to_str()?)
+}
+
+/// Extract a release year from a file or folder name without extension
+pub fn year_from(file_name: &str) -> Option<i32> {
+    let re = Regex::new(r"\d+").ok()?;
+    re.find_iter(file_name)
+        .map(|m| m.as_str())
+        .filter(|digits| {
+            digits.len() == 4 && (digits.starts_with("19") || digits.starts_with("20"))
+        })
+        .last()
+        .and_then(|digits| digits.parse().ok())
+}
+
+/// Video resolution class, as release names and media servers label it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum Resolution {
+    #[serde(rename = "480p", alias = "sd")]
+    Sd,
+    #[serde(rename = "720p")]
+    Hd720,
+    #[serde(rename = "1080p")]
+    Hd1080,
+    #[serde(rename = "2160p", alias = "4k", alias = "uhd")]
+    Uhd,
+}
+
+impl Resolution {
+    /// Classify a frame size, allowing for letterboxed frames such as 1920x800
+    pub fn from_dimensions(width: u32, height: u32) -> Self {
+        if width >= 3200 || height >= 1800 {
+            Resolution::Uhd
+        } else if width >= 1800 || height >= 1000 {
+            Resolution::Hd1080
+        } else if width >= 1200 || height >= 700 {
+            Resolution::Hd720
+        } else {
+            Resolution::Sd
+        }
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resolution::Sd => write!(f, "480p"),
+            Resolution::Hd720 => write!(f, "720p"),
+            Resolution::Hd1080 => write!(f, "1080p"),
+            Resolution::Uhd => write!(f, "2160p"),
+        }
+    }
+}
+
+/// Extract the resolution from a release name, e.g. `1080p`, `2160p` or `4K`
+pub fn parse_resolution(path: &Path) -> Option<Resolution> {
+    let re = Regex::new(
+        r"(?i)(?:^|[ ._\-\[(])(2160p|4k|uhd|1080[pi]|720p|576[pi]|480[pi])(?:$|[ ._\-\])])",
+    )
+    .ok()?;
+    let captures = re.captures(path.file_stem()?.to_str()?)?;
+    match captures[1].to_lowercase().as_str() {
+        "2160p" | "4k" | "uhd" => Some(Resolution::Uhd),
+        "1080p" | "1080i" => Some(Resolution::Hd1080),
+        "720p" => Some(Resolution::Hd720),
+        _ => Some(Resolution::Sd),
+    }
+}
+
+/// Read the resolution of a file's first video stream with `ffprobe`, if it is installed
+pub fn probe_resolution(path: &Path) -> Option<Resolution> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (width, height) = stdout.trim().split_once(',')?;
+    Some(Resolution::from_dimensions(
+        width.parse().ok()?,
+        height.parse().ok()?,
+    ))
+}
+
+/// Resolution from the file name, or failing that from probing the file
+pub fn detect_resolution(path: &Path) -> Option<Resolution> {
+    parse_resolution(path).or_else(|| probe_resolution(path))
+}
+
 pub fn parse_content_type(path: &Path) -> ContentType {
     if parse_episode_id(path).is_ok() {
         ContentType::Show
@@ -78,6 +360,82 @@ pub fn parse_content_type(path: &Path) -> ContentType {
     }
 }
 
+/// Language codes recognized in sidecar names, ISO 639-1 and 639-2 (both B and T forms)
+const LANGUAGE_CODES: [&str; 86] = [
+    "ar", "ara", "bg", "bul", "ca", "cat", "cs", "ces", "cze", "da", "dan", "de", "deu", "ger",
+    "el", "ell", "gre", "en", "eng", "es", "spa", "et", "est", "fa", "fas", "per", "fi", "fin",
+    "fr", "fra", "fre", "he", "heb", "hi", "hin", "hr", "hrv", "hu", "hun", "id", "ind", "it",
+    "ita", "ja", "jpn", "ko", "kor", "lt", "lit", "lv", "lav", "nl", "nld", "dut", "no", "nor",
+    "pl", "pol", "pt", "por", "ro", "ron", "rum", "ru", "rus", "sk", "slk", "slo", "sl", "slv",
+    "sr", "srp", "sv", "swe", "th", "tha", "tr", "tur", "uk", "ukr", "vi", "vie", "zh", "zho",
+    "chi", "und",
+];
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            extensions
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Whether a file is a subtitle: `.srt`, or either half of a VobSub `.sub`/`.idx` pair
+pub fn is_subtitle(path: &Path) -> bool {
+    has_extension(path, &["srt", "sub", "idx"])
+}
+
+/// Whether a file is an external audio track, such as `Show.S01E01.rus.mka`
+pub fn is_external_audio(path: &Path) -> bool {
+    has_extension(path, &["mka"])
+}
+
+/// Whether a file accompanies a video rather than being one: a subtitle or an audio track
+pub fn is_sidecar(path: &Path) -> bool {
+    is_subtitle(path) || is_external_audio(path)
+}
+
+/// The video a sidecar belongs to: the one in its folder whose name begins the sidecar's,
+/// such as `Heat.1995.1080p.mkv` for `Heat.1995.1080p.en.srt`, or else the only video there
+pub fn sidecar_video(path: &Path) -> Option<PathBuf> {
+    let stem = path.file_stem()?.to_str()?;
+    let videos: Vec<PathBuf> = fs::read_dir(path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|video| video.is_file() && !is_sidecar(video) && parse_extension(video).is_some())
+        .collect();
+    let named = videos
+        .iter()
+        .filter_map(|video| Some((video, video.file_stem()?.to_str()?)))
+        .filter(|(_, video_stem)| stem.starts_with(video_stem))
+        .max_by_key(|(_, video_stem)| video_stem.len())
+        .map(|(video, _)| video.clone());
+    named.or_else(|| match videos.as_slice() {
+        [video] => Some(video.clone()),
+        _ => None,
+    })
+}
+
+/// The language tag just before a sidecar's extension, e.g. `rus` in `Show.S01E01.rus.mka`
+pub fn parse_language_tag(path: &Path) -> Option<String> {
+    if !is_sidecar(path) {
+        return None;
+    }
+    let stem = path.file_stem()?.to_str()?;
+    let (_, tag) = stem.rsplit_once('.')?;
+    let tag = tag.to_ascii_lowercase();
+    LANGUAGE_CODES.contains(&tag.as_str()).then_some(tag)
+}
+
+/// The extension for a renamed file, keeping a sidecar's language tag, e.g. `rus.mka`
+pub fn tagged_extension(path: &Path, ext: &str) -> String {
+    match parse_language_tag(path) {
+        Some(tag) => format!("{}.{}", tag, ext),
+        None => ext.to_string(),
+    }
+}
+
 pub fn parse_extension(path: &Path) -> Option<String> {
     if path.is_dir() {
         return None;
@@ -85,10 +443,12 @@ pub fn parse_extension(path: &Path) -> Option<String> {
 
     let ext = path.extension()?.to_str()?.to_lowercase();
 
-    let allowed_formats = ["mp4", "mkv", "avi", "mov", "flv", "wmv", "webm", "srt"]
-        .into_iter()
-        .map(|ext| ext.to_string())
-        .collect::<HashSet<_>>();
+    let allowed_formats = [
+        "mp4", "mkv", "avi", "mov", "flv", "wmv", "webm", "srt", "sub", "idx", "mka", "iso",
+    ]
+    .into_iter()
+    .map(|ext| ext.to_string())
+    .collect::<HashSet<_>>();
     if !allowed_formats.contains(&ext) {
         return None;
     }
@@ -96,7 +456,8 @@ pub fn parse_extension(path: &Path) -> Option<String> {
     Some(ext)
 }
 
-pub fn parse_episode_id(path: &Path) -> Result<String> {
+/// Extract the season and episode numbers from a path
+pub fn parse_episode(path: &Path) -> Result<(i32, i32)> {
     let path_str = path.to_string_lossy();
 
     let season_regex = Regex::new(r"[Ss](?:eason)?[._\-\s]*(\d+)")?;
@@ -114,17 +475,45 @@ pub fn parse_episode_id(path: &Path) -> Result<String> {
         .get(1)
         .context("Failed to extract episode number")?;
 
-    Ok(format!(
-        "S{:02}E{:02}",
+    Ok((
         season_match.as_str().parse::<i32>()?,
-        episode_match.as_str().parse::<i32>()?
+        episode_match.as_str().parse::<i32>()?,
     ))
 }
 
+pub fn parse_episode_id(path: &Path) -> Result<String> {
+    let (season, episode) = parse_episode(path)?;
+    Ok(episode_id(season, episode))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sidecar_video() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        for file in [
+            "Heat.1995.1080p.mkv",
+            "Heat.1995.2160p.mkv",
+            "Heat.1995.1080p.en.srt",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        assert_eq!(
+            sidecar_video(&dir.join("Heat.1995.1080p.en.srt")),
+            Some(dir.join("Heat.1995.1080p.mkv"))
+        );
+        assert_eq!(sidecar_video(&dir.join("English.srt")), None);
+
+        fs::remove_file(dir.join("Heat.1995.2160p.mkv")).unwrap();
+        assert_eq!(
+            sidecar_video(&dir.join("English.srt")),
+            Some(dir.join("Heat.1995.1080p.mkv"))
+        );
+    }
+
     #[test]
     fn test_episode_id() {
         assert_eq!(episode_id(1, 1), "S01E01");
@@ -132,6 +521,54 @@ mod tests {
         assert_eq!(episode_id(10, 99), "S10E99");
     }
 
+    #[test]
+    fn test_parse_extra_from_folder() {
+        assert_eq!(
+            parse_extra(Path::new("Extras/Making Of.mkv")),
+            Some(ExtraKind::Other)
+        );
+        assert_eq!(
+            parse_extra(Path::new("Movie/Featurettes/Visual Effects.mkv")),
+            Some(ExtraKind::Featurettes)
+        );
+        assert_eq!(
+            parse_extra(Path::new("Behind.The.Scenes/Clip.mp4")),
+            Some(ExtraKind::BehindTheScenes)
+        );
+    }
+
+    #[test]
+    fn test_parse_extra_from_suffix() {
+        assert_eq!(
+            parse_extra(Path::new("Making Of-behindthescenes.mkv")),
+            Some(ExtraKind::BehindTheScenes)
+        );
+        assert_eq!(
+            parse_extra(Path::new("Alternate Ending-deleted.mkv")),
+            Some(ExtraKind::DeletedScenes)
+        );
+    }
+
+    #[test]
+    fn test_parse_extra_trailers() {
+        assert_eq!(
+            parse_extra(Path::new("Movie.Name.1999-trailer.mp4")),
+            Some(ExtraKind::Trailers)
+        );
+        assert_eq!(
+            parse_extra(Path::new("Trailers/Teaser.mp4")),
+            Some(ExtraKind::Trailers)
+        );
+        assert_eq!(parse_extra(Path::new("trailer.mp4")), None);
+    }
+
+    #[test]
+    fn test_parse_extra_none() {
+        assert_eq!(parse_extra(Path::new("Movie.Name.1999.mkv")), None);
+        assert_eq!(parse_extra(Path::new("s01/Show.S01E01.mkv")), None);
+        assert_eq!(parse_extra(Path::new("Spider-Man.2002.mkv")), None);
+    }
+
     #[test]
     fn test_parse_title_simple() {
         assert_eq!(
@@ -172,6 +609,139 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_edition() {
+        assert_eq!(
+            parse_edition(Path::new("Movie.Title.1999.Directors.Cut.1080p.mkv")),
+            Some("Director's Cut")
+        );
+        assert_eq!(
+            parse_edition(Path::new("Movie Title (1999) Director's Cut.mkv")),
+            Some("Director's Cut")
+        );
+        assert_eq!(
+            parse_edition(Path::new("Movie_Title_EXTENDED_EDITION.mkv")),
+            Some("Extended")
+        );
+        assert_eq!(
+            parse_edition(Path::new("Movie.Title.1999.Unrated.mkv")),
+            Some("Unrated")
+        );
+    }
+
+    #[test]
+    fn test_is_sample() {
+        assert!(is_sample(Path::new("Sample/movie.mkv")));
+        assert!(is_sample(Path::new("movie-sample.mkv")));
+        assert!(is_sample(Path::new("Movie.2019.1080p.SAMPLE.mkv")));
+        assert!(!is_sample(Path::new("Sampler.mkv")));
+        assert!(!is_sample(Path::new("Free.Samples.S01E01.mkv")));
+    }
+
+    #[test]
+    fn test_parse_external_id() {
+        assert_eq!(
+            parse_external_id(Path::new("Fight Club (1999) {tmdb-550}/Fight.Club.mkv")),
+            Some(ExternalId::Tmdb(550))
+        );
+        assert_eq!(
+            parse_external_id(Path::new("Breaking Bad [tmdbid-1396]/S01E01.mkv")),
+            Some(ExternalId::Tmdb(1396))
+        );
+        assert_eq!(
+            parse_external_id(Path::new("Fight.Club.1999.[imdbid-tt0137523].mkv")),
+            Some(ExternalId::Imdb("tt0137523".to_string()))
+        );
+        assert_eq!(
+            parse_external_id(Path::new("Movie {tmdb-1}/Movie {tmdb-2}.mkv")),
+            Some(ExternalId::Tmdb(2))
+        );
+        assert_eq!(parse_external_id(Path::new("Movie {imdb-123}.mkv")), None);
+        assert_eq!(parse_external_id(Path::new("Fight.Club.1999.mkv")), None);
+    }
+
+    #[test]
+    fn test_parse_edition_none() {
+        assert_eq!(parse_edition(Path::new("Movie.Title.1999.1080p.mkv")), None);
+        assert_eq!(parse_edition(Path::new("The.Extendables.2020.mkv")), None);
+    }
+
+    #[test]
+    fn test_parse_part() {
+        assert_eq!(
+            parse_part(Path::new("Movie.Title.1999.CD1.mkv")).as_deref(),
+            Some("cd1")
+        );
+        assert_eq!(
+            parse_part(Path::new("Movie Title (1999) part2.avi")).as_deref(),
+            Some("part2")
+        );
+        assert_eq!(
+            parse_part(Path::new("Movie_Title_Disc_01.mkv")).as_deref(),
+            Some("disc1")
+        );
+        assert_eq!(
+            parse_part(Path::new("Movie.Title-pt.2.mkv")).as_deref(),
+            Some("pt2")
+        );
+    }
+
+    #[test]
+    fn test_parse_part_none() {
+        assert_eq!(parse_part(Path::new("Movie.Title.1999.1080p.mkv")), None);
+        assert_eq!(parse_part(Path::new("Apartment.2.mkv")), None);
+    }
+
+    #[test]
+    fn test_folder_title_and_year() {
+        assert_eq!(title_from("Mr. Robot (2015)").as_deref(), Some("Mr Robot"));
+        assert_eq!(year_from("Mr. Robot (2015)"), Some(2015));
+    }
+
+    #[test]
+    fn test_parse_resolution() {
+        let cases = [
+            (
+                "Movie.2019.2160p.UHD.BluRay.x265.mkv",
+                Some(Resolution::Uhd),
+            ),
+            ("Movie (2019) [4K].mkv", Some(Resolution::Uhd)),
+            ("Show.S01E01.1080p.WEB-DL.mkv", Some(Resolution::Hd1080)),
+            ("Show.S01E01.720p.HDTV.mkv", Some(Resolution::Hd720)),
+            ("Show.S01E01.480p.mkv", Some(Resolution::Sd)),
+            ("Movie.2019.mkv", None),
+            ("Movie.4Kids.2019.mkv", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(parse_resolution(Path::new(name)), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_resolution_from_dimensions() {
+        assert_eq!(Resolution::from_dimensions(3840, 1600), Resolution::Uhd);
+        assert_eq!(Resolution::from_dimensions(1920, 800), Resolution::Hd1080);
+        assert_eq!(Resolution::from_dimensions(1280, 720), Resolution::Hd720);
+        assert_eq!(Resolution::from_dimensions(720, 576), Resolution::Sd);
+    }
+
+    #[test]
+    fn test_parse_year() {
+        assert_eq!(
+            parse_year(Path::new("Movie.Title.1999.1080p.mkv")),
+            Some(1999)
+        );
+        assert_eq!(
+            parse_year(Path::new("The Office (2005) S01E01.mkv")),
+            Some(2005)
+        );
+        assert_eq!(
+            parse_year(Path::new("Blade.Runner.2049.2017.mkv")),
+            Some(2017)
+        );
+        assert_eq!(parse_year(Path::new("Movie.Title.1080p.mkv")), None);
+    }
+
     #[test]
     fn test_parse_content_type() {
         assert_eq!(
@@ -232,6 +802,50 @@ mod tests {
         assert_eq!(parse_extension(Path::new("archive.zip")), None);
     }
 
+    #[test]
+    fn test_is_subtitle() {
+        assert!(is_subtitle(Path::new("Heat (1995).en.srt")));
+        assert!(is_subtitle(Path::new("Heat (1995).IDX")));
+        assert!(is_subtitle(Path::new("Heat (1995).sub")));
+        assert!(!is_subtitle(Path::new("Heat (1995).mkv")));
+    }
+
+    #[test]
+    fn test_language_tag() {
+        assert_eq!(
+            tagged_extension(Path::new("Show.S01E01.rus.mka"), "mka"),
+            "rus.mka"
+        );
+        assert_eq!(
+            tagged_extension(Path::new("Movie.1999.EN.srt"), "srt"),
+            "en.srt"
+        );
+        assert_eq!(tagged_extension(Path::new("Show.S01E01.mka"), "mka"), "mka");
+        assert_eq!(
+            tagged_extension(Path::new("Show.S01E01.WEB.mka"), "mka"),
+            "mka"
+        );
+        assert_eq!(
+            tagged_extension(Path::new("Movie.1999.en.mkv"), "mkv"),
+            "mkv"
+        );
+        assert!(is_sidecar(Path::new("Show.S01E01.rus.mka")));
+    }
+
+    #[test]
+    fn test_disc_path() {
+        assert_eq!(
+            disc_path(Path::new("Heat.1995.BluRay/BDMV/STREAM/00000.m2ts")),
+            Some(PathBuf::from("BDMV/STREAM/00000.m2ts"))
+        );
+        assert_eq!(
+            disc_path(Path::new("video_ts/VTS_01_1.VOB")),
+            Some(PathBuf::from("video_ts/VTS_01_1.VOB"))
+        );
+        assert_eq!(disc_path(Path::new("Heat.1995/BDMV")), None);
+        assert_eq!(disc_path(Path::new("Heat.1995/Heat.1995.mkv")), None);
+    }
+
     #[test]
     fn test_parse_extension_with_directory() {
         assert_eq!(parse_extension(Path::new("some_directory/")), None);